@@ -4,11 +4,11 @@
  * SPDX-License-Identifier: MIT
  */
 
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, LazyLock, Mutex};
 use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
-use small_websocket::{Message, WebSocket};
+use small_websocket::{ConnectionId, Hub, Message};
 use tiny_webview::Webview;
 
 use crate::dmx::{DMX_STATE, Mode};
@@ -38,24 +38,14 @@ pub(crate) enum IpcMessage {
 }
 
 // MARK: IpcConnection
-pub(crate) static IPC_CONNECTIONS: Mutex<Vec<IpcConnection>> = Mutex::new(Vec::new());
+pub(crate) static IPC_HUB: LazyLock<Hub> = LazyLock::new(Hub::new);
+pub(crate) static IPC_WEBVIEW: Mutex<Option<Arc<Mutex<Webview>>>> = Mutex::new(None);
 
 pub(crate) enum IpcConnection {
     WebviewIpc(Arc<Mutex<Webview>>),
-    WebSocket(WebSocket),
+    WebSocket(ConnectionId),
 }
 
-impl PartialEq for IpcConnection {
-    fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (IpcConnection::WebviewIpc(a), IpcConnection::WebviewIpc(b)) => Arc::ptr_eq(a, b),
-            (IpcConnection::WebSocket(a), IpcConnection::WebSocket(b)) => a == b,
-            _ => false,
-        }
-    }
-}
-impl Eq for IpcConnection {}
-
 impl IpcConnection {
     pub(crate) fn send(&mut self, message: String) {
         println!("[RUST] Sending IPC message: {}", message);
@@ -64,29 +54,38 @@ impl IpcConnection {
                 .lock()
                 .expect("Can't lock webview")
                 .send_ipc_message(message),
-            IpcConnection::WebSocket(ws) => ws
-                .send(Message::Text(message))
-                .expect("Failed to send IPC message"),
+            IpcConnection::WebSocket(id) => {
+                if let Err(err) = IPC_HUB.send(*id, Message::Text(message)) {
+                    eprintln!("[RUST] Failed to send IPC message: {}", err);
+                }
+            }
         }
     }
 
     pub(crate) fn broadcast(&mut self, message: String) {
         println!("[RUST] Broadcasting IPC message: {}", message);
-        let mut connections = IPC_CONNECTIONS
-            .lock()
-            .expect("Failed to lock IPC connections");
-        for connection in connections.iter_mut() {
-            if connection != self {
-                connection.send(message.clone());
+        match self {
+            IpcConnection::WebviewIpc(_) => IPC_HUB.broadcast(Message::Text(message)),
+            IpcConnection::WebSocket(id) => {
+                if let Some(webview) = IPC_WEBVIEW
+                    .lock()
+                    .expect("Failed to lock IPC webview")
+                    .as_ref()
+                {
+                    webview
+                        .lock()
+                        .expect("Can't lock webview")
+                        .send_ipc_message(message.clone());
+                }
+                IPC_HUB.broadcast_except(*id, Message::Text(message));
             }
         }
     }
 }
 
 // MARK: IPC Message Handler
-pub(crate) fn ipc_message_handler(mut connection: IpcConnection, message: &str) {
+pub(crate) fn ipc_message_handler(mut connection: IpcConnection, message: IpcMessage) {
     let mut dmx_state = DMX_STATE.lock().expect("Failed to lock DMX state");
-    let message = serde_json::from_str(message).expect("Failed to parse IPC message");
     println!("[RUST] Received IPC message: {:?}", message);
     match message {
         IpcMessage::GetState => {
@@ -13,10 +13,10 @@ use std::thread;
 use std::time::Duration;
 
 use rust_embed::Embed;
-use small_websocket::Message;
+use small_websocket::{TypedError, TypedSocket};
 use tiny_webview::{Event, EventLoopBuilder, LogicalSize, WebviewBuilder};
 
-use crate::ipc::{IPC_CONNECTIONS, IpcConnection, ipc_message_handler};
+use crate::ipc::{IPC_HUB, IPC_WEBVIEW, IpcConnection, IpcMessage, ipc_message_handler};
 
 mod config;
 mod dmx;
@@ -42,37 +42,26 @@ fn main() {
             .load_rust_embed::<WebAssets>()
             .internal_http_serve_handle(|req| {
                 if req.url.path() == "/ipc" {
-                    return Some(small_websocket::upgrade(req, |mut ws| {
-                        IPC_CONNECTIONS
-                            .lock()
-                            .expect("Failed to lock IPC connections")
-                            .push(IpcConnection::WebSocket(ws.clone()));
+                    return Some(small_websocket::upgrade(req, |ws| {
+                        let id = IPC_HUB.join(ws.sender());
+                        ws.set_heartbeat(Duration::from_secs(30), Duration::from_secs(10));
+                        let mut socket = TypedSocket::<IpcMessage, IpcMessage>::new(ws);
                         loop {
-                            let message = match ws.recv_non_blocking() {
-                                Ok(message) => message,
-                                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
-                                    continue;
+                            match socket.recv() {
+                                Ok(message) => {
+                                    ipc_message_handler(IpcConnection::WebSocket(id), message)
                                 }
-                                Err(err) => panic!("WebSocket recv error: {}", err),
-                            };
-                            match message {
-                                Some(Message::Text(text)) => {
-                                    ipc_message_handler(
-                                        IpcConnection::WebSocket(ws.clone()),
-                                        &text,
-                                    );
+                                Err(TypedError::Json(err)) => {
+                                    eprintln!("[RUST] Invalid IPC message: {}", err);
                                 }
-                                Some(Message::Close(_, _)) => break,
-                                None => {
-                                    thread::sleep(Duration::from_millis(100));
+                                Err(TypedError::Closed(..)) => break,
+                                Err(err) => {
+                                    eprintln!("[RUST] WebSocket recv error: {}", err);
+                                    break;
                                 }
-                                _ => {}
                             }
                         }
-                        IPC_CONNECTIONS
-                            .lock()
-                            .expect("Failed to lock IPC connections")
-                            .retain(|conn| conn != &IpcConnection::WebSocket(ws.clone()));
+                        IPC_HUB.leave(id);
                     }));
                 }
                 None
@@ -83,10 +72,7 @@ fn main() {
     let config = config::load_config("config.json").expect("Can't load config.json");
     event_loop.run(move |event| match event {
         Event::PageLoadFinished => {
-            IPC_CONNECTIONS
-                .lock()
-                .expect("Failed to lock IPC connections")
-                .push(IpcConnection::WebviewIpc(webview.clone()));
+            *IPC_WEBVIEW.lock().expect("Failed to lock IPC webview") = Some(webview.clone());
 
             let config = config.clone();
             thread::spawn(move || {
@@ -97,9 +83,10 @@ fn main() {
                 }
             });
         }
-        Event::PageMessageReceived(message) => {
-            ipc_message_handler(IpcConnection::WebviewIpc(webview.clone()), &message)
-        }
+        Event::PageMessageReceived(message) => match serde_json::from_str(&message) {
+            Ok(message) => ipc_message_handler(IpcConnection::WebviewIpc(webview.clone()), message),
+            Err(err) => eprintln!("[RUST] Invalid IPC message: {}", err),
+        },
 
         _ => {}
     });
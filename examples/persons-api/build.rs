@@ -7,6 +7,9 @@
 //! A simple persons REST API example
 
 fn main() {
+    // Recheck the queries when a migration is added
+    println!("cargo::rerun-if-changed=migrations");
+
     openapi_generator::generate_schemas_build(
         "openapi.yaml",
         format!(
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS persons(
+    id BLOB PRIMARY KEY,
+    name TEXT NOT NULL,
+    age INTEGER NOT NULL,
+    relation INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+) STRICT;
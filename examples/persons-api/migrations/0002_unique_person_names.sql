@@ -0,0 +1 @@
+CREATE UNIQUE INDEX persons_name ON persons (name);
@@ -0,0 +1,13 @@
+CREATE TABLE persons_new(
+    id BLOB NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    age INTEGER NOT NULL,
+    relation INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    seq INTEGER PRIMARY KEY
+) STRICT;
+INSERT INTO persons_new (id, name, age, relation, created_at)
+    SELECT id, name, age, relation, created_at FROM persons ORDER BY created_at, rowid;
+DROP TABLE persons;
+ALTER TABLE persons_new RENAME TO persons;
+CREATE UNIQUE INDEX persons_name ON persons (name);
@@ -10,13 +10,15 @@
 
 use std::net::{Ipv4Addr, TcpListener};
 
-use bsqlite::{Connection, FromRow, FromValue, execute_args, query_args};
+use bsqlite::fts;
+use bsqlite::migrate::Migrator;
+use bsqlite::query::Select;
+use bsqlite::{Connection, ConstraintKind, FromRow, FromValue, Pool, query_checked};
 use chrono::{DateTime, Utc};
-use const_format::formatcp;
 use from_enum::FromEnum;
 use serde::Deserialize;
 use small_http::{Method, Request, Response, Status};
-use small_router::{Router, RouterBuilder};
+use small_router::{Error, IntoResponse, Json, RouteDoc, Router, RouterBuilder, Urls};
 use uuid::Uuid;
 use validate::Validate;
 
@@ -38,89 +40,109 @@ mod validators {
 // MARK: Context
 #[derive(Clone)]
 struct Context {
-    database: Connection,
+    pool: Pool,
+    urls: Urls,
 }
 
 impl Context {
     fn with_database(path: &str) -> Self {
-        let database = Connection::open(path).expect("Can't open database");
-        database.enable_wal_logging();
-        database.apply_various_performance_settings();
-        database_create_tables(&database);
+        let pool = Pool::open(path).expect("Can't open database");
+        let database = pool.writer().expect("Can't get database writer");
+        database_migrate(&database);
         database_seed(&database);
-        Self { database }
+        drop(database);
+        Self {
+            pool,
+            urls: Urls::new(),
+        }
     }
 
     #[cfg(test)]
     fn with_test_database() -> Self {
-        let database = Connection::open_memory().expect("Can't open database");
-        database_create_tables(&database);
-        Self { database }
+        let pool = Pool::open_memory().expect("Can't open database");
+        database_migrate(&pool.writer().expect("Can't get database writer"));
+        Self {
+            pool,
+            urls: Urls::new(),
+        }
     }
 }
 
 // MARK: Database
-trait DatabaseHelpers {
-    fn insert_person(&self, person: Person);
-}
-impl DatabaseHelpers for Connection {
-    fn insert_person(&self, person: Person) {
-        self.execute(
-            formatcp!(
-                "INSERT INTO persons ({}) VALUES ({})",
-                Person::columns(),
-                Person::values()
-            ),
-            person,
-        );
+fn database_error(err: bsqlite::Error) -> Error {
+    if err.is_constraint(ConstraintKind::Unique) {
+        Error::conflict("A person with this name already exists")
+    } else {
+        Error::from(err)
     }
 }
 
-fn database_create_tables(database: &Connection) {
-    database.execute(
-        "CREATE TABLE IF NOT EXISTS persons(
-            id BLOB PRIMARY KEY,
-            name TEXT NOT NULL,
-            age INTEGER NOT NULL,
-            relation INTEGER NOT NULL,
-            created_at INTEGER NOT NULL
-        ) STRICT",
-        (),
-    );
+fn database_migrate(database: &Connection) {
+    Migrator::new()
+        .sql(
+            "create_persons",
+            include_str!("../migrations/0001_create_persons.sql"),
+        )
+        .sql(
+            "unique_person_names",
+            include_str!("../migrations/0002_unique_person_names.sql"),
+        )
+        .sql(
+            "persons_integer_primary_key",
+            include_str!("../migrations/0003_persons_integer_primary_key.sql"),
+        )
+        .closure("create_persons_fts", |tx| Person::fts_table().create(tx))
+        .run(database)
+        .expect("Can't migrate database");
 }
 
 fn database_seed(database: &Connection) {
     // Insert persons
-    if database.query_some::<i64>("SELECT COUNT(id) FROM persons", ()) == 0 {
-        database.insert_person(Person {
+    let total = query_checked!(i64, database, "SELECT COUNT(id) FROM persons")
+        .next()
+        .expect("Should be some");
+    if total == 0 {
+        let tx = database.transaction().expect("Can't start transaction");
+        Person {
             name: "Bastiaan".to_string(),
             age_in_years: 20,
             relation: Relation::Me,
             ..Default::default()
-        });
-        database.insert_person(Person {
+        }
+        .insert(&tx)
+        .expect("Can't insert person");
+        Person {
             name: "Sander".to_string(),
             age_in_years: 19,
             relation: Relation::Brother,
             ..Default::default()
-        });
-        database.insert_person(Person {
+        }
+        .insert(&tx)
+        .expect("Can't insert person");
+        Person {
             name: "Leonard".to_string(),
             age_in_years: 16,
             relation: Relation::Brother,
             ..Default::default()
-        });
-        database.insert_person(Person {
+        }
+        .insert(&tx)
+        .expect("Can't insert person");
+        Person {
             name: "Jiska".to_string(),
             age_in_years: 14,
             relation: Relation::Sister,
             ..Default::default()
-        });
+        }
+        .insert(&tx)
+        .expect("Can't insert person");
+        tx.commit().expect("Can't commit transaction");
     }
 }
 
 // MARK: Layers
 mod layers {
+    use small_router::layers::Cors;
+
     use super::*;
 
     pub(crate) fn log_pre_layer(req: &Request, _: &mut Context) -> Option<Response> {
@@ -128,29 +150,22 @@ mod layers {
         None
     }
 
-    pub(crate) fn cors_pre_layer(req: &Request, _: &mut Context) -> Option<Response> {
-        if req.method == Method::Options {
-            Some(
-                Response::with_header("Access-Control-Allow-Origin", "*")
-                    .header("Access-Control-Allow-Methods", "GET, POST")
-                    .header("Access-Control-Max-Age", "86400"),
-            )
-        } else {
-            None
-        }
-    }
-
-    pub(crate) fn cors_post_layer(_: &Request, _: &mut Context, res: Response) -> Response {
-        res.header("Access-Control-Allow-Origin", "*")
-            .header("Access-Control-Allow-Methods", "GET, POST")
-            .header("Access-Control-Max-Age", "86400")
+    pub(crate) fn cors() -> Cors {
+        Cors::new().allow_methods(&[Method::Get, Method::Post])
     }
 }
 
 // MARK: Person
 #[derive(Clone, FromRow)]
+#[sqlite(
+    table = "persons",
+    primary_key = "id",
+    fts = "persons_fts",
+    fts_rowid = "seq"
+)]
 struct Person {
     id: Uuid,
+    #[sqlite(fts)]
     name: String,
     #[sqlite(rename = "age")]
     age_in_years: i64,
@@ -221,50 +236,48 @@ impl Default for IndexQuery {
     }
 }
 
-fn persons_index(req: &Request, ctx: &Context) -> Response {
+fn persons_index(req: &Request, ctx: &Context) -> Result<Json<api::PersonIndexResponse>, Error> {
     // Parse request query
     let query = match req.url.query() {
-        Some(query) => match serde_urlencoded::from_str::<IndexQuery>(query) {
-            Ok(query) => query,
-            Err(_) => return Response::with_status(Status::BadRequest),
-        },
+        Some(query) => serde_urlencoded::from_str::<IndexQuery>(query)
+            .map_err(|_| Error::bad_request("Invalid query"))?,
         None => IndexQuery::default(),
     };
-    if let Err(report) = query.validate() {
-        return Response::with_status(Status::BadRequest).json(report);
-    }
-
-    // Get persons
-    let search_query = format!("%{}%", query.query.replace("%", "\\%"));
-    let total = ctx.database.query_some::<i64>(
-        "SELECT COUNT(id) FROM persons WHERE name LIKE ?",
-        search_query.clone(),
-    );
-    let persons = query_args!(
-        Person,
-        ctx.database,
-        formatcp!(
-            "SELECT {} FROM persons WHERE name LIKE :search_query LIMIT :limit OFFSET :offset",
-            Person::columns()
-        ),
-        Args {
-            search_query: search_query,
-            limit: query.limit,
-            offset: (query.page - 1) * query.limit
-        }
-    )
-    .map(Into::<api::Person>::into)
-    .collect::<Vec<_>>();
+    query.validate()?;
+
+    // Get persons, search results are ranked by relevance
+    let database = ctx.pool.reader()?;
+    let search_query = fts::prefix_query(&query.query);
+    let (total, persons) = if search_query.is_empty() {
+        let page = Select::from("persons")
+            .columns(Person::columns())
+            .order_by("created_at")
+            .order_by("seq")
+            .page(query.page, query.limit)
+            .fetch_page::<Person>(&database)?;
+        (page.total, page.data)
+    } else {
+        let page = Person::fts_table().search_page::<Person>(
+            &database,
+            &search_query,
+            query.page,
+            query.limit,
+        )?;
+        (
+            page.total,
+            page.data.into_iter().map(|hit| hit.row).collect(),
+        )
+    };
 
     // Return persons
-    Response::with_json(api::PersonIndexResponse {
+    Ok(Json(api::PersonIndexResponse {
         pagination: api::Pagination {
             page: query.page,
             limit: query.limit,
             total,
         },
-        data: persons,
-    })
+        data: persons.into_iter().map(Into::into).collect(),
+    }))
 }
 
 #[derive(Validate)]
@@ -286,130 +299,160 @@ impl From<api::PersonCreateUpdateBody> for PersonCreateUpdateBody {
     }
 }
 
-fn persons_create(req: &Request, ctx: &Context) -> Response {
-    // Parse and validate body
-    let body = match serde_urlencoded::from_bytes::<api::PersonCreateUpdateBody>(
+fn parse_body(req: &Request) -> Result<PersonCreateUpdateBody, Error> {
+    let body = serde_urlencoded::from_bytes::<api::PersonCreateUpdateBody>(
         req.body.as_deref().unwrap_or(&[]),
-    ) {
-        Ok(body) => Into::<PersonCreateUpdateBody>::into(body),
-        Err(_) => return Response::with_status(Status::BadRequest),
-    };
-    if let Err(report) = body.validate() {
-        return Response::with_status(Status::BadRequest).json(report);
-    }
+    )
+    .map_err(|_| Error::bad_request("Invalid body"))?;
+    let body = Into::<PersonCreateUpdateBody>::into(body);
+    body.validate()?;
+    Ok(body)
+}
+
+fn persons_create(req: &Request, ctx: &Context) -> Result<Response, Error> {
+    // Parse and validate body
+    let body = parse_body(req)?;
 
     // Create person
+    let database = ctx.pool.writer()?;
     let person = Person {
         name: body.name,
         age_in_years: body.age_in_years,
         relation: body.relation,
         ..Default::default()
-    };
-    ctx.database.insert_person(person.clone());
-
-    // Return created person
-    Response::with_json(Into::<api::Person>::into(person))
+    }
+    .insert(&database)
+    .map_err(database_error)?;
+
+    // Return created person with its location
+    let location = ctx
+        .urls
+        .url_for("persons.show", &[("person_id", &person.id.to_string())])?;
+    Ok(Json(api::Person::from(person))
+        .into_response()
+        .status(Status::Created)
+        .header("Location", location))
 }
 
-fn get_person(req: &Request, ctx: &Context) -> Option<Person> {
+fn get_person(req: &Request, ctx: &Context) -> Result<Person, Error> {
     // Parse person id from url
-    let person_id = match req
+    let person_id = req
         .params
         .get("person_id")
         .expect("Should be some")
         .parse::<Uuid>()
-    {
-        Ok(id) => id,
-        Err(_) => return None,
-    };
+        .map_err(|_| Error::not_found())?;
 
     // Get person
-    ctx.database
-        .query::<Person>(
-            formatcp!(
-                "SELECT {} FROM persons WHERE id = ? LIMIT 1",
-                Person::columns()
-            ),
-            person_id,
-        )
-        .next()
+    let database = ctx.pool.reader()?;
+    Person::find_by_id(&database, person_id)?.ok_or_else(Error::not_found)
 }
 
-fn persons_show(req: &Request, ctx: &Context) -> Response {
+fn persons_show(req: &Request, ctx: &Context) -> Result<Json<api::Person>, Error> {
     // Get person
-    let person = match get_person(req, ctx) {
-        Some(person) => person,
-        None => return not_found(req, ctx),
-    };
+    let person = get_person(req, ctx)?;
 
     // Return person
-    Response::with_json(Into::<api::Person>::into(person))
+    Ok(Json(person.into()))
 }
 
-fn persons_update(req: &Request, ctx: &Context) -> Response {
+fn persons_update(req: &Request, ctx: &Context) -> Result<Json<api::Person>, Error> {
     // Get person
-    let mut person = match get_person(req, ctx) {
-        Some(person) => person,
-        None => return not_found(req, ctx),
-    };
+    let mut person = get_person(req, ctx)?;
 
     // Parse and validate body
-    let body = match serde_urlencoded::from_bytes::<api::PersonCreateUpdateBody>(
-        req.body.as_deref().unwrap_or(&[]),
-    ) {
-        Ok(body) => Into::<PersonCreateUpdateBody>::into(body),
-        Err(_) => return Response::with_status(Status::BadRequest),
-    };
-    if let Err(report) = body.validate() {
-        return Response::with_status(Status::BadRequest).json(report);
-    }
+    let body = parse_body(req)?;
 
     // Update person
     person.name = body.name;
     person.age_in_years = body.age_in_years;
     person.relation = body.relation;
-    execute_args!(
-        ctx.database,
-        "UPDATE persons SET name = :name, age = :age, relation = :relation WHERE id = :id",
-        Args {
-            id: person.id,
-            name: person.name.clone(),
-            age: person.age_in_years,
-            relation: person.relation
-        }
-    );
+    let database = ctx.pool.writer()?;
+    person.update(&database).map_err(database_error)?;
 
     // Return updated person
-    Response::with_json(Into::<api::Person>::into(person))
+    Ok(Json(person.into()))
 }
 
-fn persons_delete(req: &Request, ctx: &Context) -> Response {
+fn persons_delete(req: &Request, ctx: &Context) -> Result<(), Error> {
     // Get person
-    let person = match get_person(req, ctx) {
-        Some(person) => person,
-        None => return not_found(req, ctx),
-    };
+    let person = get_person(req, ctx)?;
 
     // Delete person
-    ctx.database
-        .execute("DELETE FROM persons WHERE id = ?", person.id);
+    let database = ctx.pool.writer()?;
+    person.delete(&database)?;
 
     // Success response
-    Response::new()
+    Ok(())
 }
 
 // MARK: Main
 fn router(ctx: Context) -> Router<Context> {
+    let urls = ctx.urls.clone();
     RouterBuilder::<Context>::with(ctx)
+        .urls(urls)
+        .layer(layers::cors())
         .pre_layer(layers::log_pre_layer)
-        .pre_layer(layers::cors_pre_layer)
-        .post_layer(layers::cors_post_layer)
+        .openapi_info("Persons API", env!("CARGO_PKG_VERSION"))
         .get("/", home)
+        .doc(RouteDoc::new().summary("Home route").tag("Misc"))
         .get("/persons", persons_index)
+        .doc(
+            RouteDoc::new()
+                .summary("Get all persons")
+                .tag("Persons")
+                .query_param("q", "string")
+                .query_param("page", "integer")
+                .query_param("limit", "integer")
+                .response(Status::Ok, "PersonIndexResponse")
+                .response(Status::BadRequest, "Report"),
+        )
         .post("/persons", persons_create)
-        .get("/persons/:person_id", persons_show)
+        .doc(
+            RouteDoc::new()
+                .summary("Create a new person")
+                .tag("Persons")
+                .request_body_with(
+                    "application/x-www-form-urlencoded",
+                    "PersonCreateUpdateBody",
+                )
+                .response(Status::Created, "Person")
+                .response(Status::BadRequest, "Report")
+                .empty_response(Status::Conflict),
+        )
+        .get_named("persons.show", "/persons/:person_id", persons_show)
+        .doc(
+            RouteDoc::new()
+                .summary("Get a person by ID")
+                .tag("Persons")
+                .path_param("person_id", "string")
+                .response(Status::Ok, "Person")
+                .empty_response(Status::NotFound),
+        )
         .put("/persons/:person_id", persons_update)
+        .doc(
+            RouteDoc::new()
+                .summary("Update a person by ID")
+                .tag("Persons")
+                .path_param("person_id", "string")
+                .request_body_with(
+                    "application/x-www-form-urlencoded",
+                    "PersonCreateUpdateBody",
+                )
+                .response(Status::Ok, "Person")
+                .response(Status::BadRequest, "Report")
+                .empty_response(Status::NotFound)
+                .empty_response(Status::Conflict),
+        )
         .delete("/persons/:person_id", persons_delete)
+        .doc(
+            RouteDoc::new()
+                .summary("Delete a person by ID")
+                .tag("Persons")
+                .path_param("person_id", "string")
+                .empty_response(Status::Ok)
+                .empty_response(Status::NotFound),
+        )
         .fallback(not_found)
         .build()
 }
@@ -438,6 +481,19 @@ mod test {
         assert!(res.body.starts_with(b"Persons v"));
     }
 
+    #[test]
+    fn test_database_seed() {
+        let ctx = Context::with_test_database();
+        let database = ctx.pool.writer().unwrap();
+        database_seed(&database);
+        database_seed(&database);
+        assert!(!database.in_transaction());
+        assert_eq!(
+            database.query_some::<i64>("SELECT COUNT(id) FROM persons", ()),
+            4
+        );
+    }
+
     #[test]
     fn test_cors() {
         let ctx = Context::with_test_database();
@@ -457,7 +513,9 @@ mod test {
         let ctx = Context::with_test_database();
         let router = router(ctx.clone());
 
-        let req = Request::options("http://localhost/");
+        let req = Request::options("http://localhost/")
+            .header("Origin", "http://example.com")
+            .header("Access-Control-Request-Method", "POST");
         let res = router.handle(&req);
         assert_eq!(
             res.headers
@@ -479,6 +537,41 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_openapi_spec_matches_routes() {
+        let ctx = Context::with_test_database();
+        let router = router(ctx);
+
+        let spec = serde_yaml::from_str::<serde_json::Value>(include_str!("../openapi.yaml"))
+            .expect("Can't parse openapi.yaml");
+        let operations = |doc: &serde_json::Value| {
+            let mut operations = doc["paths"]
+                .as_object()
+                .expect("Should be object")
+                .iter()
+                .flat_map(|(path, item)| {
+                    item.as_object()
+                        .expect("Should be object")
+                        .keys()
+                        .map(move |method| format!("{} {}", method, path))
+                })
+                .collect::<Vec<_>>();
+            operations.sort();
+            operations
+        };
+        let doc = router.openapi();
+        assert_eq!(operations(&doc), operations(&spec));
+
+        // Every referenced schema is defined in the spec
+        for name in doc["components"]["schemas"]
+            .as_object()
+            .expect("Should be object")
+            .keys()
+        {
+            assert!(spec["components"]["schemas"][name].is_object(), "{}", name);
+        }
+    }
+
     #[test]
     fn test_persons_index() {
         let ctx = Context::with_test_database();
@@ -499,7 +592,7 @@ mod test {
             relation: Relation::Me,
             ..Default::default()
         };
-        ctx.database.insert_person(person.clone());
+        person.insert(&ctx.pool.writer().unwrap()).unwrap();
 
         // Fetch /persons check if person is there
         let res = router.handle(&Request::get("http://localhost/persons"));
@@ -517,14 +610,18 @@ mod test {
         let router = router(ctx.clone());
 
         // Create multiple persons
-        ctx.database.insert_person(Person {
+        Person {
             name: "Alice".to_string(),
             ..Default::default()
-        });
-        ctx.database.insert_person(Person {
+        }
+        .insert(&ctx.pool.writer().unwrap())
+        .unwrap();
+        Person {
             name: "Bob".to_string(),
             ..Default::default()
-        });
+        }
+        .insert(&ctx.pool.writer().unwrap())
+        .unwrap();
 
         // Search for "Alice"
         let res = router.handle(&Request::get("http://localhost/persons?q=Alice"));
@@ -532,6 +629,13 @@ mod test {
         let response = serde_json::from_slice::<api::PersonIndexResponse>(&res.body).unwrap();
         assert_eq!(response.data.len(), 1);
         assert_eq!(response.data[0].name, "Alice");
+
+        // Search matches name prefixes
+        let res = router.handle(&Request::get("http://localhost/persons?q=bo"));
+        assert_eq!(res.status, Status::Ok);
+        let response = serde_json::from_slice::<api::PersonIndexResponse>(&res.body).unwrap();
+        assert_eq!(response.pagination.total, 1);
+        assert_eq!(response.data[0].name, "Bob");
     }
 
     #[test]
@@ -541,12 +645,14 @@ mod test {
 
         // Create multiple persons
         for i in 1..=30 {
-            ctx.database.insert_person(Person {
+            Person {
                 name: format!("Person {}", i),
                 age_in_years: 20 + i,
                 relation: Relation::Me,
                 ..Default::default()
-            });
+            }
+            .insert(&ctx.pool.writer().unwrap())
+            .unwrap();
         }
 
         // Fetch /persons with limit 10 and page 1
@@ -578,9 +684,19 @@ mod test {
         let res = router.handle(
             &Request::post("http://localhost/persons").body("name=Jan&ageInYears=40&relation=me"),
         );
-        assert_eq!(res.status, Status::Ok);
+        assert_eq!(res.status, Status::Created);
         let person = serde_json::from_slice::<api::Person>(&res.body).unwrap();
         assert_eq!(person.name, "Jan");
+        let location = res.headers.get("Location").unwrap();
+        assert_eq!(location, &format!("/persons/{}", person.id));
+        let res = router.handle(&Request::get(format!("http://localhost{}", location)));
+        assert_eq!(res.status, Status::Ok);
+
+        // Create person with same name should be 409 Conflict
+        let res = router.handle(
+            &Request::post("http://localhost/persons").body("name=Jan&ageInYears=41&relation=me"),
+        );
+        assert_eq!(res.status, Status::Conflict);
     }
 
     #[test]
@@ -595,7 +711,7 @@ mod test {
             relation: Relation::Me,
             ..Default::default()
         };
-        ctx.database.insert_person(person.clone());
+        person.insert(&ctx.pool.writer().unwrap()).unwrap();
 
         // Fetch /persons/:person_id check if person is there
         let res = router.handle(&Request::get(format!(
@@ -626,7 +742,7 @@ mod test {
             relation: Relation::Me,
             ..Default::default()
         };
-        ctx.database.insert_person(person.clone());
+        person.insert(&ctx.pool.writer().unwrap()).unwrap();
 
         // Update person
         let res = router.handle(
@@ -637,6 +753,19 @@ mod test {
         let person = serde_json::from_slice::<api::Person>(&res.body).unwrap();
         assert_eq!(person.age_in_years, 41);
 
+        // Update person to name of other person should be 409 Conflict
+        Person {
+            name: "Piet".to_string(),
+            ..Default::default()
+        }
+        .insert(&ctx.pool.writer().unwrap())
+        .unwrap();
+        let res = router.handle(
+            &Request::put(format!("http://localhost/persons/{}", person.id))
+                .body("name=Piet&ageInYears=41&relation=me"),
+        );
+        assert_eq!(res.status, Status::Conflict);
+
         // Update person with validation errors
         let res = router.handle(
             &Request::put(format!("http://localhost/persons/{}", person.id))
@@ -657,7 +786,7 @@ mod test {
             relation: Relation::Me,
             ..Default::default()
         };
-        ctx.database.insert_person(person.clone());
+        person.insert(&ctx.pool.writer().unwrap()).unwrap();
 
         // Delete person
         let res = router.handle(&Request::delete(format!(
@@ -60,7 +60,14 @@ fn main() {
 
     event_loop.run(move |event| {
         if let Event::PageMessageReceived(message) = event {
-            match serde_json::from_str(&message).expect("Can't parse message") {
+            let message = match serde_json::from_str(&message) {
+                Ok(message) => message,
+                Err(err) => {
+                    eprintln!("Invalid IPC message: {}", err);
+                    return;
+                }
+            };
+            match message {
                 IpcMessage::GetTodos => {
                     let todos: Vec<Todo> = fs::read_to_string(&todos_config_path)
                         .ok()
@@ -0,0 +1,55 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+//! A example that streams a blob and makes an online backup of the database.
+
+use std::io::{Read, Write};
+
+use bsqlite::Connection;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Connect and create table
+    let db = Connection::open_memory().expect("Can't open database");
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS attachments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            data BLOB NOT NULL
+        ) STRICT",
+        (),
+    );
+
+    // Reserve space for the attachment and stream the data in chunks
+    let chunk = [b'x'; 1024];
+    db.execute(
+        "INSERT INTO attachments (name, data) VALUES (?, zeroblob(?))",
+        ("large.txt".to_string(), 64 * chunk.len() as i64),
+    );
+    let mut blob = db.blob_open("attachments", "data", db.last_insert_row_id(), false)?;
+    for _ in 0..64 {
+        blob.write_all(&chunk)?;
+    }
+    drop(blob);
+
+    // Read the attachment back
+    let mut blob = db.blob_open("attachments", "data", db.last_insert_row_id(), true)?;
+    let mut data = Vec::new();
+    blob.read_to_end(&mut data)?;
+    println!("Attachment size: {} bytes", data.len()); // -> Attachment size: 65536 bytes
+    drop(blob);
+
+    // Backup the database while it is in use
+    let path = std::env::temp_dir().join("bsqlite_backup_example.db");
+    _ = std::fs::remove_file(&path);
+    db.backup_to(&path, |progress| {
+        println!(
+            "Backup: {} of {} pages left",
+            progress.remaining, progress.page_count
+        );
+    })?;
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
@@ -0,0 +1,56 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+//! A example that receives committed row changes on another thread.
+
+use std::thread;
+
+use bsqlite::Connection;
+
+fn main() -> Result<(), bsqlite::Error> {
+    // Connect and create table
+    let db = Connection::open_memory().expect("Can't open database");
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS persons (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            age INTEGER NOT NULL
+        ) STRICT",
+        (),
+    );
+
+    // Log every row change and broadcast committed changes to another thread
+    db.on_update(|operation, table, rowid| {
+        println!("Changed: {:?} {} {}", operation, table, rowid);
+    });
+    let changes = db.change_feed();
+    let handle = thread::spawn(move || {
+        for change in changes {
+            println!("Committed: {:?}", change);
+        }
+    });
+
+    // Rolled back changes are not broadcasted
+    let tx = db.transaction()?;
+    tx.try_execute(
+        "INSERT INTO persons (name, age) VALUES (?, ?)",
+        ("Alice".to_string(), 30),
+    )?;
+    tx.rollback()?;
+
+    let tx = db.transaction()?;
+    tx.try_execute(
+        "INSERT INTO persons (name, age) VALUES (?, ?)",
+        ("Bob".to_string(), 40),
+    )?;
+    tx.try_execute("UPDATE persons SET age = age + 1", ())?;
+    tx.commit()?;
+
+    // Close the feed so the thread stops
+    db.clear_hooks();
+    handle.join().expect("Can't join thread");
+    Ok(())
+}
@@ -0,0 +1,63 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+//! A example that registers a custom SQL function, aggregate and collation.
+
+use bsqlite::{Connection, Value};
+
+fn main() -> Result<(), bsqlite::Error> {
+    // Connect and create table
+    let db = Connection::open_memory().expect("Can't open database");
+    db.execute_batch(
+        "CREATE TABLE IF NOT EXISTS persons (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            age INTEGER NOT NULL
+        ) STRICT;
+        INSERT INTO persons (name, age) VALUES ('Émile', 30), ('bob', 40), ('Alice', 20);",
+    )?;
+
+    // Register a Unicode-aware lower function
+    db.create_scalar_function("unicode_lower", 1, true, |args| match &args[0] {
+        Value::Text(text) => Ok(Value::Text(text.to_lowercase())),
+        value => Ok(value.clone()),
+    })?;
+
+    // Register an aggregate that returns the oldest name
+    db.create_aggregate(
+        "oldest",
+        2,
+        || (String::new(), i64::MIN),
+        |(oldest, oldest_age), args| {
+            let age = i64::try_from(args[1].clone())?;
+            if age > *oldest_age {
+                *oldest = String::try_from(args[0].clone())?;
+                *oldest_age = age;
+            }
+            Ok(())
+        },
+        |(oldest, _)| Ok(oldest),
+    )?;
+
+    // Register a case insensitive collation
+    db.create_collation("unicode_nocase", |a, b| {
+        a.to_lowercase().cmp(&b.to_lowercase())
+    })?;
+
+    // Use them in queries
+    let names = db.query::<String>(
+        "SELECT unicode_lower(name) FROM persons ORDER BY name COLLATE unicode_nocase",
+        (),
+    );
+    for name in names {
+        println!("{}", name); // -> alice, bob, émile
+    }
+    println!(
+        "Oldest: {}",
+        db.query_some::<String>("SELECT oldest(name, age) FROM persons", ())
+    ); // -> Oldest: bob
+    Ok(())
+}
@@ -0,0 +1,61 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+//! A example that builds a search query with dynamic filters and pagination.
+
+use bsqlite::query::Select;
+use bsqlite::{Connection, FromRow};
+
+#[derive(Debug, FromRow)]
+struct Person {
+    id: i64,
+    name: String,
+    age: i64,
+}
+
+fn main() -> Result<(), bsqlite::Error> {
+    // Connect and create table
+    let db = Connection::open_memory().expect("Can't open database");
+    db.execute_batch(
+        "CREATE TABLE IF NOT EXISTS persons (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            age INTEGER NOT NULL
+        ) STRICT;
+        INSERT INTO persons (name, age) VALUES ('Alice', 30), ('Bob', 40), ('Charlie', 25), ('Alicia', 18);",
+    )?;
+
+    // Add filters depending on the search options
+    let search = Some("ali");
+    let min_age = Some(20);
+    let mut select = Select::from("persons").columns(Person::columns());
+    if let Some(search) = search {
+        select = select.filter_contains("name", search);
+    }
+    if let Some(min_age) = min_age {
+        select = select.filter("age >= ?", min_age);
+    }
+
+    // Read the first page with the total count
+    let page = select
+        .order_by("id")
+        .page(1, 10)
+        .fetch_page::<Person>(&db)?;
+    println!("Found {} persons", page.total);
+    for person in page.data {
+        println!("{:?}", person);
+    }
+
+    // Read the next persons after the last seen id with keyset pagination
+    let persons = Select::from("persons")
+        .columns(Person::columns())
+        .order_by("id")
+        .after(&["id"], 2)
+        .limit(10)
+        .fetch::<Person>(&db)?;
+    println!("{:?}", persons);
+    Ok(())
+}
@@ -0,0 +1,57 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+//! A example that uses the derive generated CRUD helpers of a [FromRow] struct.
+
+use bsqlite::{Connection, FromRow};
+
+#[derive(Debug, FromRow)]
+#[sqlite(table = "persons", primary_key = "id")]
+struct Person {
+    #[sqlite(default)]
+    id: i64,
+    name: String,
+    age: i64,
+}
+
+fn main() -> Result<(), bsqlite::Error> {
+    // Connect and create table
+    let db = Connection::open_memory().expect("Can't open database");
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS persons (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            age INTEGER NOT NULL
+        ) STRICT",
+        (),
+    );
+
+    // Insert rows, the id is filled by the database
+    let mut alice = Person {
+        id: 0,
+        name: "Alice".to_string(),
+        age: 30,
+    }
+    .insert(&db)?;
+    let bob = Person {
+        id: 0,
+        name: "Bob".to_string(),
+        age: 40,
+    }
+    .insert(&db)?;
+
+    // Update and delete rows
+    alice.age = 31;
+    alice.update(&db)?;
+    bob.delete(&db)?;
+
+    // Read rows back
+    println!("{:?}", Person::find_by_id(&db, alice.id)?); // -> Some(Person { id: 1, name: "Alice", age: 31 })
+    for person in Person::all(&db)? {
+        println!("{:?}", person);
+    }
+    Ok(())
+}
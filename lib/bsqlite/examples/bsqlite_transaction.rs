@@ -0,0 +1,52 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+//! A example that inserts rows in a transaction with a nested savepoint.
+
+use bsqlite::Connection;
+
+fn main() -> Result<(), bsqlite::Error> {
+    // Connect and create table
+    let db = Connection::open_memory().expect("Can't open database");
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS persons (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            age INTEGER NOT NULL
+        ) STRICT",
+        (),
+    );
+
+    // Insert rows in a transaction
+    let mut tx = db.transaction()?;
+    tx.try_execute(
+        "INSERT INTO persons (name, age) VALUES (?, ?)",
+        ("Alice".to_string(), 30),
+    )?;
+    {
+        // Savepoints roll back when they are dropped without commit
+        let sp = tx.savepoint()?;
+        sp.try_execute(
+            "INSERT INTO persons (name, age) VALUES (?, ?)",
+            ("Bob".to_string(), 40),
+        )?;
+    }
+    tx.commit()?;
+
+    // Or use a closure that is retried when the database is busy
+    db.with_transaction(|tx| {
+        tx.try_execute(
+            "INSERT INTO persons (name, age) VALUES (?, ?)",
+            ("Charlie".to_string(), 50),
+        )
+    })?;
+
+    // Read rows back
+    for name in db.query::<String>("SELECT name FROM persons", ()) {
+        println!("{}", name); // -> Alice, Charlie
+    }
+    Ok(())
+}
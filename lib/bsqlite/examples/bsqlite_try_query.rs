@@ -0,0 +1,52 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+//! A example that handles query errors with the fallible API.
+
+use bsqlite::{Connection, ConstraintKind};
+
+fn main() -> Result<(), bsqlite::Error> {
+    // Connect and create table
+    let db = Connection::open_memory().expect("Can't open database");
+    db.try_execute(
+        "CREATE TABLE IF NOT EXISTS persons (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            age INTEGER NOT NULL
+        ) STRICT",
+        (),
+    )?;
+
+    // Insert a row twice
+    for _ in 0..2 {
+        match db.try_execute(
+            "INSERT INTO persons (name, age) VALUES (?, ?)",
+            ("Alice".to_string(), 30),
+        ) {
+            Ok(()) => println!("Inserted Alice"),
+            Err(err) if err.is_constraint(ConstraintKind::Unique) => {
+                println!("Alice already exists: {}", err.message())
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    // Read optional rows
+    let age = db.query_optional::<i64>(
+        "SELECT age FROM persons WHERE name = ?",
+        "Alice".to_string(),
+    )?;
+    println!("Alice age: {:?}", age); // -> Some(30)
+    let age =
+        db.query_optional::<i64>("SELECT age FROM persons WHERE name = ?", "Bob".to_string())?;
+    println!("Bob age: {:?}", age); // -> None
+
+    // Invalid queries return an error instead of panicking
+    if let Err(err) = db.try_query::<i64>("SELECT age FROM unknown_table", ()) {
+        println!("Error: {}", err);
+    }
+    Ok(())
+}
@@ -0,0 +1,6 @@
+-- The schema that the query_checked! tests are checked against
+CREATE TABLE persons (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    age INTEGER NOT NULL
+) STRICT;
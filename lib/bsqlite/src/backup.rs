@@ -0,0 +1,168 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::ffi::{c_char, c_int};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use libsqlite3_sys::*;
+
+use crate::{Connection, Error};
+
+/// Name of the main database of a connection
+pub(crate) const MAIN_DATABASE: &[u8] = b"main\0";
+
+/// Amount of pages that are copied per backup step
+const BACKUP_STEP_PAGES: c_int = 100;
+
+/// Sleep time when the source database is busy during a backup step
+const BACKUP_BUSY_SLEEP: Duration = Duration::from_millis(10);
+
+// MARK: BackupProgress
+/// The progress of a running backup
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BackupProgress {
+    /// Amount of pages that still need to be copied
+    pub remaining: i32,
+    /// Total amount of pages in the source database
+    pub page_count: i32,
+}
+
+// MARK: Connection
+impl Connection {
+    /// Copy the database to a file with the online backup API while it is in use,
+    /// the progress function is called after every step
+    ///
+    /// ```rs
+    /// db.backup_to("backup.db", |progress| {
+    ///     println!("{} of {} pages left", progress.remaining, progress.page_count);
+    /// })?;
+    /// ```
+    pub fn backup_to(
+        &self,
+        path: impl AsRef<Path>,
+        mut progress: impl FnMut(BackupProgress),
+    ) -> Result<(), Error> {
+        let destination =
+            Connection::open(path).map_err(|err| Error::new(SQLITE_CANTOPEN, err.to_string()))?;
+        let backup = unsafe {
+            sqlite3_backup_init(
+                destination.raw(),
+                MAIN_DATABASE.as_ptr() as *const c_char,
+                self.raw(),
+                MAIN_DATABASE.as_ptr() as *const c_char,
+            )
+        };
+        if backup.is_null() {
+            return Err(Error::from_db(destination.raw()));
+        }
+
+        loop {
+            let result = unsafe { sqlite3_backup_step(backup, BACKUP_STEP_PAGES) };
+            progress(BackupProgress {
+                remaining: unsafe { sqlite3_backup_remaining(backup) },
+                page_count: unsafe { sqlite3_backup_pagecount(backup) },
+            });
+            match result {
+                SQLITE_DONE => break,
+                SQLITE_OK => {}
+                SQLITE_BUSY | SQLITE_LOCKED => thread::sleep(BACKUP_BUSY_SLEEP),
+                _ => break,
+            }
+        }
+
+        // Finish returns the error of the last failed step
+        let result = unsafe { sqlite3_backup_finish(backup) };
+        if result != SQLITE_OK {
+            return Err(Error::from_code(destination.raw(), result));
+        }
+        Ok(())
+    }
+
+    /// Write a vacuumed copy of the database to a new file with `VACUUM INTO`
+    pub fn vacuum_into(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref().to_str().ok_or_else(|| {
+            Error::new(SQLITE_MISUSE, "Path is not valid UTF-8").with_sql("VACUUM INTO ?")
+        })?;
+        self.try_execute("VACUUM INTO ?", path.to_string())
+    }
+}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "bsqlite_backup_{}_{}.db",
+                name,
+                std::process::id()
+            ));
+            _ = std::fs::remove_file(&path);
+            Self(path)
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn test_database() -> Connection {
+        let db = Connection::open_memory().unwrap();
+        db.execute_batch(
+            "CREATE TABLE persons (name TEXT NOT NULL, bio BLOB NOT NULL) STRICT;
+            WITH RECURSIVE numbers(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM numbers WHERE n < 500)
+            INSERT INTO persons (name, bio) SELECT 'Person ' || n, randomblob(1024) FROM numbers;",
+        )
+        .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_backup_to() {
+        let db = test_database();
+        let path = TempPath::new("backup_to");
+
+        let mut steps = Vec::new();
+        db.backup_to(&path.0, |progress| steps.push(progress))
+            .unwrap();
+        assert!(steps.len() > 1);
+        assert_eq!(steps.last().unwrap().remaining, 0);
+        assert!(steps[0].page_count > BACKUP_STEP_PAGES);
+
+        let backup = Connection::open(&path.0).unwrap();
+        assert_eq!(
+            backup.query_some::<i64>("SELECT COUNT(name) FROM persons", ()),
+            500
+        );
+    }
+
+    #[test]
+    fn test_vacuum_into() {
+        let db = test_database();
+        db.execute("DELETE FROM persons WHERE rowid > 10", ());
+        let path = TempPath::new("vacuum_into");
+        db.vacuum_into(&path.0).unwrap();
+
+        let copy = Connection::open(&path.0).unwrap();
+        assert_eq!(
+            copy.query_some::<i64>("SELECT COUNT(name) FROM persons", ()),
+            10
+        );
+
+        // The destination file must not exist yet
+        assert!(db.vacuum_into(&path.0).is_err());
+    }
+}
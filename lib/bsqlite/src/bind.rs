@@ -4,21 +4,109 @@
  * SPDX-License-Identifier: MIT
  */
 
-use crate::{RawStatement, Value};
+use libsqlite3_sys::SQLITE_MISMATCH;
 
+use crate::error::Result;
+use crate::{Error, RawStatement, Value, ValueRef};
+
+// MARK: BindValue
+/// A trait for binding a single value to a statement
+pub trait BindValue {
+    /// Bind value to the statement parameter at index
+    fn bind_value(self, statement: &mut RawStatement, index: i32) -> Result<()>;
+
+    /// Convert into an owned value
+    fn into_value(self) -> Result<Value>;
+}
+
+impl<T: Into<Value>> BindValue for T {
+    fn bind_value(self, statement: &mut RawStatement, index: i32) -> Result<()> {
+        statement.try_bind_value(index, self.into())
+    }
+
+    fn into_value(self) -> Result<Value> {
+        Ok(self.into())
+    }
+}
+
+impl BindValue for u64 {
+    fn bind_value(self, statement: &mut RawStatement, index: i32) -> Result<()> {
+        let value = self
+            .into_value()
+            .map_err(|err| err.with_sql(statement.sql()))?;
+        statement.try_bind_value(index, value)
+    }
+
+    fn into_value(self) -> Result<Value> {
+        Value::try_from(self)
+            .map_err(|err| Error::new(SQLITE_MISMATCH, format!("Can't bind value: {}", err)))
+    }
+}
+
+impl BindValue for Option<u64> {
+    fn bind_value(self, statement: &mut RawStatement, index: i32) -> Result<()> {
+        match self {
+            Some(value) => value.bind_value(statement, index),
+            None => statement.try_bind_value(index, Value::Null),
+        }
+    }
+
+    fn into_value(self) -> Result<Value> {
+        self.map_or(Ok(Value::Null), BindValue::into_value)
+    }
+}
+
+macro_rules! impl_bind_value_for_borrowed {
+    ($($t:ty),*) => ($(
+        impl<'a> BindValue for $t {
+            fn bind_value(self, statement: &mut RawStatement, index: i32) -> Result<()> {
+                statement.try_bind_value_ref(index, ValueRef::from(self))
+            }
+
+            fn into_value(self) -> Result<Value> {
+                Ok(ValueRef::from(self).to_value())
+            }
+        }
+    )*);
+}
+impl_bind_value_for_borrowed!(
+    ValueRef<'a>,
+    &'a Value,
+    &'a str,
+    &'a String,
+    &'a [u8],
+    &'a Vec<u8>,
+    Option<&'a str>,
+    Option<&'a String>,
+    Option<&'a [u8]>,
+    Option<&'a Vec<u8>>
+);
+
+// MARK: Bind
 /// A trait for binding values to a statement
 pub trait Bind {
     /// Bind values to a statement
-    fn bind(self, statement: &mut RawStatement);
+    fn bind(self, statement: &mut RawStatement) -> Result<()>;
 }
 
 impl Bind for () {
-    fn bind(self, _statement: &mut RawStatement) {}
+    fn bind(self, _statement: &mut RawStatement) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Bind for Vec<Value> {
+    fn bind(self, statement: &mut RawStatement) -> Result<()> {
+        for (index, value) in self.into_iter().enumerate() {
+            statement.try_bind_value(index as i32, value)?;
+        }
+        Ok(())
+    }
 }
 
-impl<T: Into<Value>> Bind for T {
-    fn bind(self, statement: &mut RawStatement) {
-        statement.bind_value(0, self.into());
+impl<T: BindValue> Bind for T {
+    fn bind(self, statement: &mut RawStatement) -> Result<()> {
+        self.bind_value(statement, 0)
     }
 }
 
@@ -26,10 +114,11 @@ macro_rules! impl_bind_for_tuple {
     ($($n:tt: $t:ident),*) => (
         impl<$($t,)*> Bind for ($($t,)*)
         where
-            $($t: Into<Value>,)+
+            $($t: BindValue,)+
         {
-            fn bind(self, statement: &mut RawStatement)  {
-                $( statement.bind_value($n, self.$n.into()); )*
+            fn bind(self, statement: &mut RawStatement) -> Result<()> {
+                $( self.$n.bind_value(statement, $n)?; )*
+                Ok(())
             }
         }
     );
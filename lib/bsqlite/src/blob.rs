@@ -0,0 +1,235 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::ffi::{c_char, c_int, c_void, CString};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ptr;
+
+use libsqlite3_sys::*;
+
+use crate::backup::MAIN_DATABASE;
+use crate::{Connection, Error};
+
+// MARK: Blob
+/// A handle for incremental I/O on a single blob value
+///
+/// A blob can't grow, reserve its size first with `zeroblob(size)`. The handle
+/// expires when the row is changed by a query, further reads and writes then fail.
+pub struct Blob {
+    connection: Connection,
+    blob: *mut sqlite3_blob,
+    position: usize,
+}
+
+impl Blob {
+    /// Get the size of the blob in bytes
+    pub fn len(&self) -> usize {
+        unsafe { sqlite3_blob_bytes(self.blob) as usize }
+    }
+
+    /// Check if the blob is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Move the handle to the blob of another row in the same table and column
+    pub fn reopen(&mut self, rowid: i64) -> Result<(), Error> {
+        let result = unsafe { sqlite3_blob_reopen(self.blob, rowid) };
+        if result != SQLITE_OK {
+            return Err(Error::from_code(self.connection.raw(), result));
+        }
+        self.position = 0;
+        Ok(())
+    }
+
+    fn io_error(&self, code: i32) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Other,
+            Error::from_code(self.connection.raw(), code),
+        )
+    }
+}
+
+impl Read for Blob {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.len().saturating_sub(self.position));
+        if len == 0 {
+            return Ok(0);
+        }
+        let result = unsafe {
+            sqlite3_blob_read(
+                self.blob,
+                buf.as_mut_ptr() as *mut c_void,
+                len as c_int,
+                self.position as c_int,
+            )
+        };
+        if result != SQLITE_OK {
+            return Err(self.io_error(result));
+        }
+        self.position += len;
+        Ok(len)
+    }
+}
+
+impl Write for Blob {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Writes past the end are cut off because a blob can't grow
+        let len = buf.len().min(self.len().saturating_sub(self.position));
+        if len == 0 {
+            return Ok(0);
+        }
+        let result = unsafe {
+            sqlite3_blob_write(
+                self.blob,
+                buf.as_ptr() as *const c_void,
+                len as c_int,
+                self.position as c_int,
+            )
+        };
+        if result != SQLITE_OK {
+            return Err(self.io_error(result));
+        }
+        self.position += len;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for Blob {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => offset as i64,
+            SeekFrom::End(offset) => self.len() as i64 + offset,
+            SeekFrom::Current(offset) => self.position as i64 + offset,
+        };
+        if position < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Can't seek before the start of the blob",
+            ));
+        }
+        self.position = position as usize;
+        Ok(self.position as u64)
+    }
+}
+
+impl Drop for Blob {
+    fn drop(&mut self) {
+        unsafe { sqlite3_blob_close(self.blob) };
+    }
+}
+
+// MARK: Connection
+impl Connection {
+    /// Open a blob value in a row for incremental I/O
+    ///
+    /// ```rs
+    /// db.execute("INSERT INTO files (data) VALUES (zeroblob(?))", file_size);
+    /// let mut blob = db.blob_open("files", "data", db.last_insert_row_id(), false)?;
+    /// std::io::copy(&mut file, &mut blob)?;
+    /// ```
+    pub fn blob_open(
+        &self,
+        table: &str,
+        column: &str,
+        rowid: i64,
+        read_only: bool,
+    ) -> Result<Blob, Error> {
+        let c_table = CString::new(table)
+            .map_err(|_| Error::new(SQLITE_MISUSE, "Table name contains a nul byte"))?;
+        let c_column = CString::new(column)
+            .map_err(|_| Error::new(SQLITE_MISUSE, "Column name contains a nul byte"))?;
+        let mut blob = ptr::null_mut();
+        let result = unsafe {
+            sqlite3_blob_open(
+                self.raw(),
+                MAIN_DATABASE.as_ptr() as *const c_char,
+                c_table.as_ptr(),
+                c_column.as_ptr(),
+                rowid,
+                if read_only { 0 } else { 1 },
+                &mut blob,
+            )
+        };
+        if result != SQLITE_OK {
+            // A handle is returned on some errors and must still be closed
+            let err = Error::from_code(self.raw(), result);
+            unsafe { sqlite3_blob_close(blob) };
+            return Err(err);
+        }
+        Ok(Blob {
+            connection: self.clone(),
+            blob,
+            position: 0,
+        })
+    }
+}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_database() -> Connection {
+        let db = Connection::open_memory().unwrap();
+        db.execute_batch(
+            "CREATE TABLE files (id INTEGER PRIMARY KEY, data BLOB NOT NULL) STRICT;
+            INSERT INTO files (id, data) VALUES (1, zeroblob(16)), (2, x'0102030405');",
+        )
+        .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_blob_read_write_seek() {
+        let db = test_database();
+        let mut blob = db.blob_open("files", "data", 1, false).unwrap();
+        assert_eq!(blob.len(), 16);
+
+        blob.write_all(b"Hello").unwrap();
+        blob.seek(SeekFrom::End(-5)).unwrap();
+        blob.write_all(b"World").unwrap();
+        assert!(blob.write_all(b"!").is_err());
+
+        blob.seek(SeekFrom::Start(0)).unwrap();
+        let mut data = Vec::new();
+        blob.read_to_end(&mut data).unwrap();
+        assert_eq!(&data[..5], b"Hello");
+        assert_eq!(&data[11..], b"World");
+        assert!(blob.seek(SeekFrom::Current(-17)).is_err());
+        drop(blob);
+
+        let data = db.query_some::<Vec<u8>>("SELECT data FROM files WHERE id = 1", ());
+        assert_eq!(&data[..5], b"Hello");
+
+        // Reopen moves to another row
+        let mut blob = db.blob_open("files", "data", 1, true).unwrap();
+        blob.reopen(2).unwrap();
+        let mut data = Vec::new();
+        blob.read_to_end(&mut data).unwrap();
+        assert_eq!(data, vec![1, 2, 3, 4, 5]);
+        blob.rewind().unwrap();
+        assert!(blob.write(b"x").is_err());
+    }
+
+    #[test]
+    fn test_blob_open_errors() {
+        let db = test_database();
+        assert!(db.blob_open("files", "data", 3, true).is_err());
+        assert!(db.blob_open("files", "unknown", 1, true).is_err());
+        assert!(db.blob_open("unknown", "data", 1, true).is_err());
+
+        // The handle expires when the row changes
+        let mut blob = db.blob_open("files", "data", 2, true).unwrap();
+        db.execute("UPDATE files SET data = x'ff' WHERE id = 2", ());
+        let mut data = [0; 1];
+        assert!(blob.read(&mut data).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::collections::VecDeque;
+use std::mem::ManuallyDrop;
+use std::sync::{Arc, Mutex};
+
+use libsqlite3_sys::*;
+
+/// Default capacity of the prepared statement cache
+pub(crate) const DEFAULT_CAPACITY: usize = 16;
+
+// MARK: Cached Statement
+pub(crate) struct CachedStatement(*mut sqlite3_stmt);
+// SAFETY: Connections are opened in serialized mode so statements can move between threads
+unsafe impl Send for CachedStatement {}
+
+impl Drop for CachedStatement {
+    fn drop(&mut self) {
+        unsafe { sqlite3_finalize(self.0) };
+    }
+}
+
+/// A handle to return a statement to the cache it came from
+pub(crate) struct CacheReturn {
+    cache: Arc<Mutex<StatementCache>>,
+    key: String,
+}
+
+impl CacheReturn {
+    pub(crate) fn give_back(self, statement: *mut sqlite3_stmt) {
+        self.cache
+            .lock()
+            .expect("Can't lock")
+            .insert(self.key, CachedStatement(statement));
+    }
+}
+
+// MARK: Statement Cache
+/// A least recently used cache of prepared statements keyed by their SQL text
+pub(crate) struct StatementCache {
+    capacity: usize,
+    closed: bool,
+    entries: VecDeque<(String, CachedStatement)>,
+}
+
+impl StatementCache {
+    pub(crate) fn new(capacity: usize) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(Self {
+            capacity,
+            closed: false,
+            entries: VecDeque::new(),
+        }))
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Take a statement out of the cache
+    pub(crate) fn take(
+        cache: &Arc<Mutex<Self>>,
+        key: &str,
+    ) -> Option<(*mut sqlite3_stmt, CacheReturn)> {
+        let mut this = cache.lock().expect("Can't lock");
+        let index = this.entries.iter().position(|(k, _)| k == key)?;
+        let (key, statement) = this.entries.remove(index).expect("Should be some");
+        let statement = ManuallyDrop::new(statement);
+        Some((
+            statement.0,
+            CacheReturn {
+                cache: cache.clone(),
+                key,
+            },
+        ))
+    }
+
+    /// Create a handle to return a new statement to the cache
+    pub(crate) fn return_handle(cache: &Arc<Mutex<Self>>, key: &str) -> Option<CacheReturn> {
+        let this = cache.lock().expect("Can't lock");
+        if this.closed || this.capacity == 0 {
+            return None;
+        }
+        Some(CacheReturn {
+            cache: cache.clone(),
+            key: key.to_string(),
+        })
+    }
+
+    fn insert(&mut self, key: String, statement: CachedStatement) {
+        if self.closed || self.capacity == 0 || self.entries.iter().any(|(k, _)| *k == key) {
+            return;
+        }
+        unsafe {
+            sqlite3_reset(statement.0);
+            sqlite3_clear_bindings(statement.0);
+        }
+        self.entries.push_front((key, statement));
+        self.entries.truncate(self.capacity);
+    }
+
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.entries.truncate(capacity);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Finalize all statements and stop caching, called before the connection is closed
+    pub(crate) fn close(&mut self) {
+        self.closed = true;
+        self.entries.clear();
+    }
+}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use crate::Connection;
+
+    #[test]
+    fn test_statement_cache_reuse() {
+        let db = Connection::open_memory().unwrap();
+        assert_eq!(db.statement_cache_len(), 0);
+        {
+            let mut statement = db.prepare_cached::<i64>("SELECT ?");
+            statement.bind(5);
+            assert_eq!(statement.next(), Some(5));
+            assert_eq!(db.statement_cache_len(), 0);
+        }
+        assert_eq!(db.statement_cache_len(), 1);
+
+        // Reused statements are reset and their bindings are cleared
+        {
+            let mut statement = db.prepare_cached::<Option<i64>>("SELECT ?");
+            assert_eq!(db.statement_cache_len(), 0);
+            assert_eq!(statement.next(), Some(None));
+
+            // A second checkout of the same query prepares a new statement
+            let mut other = db.prepare_cached::<i64>("SELECT ?");
+            other.bind(6);
+            assert_eq!(other.next(), Some(6));
+        }
+        assert_eq!(db.statement_cache_len(), 1);
+
+        // Unfinished statements are reset when returned
+        db.execute("CREATE TABLE numbers (number INTEGER) STRICT", ());
+        db.execute("INSERT INTO numbers VALUES (1), (2)", ());
+        for _ in 0..2 {
+            let mut statement = db.query::<i64>("SELECT number FROM numbers ORDER BY number", ());
+            assert_eq!(statement.next(), Some(1));
+        }
+    }
+
+    #[test]
+    fn test_statement_cache_capacity() {
+        let db = Connection::open_memory().unwrap();
+        db.set_statement_cache_capacity(2);
+        assert_eq!(db.statement_cache_capacity(), 2);
+        for query in ["SELECT 1", "SELECT 2", "SELECT 3", "SELECT 1"] {
+            db.query_some::<i64>(query, ());
+        }
+        assert_eq!(db.statement_cache_len(), 2);
+
+        db.set_statement_cache_capacity(1);
+        assert_eq!(db.statement_cache_len(), 1);
+        db.clear_statement_cache();
+        assert_eq!(db.statement_cache_len(), 0);
+
+        db.set_statement_cache_capacity(0);
+        db.query_some::<i64>("SELECT 1", ());
+        assert_eq!(db.statement_cache_len(), 0);
+    }
+
+    #[test]
+    fn test_statement_cache_outlives_connection() {
+        let db = Connection::open_memory().unwrap();
+        let mut statement = db.prepare_cached::<i64>("SELECT 1");
+        drop(db);
+        assert_eq!(statement.next(), Some(1));
+    }
+}
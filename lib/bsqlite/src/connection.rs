@@ -4,19 +4,21 @@
  * SPDX-License-Identifier: MIT
  */
 
-use std::error::Error;
+use std::error::Error as StdError;
 use std::ffi::{c_char, CStr, CString};
 use std::fmt::{self, Display, Formatter};
 use std::path::Path;
 use std::ptr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use libsqlite3_sys::*;
 
-use crate::{Bind, FromRow, Statement};
+use crate::cache::{StatementCache, DEFAULT_CAPACITY};
+use crate::hook::Hooks;
+use crate::{Bind, Error, FromRow, Statement};
 
 // MARK: Inner Connection
-struct InnerConnection(*mut sqlite3);
+struct InnerConnection(*mut sqlite3, Arc<Mutex<StatementCache>>, Arc<Mutex<Hooks>>);
 unsafe impl Send for InnerConnection {}
 unsafe impl Sync for InnerConnection {}
 
@@ -40,10 +42,34 @@ impl InnerConnection {
                 msg: format!("Failed to open database: {}", error),
             });
         }
-        Ok(InnerConnection(db))
+        Ok(InnerConnection(
+            db,
+            StatementCache::new(DEFAULT_CAPACITY),
+            Arc::new(Mutex::new(Hooks::default())),
+        ))
+    }
+
+    fn prepare<T: FromRow>(&self, query: &str) -> Result<Statement<T>, Error> {
+        Ok(Statement::new(
+            self.prepare_raw(query)?,
+            None,
+            self.2.clone(),
+        ))
+    }
+
+    fn prepare_cached<T: FromRow>(&self, query: &str) -> Result<Statement<T>, Error> {
+        if let Some((statement, cache)) = StatementCache::take(&self.1, query) {
+            return Ok(Statement::new(statement, Some(cache), self.2.clone()));
+        }
+        let statement = self.prepare_raw(query)?;
+        Ok(Statement::new(
+            statement,
+            StatementCache::return_handle(&self.1, query),
+            self.2.clone(),
+        ))
     }
 
-    fn prepare<T: FromRow>(&self, query: &str) -> Statement<T> {
+    fn prepare_raw(&self, query: &str) -> Result<*mut sqlite3_stmt, Error> {
         let mut statement = ptr::null_mut();
         let result = unsafe {
             sqlite3_prepare_v2(
@@ -55,13 +81,29 @@ impl InnerConnection {
             )
         };
         if result != SQLITE_OK {
-            let error = unsafe { CStr::from_ptr(sqlite3_errmsg(self.0)) }.to_string_lossy();
-            panic!(
-                "bsqlite: Failed to prepare SQL statement!\n  Query: {}\n  Error: {}",
-                query, error
-            );
+            return Err(Error::from_code(self.0, result).with_sql(query));
+        }
+        Ok(statement)
+    }
+
+    fn execute_batch(&self, queries: &str) -> Result<(), Error> {
+        let c_queries = CString::new(queries).map_err(|_| {
+            Error::new(SQLITE_MISUSE, "Queries contain a nul byte").with_sql(queries)
+        })?;
+        let result = unsafe {
+            sqlite3_exec(
+                self.0,
+                c_queries.as_ptr(),
+                None,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        Hooks::send_committed(&self.2, self.0);
+        if result != SQLITE_OK {
+            return Err(Error::from_code(self.0, result).with_sql(queries));
         }
-        Statement::new(statement)
+        Ok(())
     }
 
     fn affected_rows(&self) -> i32 {
@@ -71,11 +113,18 @@ impl InnerConnection {
     fn last_insert_row_id(&self) -> i64 {
         unsafe { sqlite3_last_insert_rowid(self.0) }
     }
+
+    fn is_autocommit(&self) -> bool {
+        unsafe { sqlite3_get_autocommit(self.0) != 0 }
+    }
 }
 
 impl Drop for InnerConnection {
     fn drop(&mut self) {
-        unsafe { sqlite3_close(self.0) };
+        self.1.lock().expect("Can't lock").close();
+        // Unregister hooks first because statements can outlive the connection
+        Hooks::uninstall(self.0);
+        unsafe { sqlite3_close_v2(self.0) };
     }
 }
 
@@ -92,7 +141,7 @@ impl Display for ConnectionError {
     }
 }
 
-impl Error for ConnectionError {}
+impl StdError for ConnectionError {}
 
 // MARK: Connection
 /// A SQLite connection
@@ -102,7 +151,9 @@ pub struct Connection(Arc<InnerConnection>);
 impl Connection {
     /// Open a connection to a SQLite database
     pub fn open(path: impl AsRef<Path>) -> Result<Self, ConnectionError> {
-        Ok(Connection(Arc::new(InnerConnection::open(path.as_ref())?)))
+        let connection = Connection(Arc::new(InnerConnection::open(path.as_ref())?));
+        Hooks::install(&connection);
+        Ok(connection)
     }
 
     /// Open a memory database
@@ -110,6 +161,16 @@ impl Connection {
         Self::open(":memory:")
     }
 
+    /// Get the raw database handle
+    pub(crate) fn raw(&self) -> *mut sqlite3 {
+        self.0 .0
+    }
+
+    /// Get the hooks of the connection
+    pub(crate) fn hooks(&self) -> &Mutex<Hooks> {
+        &self.0 .2
+    }
+
     /// Set the journal mode to Write-Ahead Logging for better concurrency throughput
     pub fn enable_wal_logging(&self) {
         self.execute("PRAGMA journal_mode = WAL", ());
@@ -138,16 +199,79 @@ impl Connection {
 
     /// Prepare a statement
     pub fn prepare<T: FromRow>(&self, query: impl AsRef<str>) -> Statement<T> {
+        match self.try_prepare(query.as_ref()) {
+            Ok(statement) => statement,
+            Err(err) => panic!(
+                "bsqlite: Failed to prepare SQL statement!\n  Query: {}\n  Error: {}",
+                query.as_ref(),
+                err.message()
+            ),
+        }
+    }
+
+    /// Try to prepare a statement
+    pub fn try_prepare<T: FromRow>(&self, query: impl AsRef<str>) -> Result<Statement<T>, Error> {
         self.0.prepare(query.as_ref())
     }
 
-    /// Run a query
+    /// Prepare a statement or reuse it from the statement cache, it is returned to the cache when dropped
+    pub fn prepare_cached<T: FromRow>(&self, query: impl AsRef<str>) -> Statement<T> {
+        match self.try_prepare_cached(query.as_ref()) {
+            Ok(statement) => statement,
+            Err(err) => panic!(
+                "bsqlite: Failed to prepare SQL statement!\n  Query: {}\n  Error: {}",
+                query.as_ref(),
+                err.message()
+            ),
+        }
+    }
+
+    /// Try to prepare a statement or reuse it from the statement cache
+    pub fn try_prepare_cached<T: FromRow>(
+        &self,
+        query: impl AsRef<str>,
+    ) -> Result<Statement<T>, Error> {
+        self.0.prepare_cached(query.as_ref())
+    }
+
+    /// Set the capacity of the statement cache, zero disables the cache
+    pub fn set_statement_cache_capacity(&self, capacity: usize) {
+        self.0 .1.lock().expect("Can't lock").set_capacity(capacity);
+    }
+
+    /// Get the capacity of the statement cache
+    pub fn statement_cache_capacity(&self) -> usize {
+        self.0 .1.lock().expect("Can't lock").capacity()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn statement_cache_len(&self) -> usize {
+        self.0 .1.lock().expect("Can't lock").len()
+    }
+
+    /// Finalize all statements in the statement cache
+    pub fn clear_statement_cache(&self) {
+        self.0 .1.lock().expect("Can't lock").clear();
+    }
+
+    /// Run a query, the statement is reused from the statement cache
     pub fn query<T: FromRow>(&self, query: impl AsRef<str>, params: impl Bind) -> Statement<T> {
-        let mut statement = self.prepare::<T>(query.as_ref());
+        let mut statement = self.prepare_cached::<T>(query.as_ref());
         statement.bind(params);
         statement
     }
 
+    /// Try to run a query, the statement is reused from the statement cache
+    pub fn try_query<T: FromRow>(
+        &self,
+        query: impl AsRef<str>,
+        params: impl Bind,
+    ) -> Result<Statement<T>, Error> {
+        let mut statement = self.try_prepare_cached::<T>(query.as_ref())?;
+        statement.try_bind(params)?;
+        Ok(statement)
+    }
+
     /// Run a query, read and expect the first row
     pub fn query_some<T: FromRow>(&self, query: impl AsRef<str>, params: impl Bind) -> T {
         self.query::<T>(query.as_ref(), params)
@@ -155,11 +279,31 @@ impl Connection {
             .expect("Should be some")
     }
 
+    /// Try to run a query and read the first row if there is one
+    pub fn query_optional<T: FromRow>(
+        &self,
+        query: impl AsRef<str>,
+        params: impl Bind,
+    ) -> Result<Option<T>, Error> {
+        self.try_query::<T>(query.as_ref(), params)?.try_next()
+    }
+
     /// Execute a query
     pub fn execute(&self, query: impl AsRef<str>, params: impl Bind) {
         self.query::<()>(query.as_ref(), params).next();
     }
 
+    /// Try to execute a query
+    pub fn try_execute(&self, query: impl AsRef<str>, params: impl Bind) -> Result<(), Error> {
+        self.try_query::<()>(query.as_ref(), params)?.try_next()?;
+        Ok(())
+    }
+
+    /// Execute multiple queries separated by semicolons without params
+    pub fn execute_batch(&self, queries: impl AsRef<str>) -> Result<(), Error> {
+        self.0.execute_batch(queries.as_ref())
+    }
+
     /// Get the number of affected rows
     pub fn affected_rows(&self) -> i32 {
         self.0.affected_rows()
@@ -169,6 +313,11 @@ impl Connection {
     pub fn last_insert_row_id(&self) -> i64 {
         self.0.last_insert_row_id()
     }
+
+    /// Check if a transaction is open on the connection
+    pub fn in_transaction(&self) -> bool {
+        !self.0.is_autocommit()
+    }
 }
 
 // MARK: Macros
@@ -177,9 +326,9 @@ impl Connection {
 #[macro_export]
 macro_rules! query_args {
     ($t:tt, $db:expr, $query:expr, Args { $($key:ident : $value:expr),* $(,)? } $(,)?) => {{
-        let mut stat = $db.prepare::<$t>($query);
+        let mut stat = $db.prepare_cached::<$t>($query);
         $(
-            stat.bind_named_value(concat!(":", stringify!($key)), Into::<$crate::Value>::into($value));
+            stat.bind_named_value(concat!(":", stringify!($key)), $value);
         )*
         stat
     }};
@@ -189,9 +338,9 @@ macro_rules! query_args {
 #[macro_export]
 macro_rules! execute_args {
     ($db:expr, $query:expr, Args { $($key:ident : $value:expr),* $(,)? } $(,)?) => {{
-        let mut stat = $db.prepare::<()>($query);
+        let mut stat = $db.prepare_cached::<()>($query);
         $(
-            stat.bind_named_value(concat!(":", stringify!($key)), Into::<$crate::Value>::into($value));
+            stat.bind_named_value(concat!(":", stringify!($key)), $value);
         )*
         stat.next();
     }};
@@ -201,6 +350,7 @@ macro_rules! execute_args {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::{ConstraintKind, ErrorKind};
 
     #[test]
     fn test_open_db_execute_queries() {
@@ -217,7 +367,7 @@ mod test {
             db,
             "INSERT INTO persons (name, age) VALUES (:name, :age)",
             Args {
-                name: "Bob".to_string(),
+                name: "Bob",
                 age: 40,
             },
         );
@@ -231,5 +381,137 @@ mod test {
             names,
             vec![("Alice".to_string(), 30), ("Bob".to_string(), 40)]
         );
+        let name = "Bob".to_string();
+        let ages = query_args!(
+            i64,
+            db,
+            "SELECT age FROM persons WHERE name = :name",
+            Args { name: &name }
+        )
+        .collect::<Vec<_>>();
+        assert_eq!(ages, vec![40]);
+    }
+
+    #[test]
+    fn test_try_prepare_error() {
+        let db = Connection::open_memory().unwrap();
+        let err = db
+            .try_prepare::<()>("SELECT * FROM missing_table")
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), SQLITE_ERROR);
+        assert_eq!(err.kind(), ErrorKind::Other);
+        assert_eq!(err.sql(), Some("SELECT * FROM missing_table"));
+        assert!(err.message().contains("missing_table"));
+    }
+
+    #[test]
+    fn test_try_execute_constraint_errors() {
+        let db = Connection::open_memory().unwrap();
+        db.execute(
+            "CREATE TABLE persons (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE, age INTEGER CHECK (age >= 0)) STRICT",
+            (),
+        );
+        db.try_execute("INSERT INTO persons (id, name) VALUES (1, 'Alice')", ())
+            .unwrap();
+
+        let err = db
+            .try_execute("INSERT INTO persons (name) VALUES ('Alice')", ())
+            .unwrap_err();
+        assert_eq!(err.code(), SQLITE_CONSTRAINT);
+        assert_eq!(err.extended_code(), SQLITE_CONSTRAINT_UNIQUE);
+        assert_eq!(err.kind(), ErrorKind::Constraint(ConstraintKind::Unique));
+        assert!(err.is_constraint(ConstraintKind::Unique));
+        assert_eq!(
+            err.sql(),
+            Some("INSERT INTO persons (name) VALUES ('Alice')")
+        );
+
+        let err = db
+            .try_execute("INSERT INTO persons (id, name) VALUES (1, 'Bob')", ())
+            .unwrap_err();
+        assert!(err.is_constraint(ConstraintKind::PrimaryKey));
+
+        let err = db
+            .try_execute(
+                "INSERT INTO persons (name) VALUES (?)",
+                Option::<String>::None,
+            )
+            .unwrap_err();
+        assert!(err.is_constraint(ConstraintKind::NotNull));
+
+        let err = db
+            .try_execute("INSERT INTO persons (name, age) VALUES ('Bob', -1)", ())
+            .unwrap_err();
+        assert!(err.is_constraint(ConstraintKind::Check));
+
+        // Connection is still usable after errors
+        let total = db.query_some::<i64>("SELECT COUNT(id) FROM persons", ());
+        assert_eq!(total, 1);
+    }
+
+    #[test]
+    fn test_query_optional() {
+        let db = Connection::open_memory().unwrap();
+        db.execute("CREATE TABLE persons (name TEXT) STRICT", ());
+        db.execute("INSERT INTO persons (name) VALUES (?)", "Alice".to_string());
+
+        let name = db
+            .query_optional::<String>(
+                "SELECT name FROM persons WHERE name = ?",
+                "Alice".to_string(),
+            )
+            .unwrap();
+        assert_eq!(name, Some("Alice".to_string()));
+        let name = db
+            .query_optional::<String>("SELECT name FROM persons WHERE name = ?", "Bob".to_string())
+            .unwrap();
+        assert_eq!(name, None);
+    }
+
+    #[test]
+    fn test_try_bind_errors() {
+        let db = Connection::open_memory().unwrap();
+        let err = db.try_query::<i64>("SELECT ?", (1, 2)).err().unwrap();
+        assert_eq!(err.code(), SQLITE_RANGE);
+        assert_eq!(err.sql(), Some("SELECT ?"));
+
+        let mut statement = db.prepare::<i64>("SELECT :value");
+        let err = statement.try_bind_named_value(":missing", 1).unwrap_err();
+        assert_eq!(err.code(), SQLITE_RANGE);
+        assert_eq!(err.sql(), Some("SELECT :value"));
+    }
+
+    #[test]
+    fn test_bind_borrowed_values() {
+        let db = Connection::open_memory().unwrap();
+        let name = "Alice".to_string();
+        let data = vec![1u8, 2, 3];
+        let row = db
+            .try_query::<(String, Vec<u8>, Option<String>, String)>(
+                "SELECT ?, ?, ?, ?",
+                (name.as_str(), &data, None::<&str>, &name),
+            )
+            .unwrap()
+            .next()
+            .unwrap();
+        assert_eq!(row, (name.clone(), data.clone(), None, name.clone()));
+
+        let mut statement = db.prepare::<String>("SELECT :name");
+        statement.bind_named_value(":name", name.as_str());
+        assert_eq!(statement.next().unwrap(), "Alice");
+    }
+
+    #[test]
+    fn test_bind_u64_values() {
+        let db = Connection::open_memory().unwrap();
+        let value = db.query_some::<i64>("SELECT ?", 42u64);
+        assert_eq!(value, 42);
+        let value = db.query_some::<Option<i64>>("SELECT ?", None::<u64>);
+        assert_eq!(value, None);
+
+        let err = db.try_query::<i64>("SELECT ?", u64::MAX).err().unwrap();
+        assert_eq!(err.code(), SQLITE_MISMATCH);
+        assert_eq!(err.sql(), Some("SELECT ?"));
     }
 }
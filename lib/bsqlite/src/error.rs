@@ -0,0 +1,193 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::ffi::{c_char, CStr};
+use std::fmt::{self, Display, Formatter};
+
+use libsqlite3_sys::*;
+
+/// A bsqlite result
+pub type Result<T> = std::result::Result<T, Error>;
+
+// MARK: ErrorKind
+/// A SQLite error kind
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The database file is locked by another connection
+    Busy,
+    /// A table is locked by another statement on the same connection
+    Locked,
+    /// A constraint was violated
+    Constraint(ConstraintKind),
+    /// An other error
+    Other,
+}
+
+/// A SQLite constraint kind
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConstraintKind {
+    /// A UNIQUE constraint was violated
+    Unique,
+    /// A PRIMARY KEY constraint was violated
+    PrimaryKey,
+    /// A FOREIGN KEY constraint was violated
+    ForeignKey,
+    /// A NOT NULL constraint was violated
+    NotNull,
+    /// A CHECK constraint was violated
+    Check,
+    /// An other constraint was violated
+    Other,
+}
+
+// MARK: Error
+/// A SQLite error
+#[derive(Debug, Clone)]
+pub struct Error {
+    code: i32,
+    extended_code: i32,
+    message: String,
+    sql: Option<String>,
+}
+
+impl Error {
+    /// Create new error with result code and message
+    pub(crate) fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code: code & 0xff,
+            extended_code: code,
+            message: message.into(),
+            sql: None,
+        }
+    }
+
+    /// Create new error from the last error of a database handle
+    pub(crate) fn from_db(db: *mut sqlite3) -> Self {
+        Self::from_code(db, unsafe { sqlite3_errcode(db) })
+    }
+
+    /// Create new error from a returned result code, the message of the database handle
+    /// is only used when an other thread didn't replace its last error in the meantime
+    pub(crate) fn from_code(db: *mut sqlite3, code: i32) -> Self {
+        let mutex = unsafe { sqlite3_db_mutex(db) };
+        unsafe { sqlite3_mutex_enter(mutex) };
+        let (extended_code, message) = if unsafe { sqlite3_errcode(db) } == code & 0xff {
+            (
+                unsafe { sqlite3_extended_errcode(db) },
+                unsafe { CStr::from_ptr(sqlite3_errmsg(db)) }
+                    .to_string_lossy()
+                    .to_string(),
+            )
+        } else {
+            (
+                code,
+                unsafe { CStr::from_ptr(sqlite3_errstr(code)) }
+                    .to_string_lossy()
+                    .to_string(),
+            )
+        };
+        unsafe { sqlite3_mutex_leave(mutex) };
+        Self {
+            code: code & 0xff,
+            extended_code,
+            message,
+            sql: None,
+        }
+    }
+
+    /// Create new error from a result code returned for a statement handle
+    pub(crate) fn from_statement(statement: *mut sqlite3_stmt, code: i32) -> Self {
+        let sql = unsafe { CStr::from_ptr(sqlite3_sql(statement) as *const c_char) }
+            .to_string_lossy()
+            .to_string();
+        Self::from_code(unsafe { sqlite3_db_handle(statement) }, code).with_sql(sql)
+    }
+
+    pub(crate) fn with_sql(mut self, sql: impl Into<String>) -> Self {
+        self.sql = Some(sql.into());
+        self
+    }
+
+    /// Get primary result code
+    pub fn code(&self) -> i32 {
+        self.code
+    }
+
+    /// Get extended result code
+    pub fn extended_code(&self) -> i32 {
+        self.extended_code
+    }
+
+    /// Get error message
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Get SQL text of the failed statement
+    pub fn sql(&self) -> Option<&str> {
+        self.sql.as_deref()
+    }
+
+    /// Get error kind
+    pub fn kind(&self) -> ErrorKind {
+        match self.code {
+            SQLITE_BUSY => ErrorKind::Busy,
+            SQLITE_LOCKED => ErrorKind::Locked,
+            SQLITE_CONSTRAINT => ErrorKind::Constraint(match self.extended_code {
+                SQLITE_CONSTRAINT_UNIQUE => ConstraintKind::Unique,
+                SQLITE_CONSTRAINT_PRIMARYKEY => ConstraintKind::PrimaryKey,
+                SQLITE_CONSTRAINT_FOREIGNKEY => ConstraintKind::ForeignKey,
+                SQLITE_CONSTRAINT_NOTNULL => ConstraintKind::NotNull,
+                SQLITE_CONSTRAINT_CHECK => ConstraintKind::Check,
+                _ => ConstraintKind::Other,
+            }),
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// Check if error is a violation of a constraint kind
+    pub fn is_constraint(&self, kind: ConstraintKind) -> bool {
+        self.kind() == ErrorKind::Constraint(kind)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (code {})", self.message, self.extended_code)?;
+        if let Some(sql) = &self.sql {
+            write!(f, "\n  Query: {}", sql)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Connection;
+
+    #[test]
+    fn test_from_code_uses_returned_code() {
+        let db = Connection::open_memory().unwrap();
+        db.execute_batch("CREATE TABLE t (id INTEGER PRIMARY KEY);")
+            .unwrap();
+        assert!(db.execute_batch("SELECT * FROM missing;").is_err());
+
+        // Message of the connection belongs to the returned code
+        let err = Error::from_code(db.raw(), SQLITE_ERROR);
+        assert_eq!(err.code(), SQLITE_ERROR);
+        assert_eq!(err.message(), "no such table: missing");
+
+        // Last error of the connection was replaced, so the generic message is used
+        db.execute_batch("SELECT 1;").unwrap();
+        let err = Error::from_code(db.raw(), SQLITE_CONSTRAINT_UNIQUE);
+        assert_eq!(err.kind(), ErrorKind::Constraint(ConstraintKind::Unique));
+        assert_eq!(err.message(), "constraint failed");
+    }
+}
@@ -4,24 +4,29 @@
  * SPDX-License-Identifier: MIT
  */
 
+use std::fmt::Display;
+
+use crate::error::Result;
 use crate::{RawStatement, Value};
 
 /// A trait for converting read values from a statement to a row
 pub trait FromRow: Sized {
     /// Convert read values from a statement to a row
-    fn from_row(statement: &mut RawStatement) -> Self;
+    fn from_row(statement: &mut RawStatement) -> Result<Self>;
 }
 
 impl FromRow for () {
-    fn from_row(_statement: &mut RawStatement) -> Self {}
+    fn from_row(_statement: &mut RawStatement) -> Result<Self> {
+        Ok(())
+    }
 }
 
-impl<T: TryFrom<Value>> FromRow for T {
-    fn from_row(statement: &mut RawStatement) -> Self {
-        match T::try_from(statement.read_value(0)) {
-            Ok(value) => value,
-            Err(_) => panic!("Can't convert Value"),
-        }
+impl<T: TryFrom<Value>> FromRow for T
+where
+    T::Error: Display,
+{
+    fn from_row(statement: &mut RawStatement) -> Result<Self> {
+        statement.read(0)
     }
 }
 
@@ -29,17 +34,10 @@ macro_rules! impl_from_row_for_tuple {
     ($($n:tt: $t:ident),*) => (
         impl<$($t,)*> FromRow for ($($t,)*)
         where
-            $($t: TryFrom<Value>,)+
+            $($t: TryFrom<Value>, $t::Error: Display,)+
         {
-            fn from_row(statement: &mut RawStatement) -> Self {
-                (
-                    $(
-                        match $t::try_from(statement.read_value($n)) {
-                            Ok(value) => value,
-                            Err(_) => panic!("Can't convert Value"),
-                        },
-                    )*
-                )
+            fn from_row(statement: &mut RawStatement) -> Result<Self> {
+                Ok(($(statement.read::<$t>($n)?,)*))
             }
         }
     );
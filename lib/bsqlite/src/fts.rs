@@ -0,0 +1,376 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Full-text search with FTS5 external content tables
+//!
+//! An external content table indexes columns of an existing table without storing the
+//! text twice, triggers keep the index in sync with the content table.
+//!
+//! ```rs
+//! use bsqlite::fts::{self, FtsTable, SearchHit};
+//!
+//! let fts = FtsTable::new("persons_fts", "persons").columns(&["name"]);
+//! fts.create(&db)?;
+//! let hits = fts.search::<Person>(&db, &fts::prefix_query("ali"), 10, 0)?;
+//! for hit in hits {
+//!     println!("{} {}", hit.highlights[0], hit.rank);
+//! }
+//! ```
+
+use libsqlite3_sys::SQLITE_MISUSE;
+
+use crate::query::Page;
+use crate::{Connection, Error, FromRow, RawStatement, Result, Value};
+
+/// Quote every term of user input so it matches as prefix and can't contain FTS5 query syntax
+pub fn prefix_query(text: &str) -> String {
+    text.split_whitespace()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// MARK: SearchHit
+/// A search result row
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit<T> {
+    /// Content row
+    pub row: T,
+    /// The `bm25()` rank, lower is a better match
+    pub rank: f64,
+    /// The `highlight()` text of every indexed column
+    pub highlights: Vec<String>,
+}
+
+impl<T: FromRow> FromRow for SearchHit<T> {
+    fn from_row(statement: &mut RawStatement) -> Result<Self> {
+        let mut highlights = Vec::new();
+        while let Some(index) =
+            statement.column_index(&format!("fts_highlight_{}", highlights.len()))
+        {
+            highlights.push(statement.read::<Option<String>>(index)?.unwrap_or_default());
+        }
+        Ok(Self {
+            row: T::from_row(statement)?,
+            rank: statement.read_named("fts_rank")?,
+            highlights,
+        })
+    }
+}
+
+// MARK: FtsTable
+/// A FTS5 external content table
+#[derive(Debug, Clone)]
+pub struct FtsTable {
+    name: String,
+    content: String,
+    content_rowid: String,
+    columns: Vec<String>,
+    tokenize: Option<String>,
+    highlight_open: String,
+    highlight_close: String,
+}
+
+impl FtsTable {
+    /// Create FTS table for a content table that is linked by its rowid
+    ///
+    /// The content table must have an `INTEGER PRIMARY KEY` column, because an implicit
+    /// rowid can be renumbered by `VACUUM` which breaks the link with the index.
+    pub fn new(name: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            content: content.into(),
+            content_rowid: "rowid".to_string(),
+            columns: Vec::new(),
+            tokenize: None,
+            highlight_open: "<mark>".to_string(),
+            highlight_close: "</mark>".to_string(),
+        }
+    }
+
+    /// Set indexed columns of the content table
+    pub fn columns(mut self, columns: &[&str]) -> Self {
+        self.columns = columns.iter().map(|column| column.to_string()).collect();
+        self
+    }
+
+    /// Set the `INTEGER PRIMARY KEY` column of the content table, defaults to its `rowid` alias
+    pub fn content_rowid(mut self, column: impl Into<String>) -> Self {
+        self.content_rowid = column.into();
+        self
+    }
+
+    /// Set tokenizer, for example `porter unicode61 remove_diacritics 2`
+    pub fn tokenize(mut self, tokenize: impl Into<String>) -> Self {
+        self.tokenize = Some(tokenize.into());
+        self
+    }
+
+    /// Set the text that is inserted around matched terms in highlights
+    pub fn highlight(mut self, open: impl Into<String>, close: impl Into<String>) -> Self {
+        self.highlight_open = open.into();
+        self.highlight_close = close.into();
+        self
+    }
+
+    fn column_list(&self, prefix: &str) -> String {
+        self.columns
+            .iter()
+            .map(|column| format!("{}{}", prefix, column))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Get SQL text that creates the FTS table and the sync triggers
+    pub fn create_sql(&self) -> String {
+        let mut options = vec![
+            self.column_list(""),
+            format!("content='{}'", self.content),
+            format!("content_rowid='{}'", self.content_rowid),
+        ];
+        if let Some(tokenize) = &self.tokenize {
+            options.push(format!("tokenize='{}'", tokenize.replace('\'', "''")));
+        }
+        let name = &self.name;
+        let content = &self.content;
+        let rowid = &self.content_rowid;
+        let columns = self.column_list("");
+        let new_values = self.column_list("new.");
+        let old_values = self.column_list("old.");
+        format!(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS {name} USING fts5({options});
+            CREATE TRIGGER IF NOT EXISTS {name}_insert AFTER INSERT ON {content} BEGIN
+                INSERT INTO {name} (rowid, {columns}) VALUES (new.{rowid}, {new_values});
+            END;
+            CREATE TRIGGER IF NOT EXISTS {name}_delete AFTER DELETE ON {content} BEGIN
+                INSERT INTO {name} ({name}, rowid, {columns}) VALUES ('delete', old.{rowid}, {old_values});
+            END;
+            CREATE TRIGGER IF NOT EXISTS {name}_update AFTER UPDATE ON {content} BEGIN
+                INSERT INTO {name} ({name}, rowid, {columns}) VALUES ('delete', old.{rowid}, {old_values});
+                INSERT INTO {name} (rowid, {columns}) VALUES (new.{rowid}, {new_values});
+            END;",
+            options = options.join(", ")
+        )
+    }
+
+    /// Create the FTS table and sync triggers when they don't exist and index the existing rows
+    pub fn create(&self, connection: &Connection) -> Result<()> {
+        self.check_content_rowid(connection)?;
+        let exists = connection
+            .try_query::<i64>(
+                "SELECT COUNT(*) FROM sqlite_schema WHERE type = 'table' AND name = ?",
+                self.name.clone(),
+            )?
+            .try_next()?
+            .unwrap_or(0)
+            > 0;
+        connection.execute_batch(self.create_sql())?;
+        if !exists {
+            self.rebuild(connection)?;
+        }
+        Ok(())
+    }
+
+    /// Check that the content rowid is a stable `INTEGER PRIMARY KEY` column
+    fn check_content_rowid(&self, connection: &Connection) -> Result<()> {
+        let mut statement = connection.try_query::<(String, String)>(
+            "SELECT name, type FROM pragma_table_info(?) WHERE pk > 0",
+            self.content.clone(),
+        )?;
+        let mut primary_keys = Vec::new();
+        while let Some(primary_key) = statement.try_next()? {
+            primary_keys.push(primary_key);
+        }
+        let is_alias = ["rowid", "oid", "_rowid_"]
+            .iter()
+            .any(|alias| alias.eq_ignore_ascii_case(&self.content_rowid));
+        match primary_keys.as_slice() {
+            [(name, r#type)]
+                if r#type.eq_ignore_ascii_case("INTEGER")
+                    && (is_alias || name.eq_ignore_ascii_case(&self.content_rowid)) =>
+            {
+                Ok(())
+            }
+            _ => Err(Error::new(
+                SQLITE_MISUSE,
+                format!(
+                    "Content table {} needs an INTEGER PRIMARY KEY column for content_rowid {}",
+                    self.content, self.content_rowid
+                ),
+            )),
+        }
+    }
+
+    /// Rebuild the full-text index from the content table
+    pub fn rebuild(&self, connection: &Connection) -> Result<()> {
+        connection.try_execute(
+            format!("INSERT INTO {0} ({0}) VALUES ('rebuild')", self.name),
+            (),
+        )
+    }
+
+    /// Search rows matching a FTS5 query ordered by rank
+    pub fn search<T: FromRow>(
+        &self,
+        connection: &Connection,
+        query: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<SearchHit<T>>> {
+        let highlights = (0..self.columns.len())
+            .map(|index| {
+                format!(
+                    "highlight({}, {}, ?1, ?2) AS fts_highlight_{}",
+                    self.name, index, index
+                )
+            })
+            .collect::<Vec<_>>();
+        let sql = format!(
+            "SELECT {content}.*, bm25({name}) AS fts_rank, {highlights} FROM {name}
+                JOIN {content} ON {content}.{rowid} = {name}.rowid
+                WHERE {name} MATCH ?3 ORDER BY fts_rank LIMIT ?4 OFFSET ?5",
+            name = self.name,
+            content = self.content,
+            rowid = self.content_rowid,
+            highlights = highlights.join(", ")
+        );
+        let values = vec![
+            Value::Text(self.highlight_open.clone()),
+            Value::Text(self.highlight_close.clone()),
+            Value::Text(query.to_string()),
+            Value::Integer(limit),
+            Value::Integer(offset),
+        ];
+        let mut statement = connection.try_query::<SearchHit<T>>(sql, values)?;
+        let mut hits = Vec::new();
+        while let Some(hit) = statement.try_next()? {
+            hits.push(hit);
+        }
+        Ok(hits)
+    }
+
+    /// Count rows matching a FTS5 query
+    pub fn count(&self, connection: &Connection, query: &str) -> Result<i64> {
+        Ok(connection
+            .try_query::<i64>(
+                format!("SELECT COUNT(*) FROM {0} WHERE {0} MATCH ?", self.name),
+                query.to_string(),
+            )?
+            .try_next()?
+            .unwrap_or(0))
+    }
+
+    /// Search a page of rows matching a FTS5 query with the total count, the first page is 1
+    pub fn search_page<T: FromRow>(
+        &self,
+        connection: &Connection,
+        query: &str,
+        page: i64,
+        limit: i64,
+    ) -> Result<Page<SearchHit<T>>> {
+        Ok(Page {
+            total: self.count(connection, query)?,
+            data: self.search(connection, query, limit, (page.max(1) - 1) * limit)?,
+        })
+    }
+}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_database() -> (Connection, FtsTable) {
+        let db = Connection::open_memory().unwrap();
+        db.execute_batch(
+            "CREATE TABLE persons (id INTEGER PRIMARY KEY, name TEXT NOT NULL, bio TEXT) STRICT;
+            INSERT INTO persons (name, bio) VALUES ('Alice Smith', 'Likes rust'), ('Bob Jones', 'Likes alice');",
+        )
+        .unwrap();
+        let fts = FtsTable::new("persons_fts", "persons")
+            .columns(&["name", "bio"])
+            .content_rowid("id");
+        fts.create(&db).unwrap();
+        (db, fts)
+    }
+
+    #[test]
+    fn test_prefix_query() {
+        assert_eq!(prefix_query("  ali  \"bob "), "\"ali\"* \"\"\"bob\"*");
+        assert_eq!(prefix_query(""), "");
+    }
+
+    #[test]
+    fn test_search_ranked_with_highlights() {
+        let (db, fts) = test_database();
+
+        // Existing rows are indexed and hits are ordered by rank
+        let hits = fts.search::<(i64, String)>(&db, "alice", 10, 0).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().all(|hit| hit.rank < 0.0));
+        assert!(hits[0].rank <= hits[1].rank);
+        let ids = hits.iter().map(|hit| hit.row.0).collect::<Vec<_>>();
+        assert!(ids.contains(&1) && ids.contains(&2));
+        let alice = hits.iter().find(|hit| hit.row.0 == 1).unwrap();
+        assert_eq!(
+            alice.highlights,
+            vec![
+                "<mark>Alice</mark> Smith".to_string(),
+                "Likes rust".to_string()
+            ]
+        );
+
+        // Prefix queries
+        let hits = fts
+            .search::<(i64, String)>(&db, &prefix_query("jon"), 10, 0)
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].row.1, "Bob Jones");
+    }
+
+    #[test]
+    fn test_content_rowid_must_be_integer_primary_key() {
+        let db = Connection::open_memory().unwrap();
+        db.execute_batch(
+            "CREATE TABLE persons (id BLOB PRIMARY KEY, name TEXT NOT NULL) STRICT;
+            CREATE TABLE notes (id INTEGER PRIMARY KEY, title TEXT NOT NULL) STRICT;",
+        )
+        .unwrap();
+
+        // Implicit rowids are not stable
+        let err = FtsTable::new("persons_fts", "persons")
+            .columns(&["name"])
+            .create(&db)
+            .unwrap_err();
+        assert!(err.message().contains("needs an INTEGER PRIMARY KEY"));
+
+        // The rowid alias or the column name can be used
+        let fts = FtsTable::new("notes_fts", "notes").columns(&["title"]);
+        fts.create(&db).unwrap();
+        assert!(fts.clone().content_rowid("title").create(&db).is_err());
+        fts.content_rowid("id").create(&db).unwrap();
+    }
+
+    #[test]
+    fn test_sync_triggers() {
+        let (db, fts) = test_database();
+        db.execute("INSERT INTO persons (name) VALUES ('Charlie Smith')", ());
+        assert_eq!(fts.count(&db, "smith").unwrap(), 2);
+        db.execute("UPDATE persons SET name = 'Alice Brown' WHERE id = 1", ());
+        assert_eq!(fts.count(&db, "smith").unwrap(), 1);
+        assert_eq!(fts.count(&db, "brown").unwrap(), 1);
+        db.execute("DELETE FROM persons WHERE id = 3", ());
+        assert_eq!(fts.count(&db, "smith").unwrap(), 0);
+
+        // Creating again keeps the index
+        fts.create(&db).unwrap();
+        let page = fts
+            .search_page::<(i64, String)>(&db, "likes", 2, 1)
+            .unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.data.len(), 1);
+    }
+}
@@ -0,0 +1,497 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::cmp::Ordering;
+use std::error::Error as StdError;
+use std::ffi::{c_char, c_int, c_void, CString};
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+
+use libsqlite3_sys::*;
+
+use crate::{Connection, Error, Value};
+
+/// A user-defined function result
+pub type FunctionResult<T> = Result<T, Box<dyn StdError + Send + Sync>>;
+
+// MARK: Helpers
+unsafe fn read_value(value: *mut sqlite3_value) -> Value {
+    let bytes = |data: *const c_void| {
+        let len = sqlite3_value_bytes(value) as usize;
+        if data.is_null() || len == 0 {
+            &[][..]
+        } else {
+            slice::from_raw_parts(data as *const u8, len)
+        }
+    };
+    match sqlite3_value_type(value) {
+        SQLITE_INTEGER => Value::Integer(sqlite3_value_int64(value)),
+        SQLITE_FLOAT => Value::Real(sqlite3_value_double(value)),
+        SQLITE_TEXT => Value::Text(
+            String::from_utf8_lossy(bytes(sqlite3_value_text(value) as *const c_void)).to_string(),
+        ),
+        SQLITE_BLOB => Value::Blob(bytes(sqlite3_value_blob(value)).to_vec()),
+        _ => Value::Null,
+    }
+}
+
+unsafe fn read_args(argc: c_int, argv: *mut *mut sqlite3_value) -> Vec<Value> {
+    (0..argc as usize)
+        .map(|index| read_value(*argv.add(index)))
+        .collect()
+}
+
+unsafe fn set_result(ctx: *mut sqlite3_context, result: FunctionResult<Value>) {
+    match result {
+        Ok(Value::Null) => sqlite3_result_null(ctx),
+        Ok(Value::Integer(i)) => sqlite3_result_int64(ctx, i),
+        Ok(Value::Real(f)) => sqlite3_result_double(ctx, f),
+        Ok(Value::Text(s)) => sqlite3_result_text(
+            ctx,
+            s.as_ptr() as *const c_char,
+            s.len() as c_int,
+            SQLITE_TRANSIENT(),
+        ),
+        Ok(Value::Blob(b)) => sqlite3_result_blob(
+            ctx,
+            b.as_ptr() as *const c_void,
+            b.len() as c_int,
+            SQLITE_TRANSIENT(),
+        ),
+        Err(err) => {
+            let message = err.to_string();
+            sqlite3_result_error(
+                ctx,
+                message.as_ptr() as *const c_char,
+                message.len() as c_int,
+            );
+        }
+    }
+}
+
+/// Run a callback and turn a panic into an error, panics can't unwind into SQLite
+fn catch_panic<T>(f: impl FnOnce() -> FunctionResult<T>) -> FunctionResult<T> {
+    panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|_| Err("bsqlite: user-defined function panicked".into()))
+}
+
+unsafe extern "C" fn drop_boxed<T>(data: *mut c_void) {
+    drop(Box::from_raw(data as *mut T));
+}
+
+fn c_name(name: &str) -> Result<CString, Error> {
+    CString::new(name).map_err(|_| Error::new(SQLITE_MISUSE, "Name contains a nul byte"))
+}
+
+// MARK: Scalar function
+unsafe extern "C" fn scalar_function<F, R>(
+    ctx: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) where
+    F: Fn(&[Value]) -> FunctionResult<R>,
+    R: Into<Value>,
+{
+    let f = &*(sqlite3_user_data(ctx) as *const F);
+    let args = read_args(argc, argv);
+    set_result(ctx, catch_panic(|| f(&args).map(Into::into)));
+}
+
+// MARK: Aggregate
+struct Aggregate<S, I, St, Fi> {
+    init: I,
+    step: St,
+    finalize: Fi,
+    _state: PhantomData<fn() -> S>,
+}
+
+unsafe extern "C" fn aggregate_step<S, I, St, Fi>(
+    ctx: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) where
+    I: Fn() -> S,
+    St: Fn(&mut S, &[Value]) -> FunctionResult<()>,
+{
+    let aggregate = &*(sqlite3_user_data(ctx) as *const Aggregate<S, I, St, Fi>);
+    // The aggregate context is zeroed memory that holds a pointer to the boxed state
+    let slot = sqlite3_aggregate_context(ctx, size_of::<*mut S>() as c_int) as *mut *mut S;
+    if slot.is_null() {
+        set_result(ctx, Err("bsqlite: can't allocate aggregate context".into()));
+        return;
+    }
+    let args = read_args(argc, argv);
+    let result = catch_panic(|| {
+        if (*slot).is_null() {
+            *slot = Box::into_raw(Box::new((aggregate.init)()));
+        }
+        (aggregate.step)(&mut **slot, &args)
+    });
+    if let Err(err) = result {
+        set_result(ctx, Err(err));
+    }
+}
+
+unsafe extern "C" fn aggregate_finalize<S, I, St, Fi, R>(ctx: *mut sqlite3_context)
+where
+    I: Fn() -> S,
+    Fi: Fn(S) -> FunctionResult<R>,
+    R: Into<Value>,
+{
+    let aggregate = &*(sqlite3_user_data(ctx) as *const Aggregate<S, I, St, Fi>);
+    let slot = sqlite3_aggregate_context(ctx, 0) as *mut *mut S;
+    let state = if slot.is_null() || (*slot).is_null() {
+        None
+    } else {
+        Some(*Box::from_raw(*slot))
+    };
+    set_result(
+        ctx,
+        catch_panic(|| {
+            // Aggregates over zero rows never step so start with a fresh state
+            let state = state.unwrap_or_else(|| (aggregate.init)());
+            (aggregate.finalize)(state).map(Into::into)
+        }),
+    );
+}
+
+// MARK: Collation
+unsafe extern "C" fn collation<F>(
+    data: *mut c_void,
+    a_len: c_int,
+    a: *const c_void,
+    b_len: c_int,
+    b: *const c_void,
+) -> c_int
+where
+    F: Fn(&str, &str) -> Ordering,
+{
+    let text = |data: *const c_void, len: c_int| {
+        if data.is_null() || len <= 0 {
+            String::new()
+        } else {
+            String::from_utf8_lossy(slice::from_raw_parts(data as *const u8, len as usize))
+                .to_string()
+        }
+    };
+    let f = &*(data as *const F);
+    let (a, b) = (text(a, a_len), text(b, b_len));
+    match panic::catch_unwind(AssertUnwindSafe(|| f(&a, &b))) {
+        Ok(Ordering::Less) => -1,
+        Ok(Ordering::Equal) | Err(_) => 0,
+        Ok(Ordering::Greater) => 1,
+    }
+}
+
+// MARK: Connection
+impl Connection {
+    /// Create or redefine a scalar SQL function, `n_args` -1 accepts any amount of arguments
+    ///
+    /// Deterministic functions always return the same result for the same arguments,
+    /// which allows SQLite to use them in indexes and optimize calls away.
+    ///
+    /// ```rs
+    /// db.create_scalar_function("unicode_lower", 1, true, |args| match &args[0] {
+    ///     Value::Text(text) => Ok(Value::Text(text.to_lowercase())),
+    ///     value => Ok(value.clone()),
+    /// })?;
+    /// ```
+    pub fn create_scalar_function<F, R>(
+        &self,
+        name: &str,
+        n_args: i32,
+        deterministic: bool,
+        f: F,
+    ) -> Result<(), Error>
+    where
+        F: Fn(&[Value]) -> FunctionResult<R> + Send + 'static,
+        R: Into<Value>,
+    {
+        let name = c_name(name)?;
+        let mut flags = SQLITE_UTF8;
+        if deterministic {
+            flags |= SQLITE_DETERMINISTIC;
+        }
+        // SQLite calls the destructor when registering fails
+        let result = unsafe {
+            sqlite3_create_function_v2(
+                self.raw(),
+                name.as_ptr(),
+                n_args,
+                flags,
+                Box::into_raw(Box::new(f)) as *mut c_void,
+                Some(scalar_function::<F, R>),
+                None,
+                None,
+                Some(drop_boxed::<F>),
+            )
+        };
+        if result != SQLITE_OK {
+            return Err(Error::from_code(self.raw(), result));
+        }
+        Ok(())
+    }
+
+    /// Create or redefine an aggregate SQL function, `n_args` -1 accepts any amount of arguments
+    ///
+    /// Every group starts with the state of `init`, `step` is called for every row
+    /// and `finalize` turns the state into the result.
+    ///
+    /// ```rs
+    /// db.create_aggregate(
+    ///     "product",
+    ///     1,
+    ///     || 1,
+    ///     |product, args| {
+    ///         *product *= i64::try_from(args[0].clone())?;
+    ///         Ok(())
+    ///     },
+    ///     Ok,
+    /// )?;
+    /// ```
+    pub fn create_aggregate<S, I, St, Fi, R>(
+        &self,
+        name: &str,
+        n_args: i32,
+        init: I,
+        step: St,
+        finalize: Fi,
+    ) -> Result<(), Error>
+    where
+        S: 'static,
+        I: Fn() -> S + Send + 'static,
+        St: Fn(&mut S, &[Value]) -> FunctionResult<()> + Send + 'static,
+        Fi: Fn(S) -> FunctionResult<R> + Send + 'static,
+        R: Into<Value>,
+    {
+        let name = c_name(name)?;
+        let aggregate = Aggregate::<S, I, St, Fi> {
+            init,
+            step,
+            finalize,
+            _state: PhantomData,
+        };
+        // SQLite calls the destructor when registering fails
+        let result = unsafe {
+            sqlite3_create_function_v2(
+                self.raw(),
+                name.as_ptr(),
+                n_args,
+                SQLITE_UTF8,
+                Box::into_raw(Box::new(aggregate)) as *mut c_void,
+                None,
+                Some(aggregate_step::<S, I, St, Fi>),
+                Some(aggregate_finalize::<S, I, St, Fi, R>),
+                Some(drop_boxed::<Aggregate<S, I, St, Fi>>),
+            )
+        };
+        if result != SQLITE_OK {
+            return Err(Error::from_code(self.raw(), result));
+        }
+        Ok(())
+    }
+
+    /// Create or redefine a collation that can be used with `COLLATE name`
+    ///
+    /// ```rs
+    /// db.create_collation("nocase_unicode", |a, b| a.to_lowercase().cmp(&b.to_lowercase()))?;
+    /// ```
+    pub fn create_collation<F>(&self, name: &str, f: F) -> Result<(), Error>
+    where
+        F: Fn(&str, &str) -> Ordering + Send + 'static,
+    {
+        let name = c_name(name)?;
+        let data = Box::into_raw(Box::new(f));
+        let result = unsafe {
+            sqlite3_create_collation_v2(
+                self.raw(),
+                name.as_ptr(),
+                SQLITE_UTF8,
+                data as *mut c_void,
+                Some(collation::<F>),
+                Some(drop_boxed::<F>),
+            )
+        };
+        if result != SQLITE_OK {
+            // SQLite doesn't call the destructor when registering fails
+            unsafe { drop(Box::from_raw(data)) };
+            return Err(Error::from_code(self.raw(), result));
+        }
+        Ok(())
+    }
+}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_database() -> Connection {
+        let db = Connection::open_memory().unwrap();
+        db.execute_batch(
+            "CREATE TABLE persons (name TEXT NOT NULL, age INTEGER NOT NULL) STRICT;
+            INSERT INTO persons (name, age) VALUES ('Émile', 30), ('bob', 40), ('Alice', 20);",
+        )
+        .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_scalar_function() {
+        let db = test_database();
+        db.create_scalar_function("unicode_lower", 1, true, |args| match &args[0] {
+            Value::Text(text) => Ok(Value::Text(text.to_lowercase())),
+            value => Ok(value.clone()),
+        })
+        .unwrap();
+        assert_eq!(
+            db.query_some::<String>("SELECT unicode_lower('ÉMILE')", ()),
+            "émile"
+        );
+        assert_eq!(
+            db.query_some::<Option<i64>>("SELECT unicode_lower(NULL)", ()),
+            None
+        );
+
+        // Variadic function with all value types
+        db.create_scalar_function("count_args", -1, true, |args| Ok(args.len() as i64))
+            .unwrap();
+        assert_eq!(
+            db.query_some::<i64>("SELECT count_args(1, 2.5, 'text', x'00ff', NULL)", ()),
+            5
+        );
+
+        // Wrong amount of arguments is an error
+        assert!(db.try_prepare::<i64>("SELECT unicode_lower(1, 2)").is_err());
+    }
+
+    #[test]
+    fn test_scalar_function_errors() {
+        let db = test_database();
+        db.create_scalar_function("regexp", 2, true, |args| {
+            let pattern = String::try_from(args[0].clone())?;
+            let text = String::try_from(args[1].clone())?;
+            if pattern.is_empty() {
+                return Err("Empty pattern".into());
+            }
+            Ok(text.contains(&pattern) as i64)
+        })
+        .unwrap();
+        assert_eq!(
+            db.query_some::<i64>("SELECT COUNT(name) FROM persons WHERE name REGEXP 'li'", ()),
+            1
+        );
+
+        let err = db
+            .try_query::<i64>("SELECT 'abc' REGEXP ''", ())
+            .unwrap()
+            .try_next()
+            .unwrap_err();
+        assert_eq!(err.message(), "Empty pattern");
+        let err = db
+            .try_query::<i64>("SELECT 1 REGEXP 'a'", ())
+            .unwrap()
+            .try_next()
+            .unwrap_err();
+        assert_eq!(
+            err.message(),
+            "Value error: can't convert INTEGER to String"
+        );
+
+        db.create_scalar_function("explode", 0, false, |_| -> FunctionResult<i64> {
+            panic!("Boom")
+        })
+        .unwrap();
+        let err = db
+            .try_query::<i64>("SELECT explode()", ())
+            .unwrap()
+            .try_next()
+            .unwrap_err();
+        assert!(err.message().contains("panicked"));
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let db = test_database();
+        db.create_aggregate(
+            "product",
+            1,
+            || 1,
+            |product, args| {
+                *product *= i64::try_from(args[0].clone())?;
+                Ok(())
+            },
+            Ok,
+        )
+        .unwrap();
+        assert_eq!(
+            db.query_some::<i64>("SELECT product(age) FROM persons", ()),
+            30 * 40 * 20
+        );
+        assert_eq!(
+            db.query_some::<i64>("SELECT product(age) FROM persons WHERE age > 100", ()),
+            1
+        );
+
+        // State is kept per group
+        db.create_aggregate(
+            "join_names",
+            1,
+            Vec::new,
+            |names: &mut Vec<String>, args| {
+                names.push(String::try_from(args[0].clone())?);
+                Ok(())
+            },
+            |names| Ok(names.join(",")),
+        )
+        .unwrap();
+        let rows = db
+            .query::<(i64, String)>(
+                "SELECT age > 25, join_names(name) FROM (SELECT * FROM persons ORDER BY name) GROUP BY age > 25 ORDER BY 1",
+                (),
+            )
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            vec![(0, "Alice".to_string()), (1, "bob,Émile".to_string())]
+        );
+
+        let err = db
+            .try_query::<String>("SELECT join_names(age) FROM persons", ())
+            .unwrap()
+            .try_next()
+            .unwrap_err();
+        assert_eq!(
+            err.message(),
+            "Value error: can't convert INTEGER to String"
+        );
+    }
+
+    #[test]
+    fn test_collation() {
+        let db = test_database();
+        db.create_collation("unicode_nocase", |a, b| {
+            a.to_lowercase().cmp(&b.to_lowercase())
+        })
+        .unwrap();
+        let names = db
+            .query::<String>(
+                "SELECT name FROM persons ORDER BY name COLLATE unicode_nocase",
+                (),
+            )
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Alice", "bob", "Émile"]);
+        assert_eq!(
+            db.query_some::<i64>(
+                "SELECT COUNT(name) FROM persons WHERE name = 'émile' COLLATE unicode_nocase",
+                ()
+            ),
+            1
+        );
+    }
+}
@@ -0,0 +1,391 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::ffi::{c_char, c_int, c_void, CStr};
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use libsqlite3_sys::*;
+
+use crate::Connection;
+
+type UpdateHookFn = Box<dyn FnMut(UpdateOperation, &str, i64) + Send>;
+type CommitHookFn = Box<dyn FnMut() -> bool + Send>;
+type RollbackHookFn = Box<dyn FnMut() + Send>;
+
+// MARK: Change
+/// A row change operation
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UpdateOperation {
+    /// A row is inserted
+    Insert,
+    /// A row is updated
+    Update,
+    /// A row is deleted
+    Delete,
+}
+
+/// A committed row change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// Operation
+    pub operation: UpdateOperation,
+    /// Table name
+    pub table: String,
+    /// Row id of the changed row
+    pub rowid: i64,
+}
+
+// MARK: Hooks
+#[derive(Default)]
+pub(crate) struct Hooks {
+    update: Option<UpdateHookFn>,
+    commit: Option<CommitHookFn>,
+    rollback: Option<RollbackHookFn>,
+    feeds: Vec<Sender<Change>>,
+    pending: Vec<Change>,
+    committed: Vec<Change>,
+}
+
+impl Hooks {
+    /// Register the callbacks once when the connection is opened, so the hooks lock is
+    /// never held while calling into SQLite, which takes the connection lock
+    pub(crate) fn install(connection: &Connection) {
+        let arg = connection.hooks() as *const Mutex<Hooks> as *mut c_void;
+        unsafe {
+            sqlite3_update_hook(connection.raw(), Some(update_hook), arg);
+            sqlite3_commit_hook(connection.raw(), Some(commit_hook), arg);
+            sqlite3_rollback_hook(connection.raw(), Some(rollback_hook), arg);
+        }
+    }
+
+    /// Unregister the callbacks
+    pub(crate) fn uninstall(db: *mut sqlite3) {
+        unsafe {
+            sqlite3_update_hook(db, None, ptr::null_mut());
+            sqlite3_commit_hook(db, None, ptr::null_mut());
+            sqlite3_rollback_hook(db, None, ptr::null_mut());
+        }
+    }
+
+    /// Send the changes of a committed transaction to the feeds once the connection
+    /// is back in autocommit mode, because the commit can still fail after the commit hook
+    pub(crate) fn send_committed(hooks: &Mutex<Hooks>, db: *mut sqlite3) {
+        if unsafe { sqlite3_get_autocommit(db) } == 0 {
+            return;
+        }
+        let mut hooks = hooks.lock().unwrap_or_else(PoisonError::into_inner);
+        if hooks.committed.is_empty() {
+            return;
+        }
+        // Drop feeds of which the receiver is gone
+        let committed = mem::take(&mut hooks.committed);
+        hooks.feeds.retain(|feed| {
+            committed
+                .iter()
+                .all(|change| feed.send(change.clone()).is_ok())
+        });
+    }
+}
+
+/// Lock the hooks from a callback, a panicking hook must not disable the other hooks
+unsafe fn lock_hooks<'a>(arg: *mut c_void) -> MutexGuard<'a, Hooks> {
+    (*(arg as *const Mutex<Hooks>))
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+unsafe extern "C" fn update_hook(
+    arg: *mut c_void,
+    operation: c_int,
+    _database: *const c_char,
+    table: *const c_char,
+    rowid: i64,
+) {
+    let operation = match operation {
+        SQLITE_INSERT => UpdateOperation::Insert,
+        SQLITE_UPDATE => UpdateOperation::Update,
+        SQLITE_DELETE => UpdateOperation::Delete,
+        _ => return,
+    };
+    let table = CStr::from_ptr(table).to_string_lossy();
+    _ = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut hooks = lock_hooks(arg);
+        if let Some(update) = &mut hooks.update {
+            update(operation, &table, rowid);
+        }
+        if !hooks.feeds.is_empty() {
+            hooks.pending.push(Change {
+                operation,
+                table: table.to_string(),
+                rowid,
+            });
+        }
+    }));
+}
+
+unsafe extern "C" fn commit_hook(arg: *mut c_void) -> c_int {
+    let allow = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut hooks = lock_hooks(arg);
+        let allow = match &mut hooks.commit {
+            Some(commit) => commit(),
+            None => true,
+        };
+        if allow {
+            let pending = mem::take(&mut hooks.pending);
+            hooks.committed.extend(pending);
+        }
+        allow
+    }));
+    // A non zero result turns the commit into a rollback
+    if allow.unwrap_or(false) {
+        0
+    } else {
+        1
+    }
+}
+
+unsafe extern "C" fn rollback_hook(arg: *mut c_void) {
+    _ = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut hooks = lock_hooks(arg);
+        hooks.pending.clear();
+        hooks.committed.clear();
+        if let Some(rollback) = &mut hooks.rollback {
+            rollback();
+        }
+    }));
+}
+
+// MARK: Connection
+impl Connection {
+    /// Set hook that is called for every inserted, updated or deleted row
+    ///
+    /// The hook is called with the operation, table name and row id. Changes to
+    /// `WITHOUT ROWID` tables are not reported. The hook must not use the connection.
+    pub fn on_update(&self, f: impl FnMut(UpdateOperation, &str, i64) + Send + 'static) {
+        self.hooks().lock().expect("Can't lock").update = Some(Box::new(f));
+    }
+
+    /// Set hook that is called before a transaction commits, return false to turn
+    /// the commit into a rollback. The hook must not use the connection.
+    pub fn on_commit(&self, f: impl FnMut() -> bool + Send + 'static) {
+        self.hooks().lock().expect("Can't lock").commit = Some(Box::new(f));
+    }
+
+    /// Set hook that is called when a transaction is rolled back
+    pub fn on_rollback(&self, f: impl FnMut() + Send + 'static) {
+        self.hooks().lock().expect("Can't lock").rollback = Some(Box::new(f));
+    }
+
+    /// Create a change feed that receives the row changes of every committed transaction
+    ///
+    /// Only changes made through this connection are reported, so create the feed on
+    /// the writer connection of a [crate::Pool] to receive all changes. Changes that are
+    /// rolled back to a savepoint are still reported when the transaction commits.
+    ///
+    /// ```rs
+    /// let changes = pool.writer()?.change_feed();
+    /// thread::spawn(move || {
+    ///     for change in changes {
+    ///         println!("{:?} {} {}", change.operation, change.table, change.rowid);
+    ///     }
+    /// });
+    /// ```
+    pub fn change_feed(&self) -> Receiver<Change> {
+        let (sender, receiver) = mpsc::channel();
+        self.hooks().lock().expect("Can't lock").feeds.push(sender);
+        receiver
+    }
+
+    /// Remove all hooks and close all change feeds
+    pub fn clear_hooks(&self) {
+        *self.hooks().lock().expect("Can't lock") = Hooks::default();
+    }
+}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::*;
+
+    fn test_database() -> Connection {
+        let db = Connection::open_memory().unwrap();
+        db.execute(
+            "CREATE TABLE persons (id INTEGER PRIMARY KEY, name TEXT NOT NULL) STRICT",
+            (),
+        );
+        db
+    }
+
+    #[test]
+    fn test_update_hook() {
+        let db = test_database();
+        let updates = Arc::new(Mutex::new(Vec::new()));
+        {
+            let updates = updates.clone();
+            db.on_update(move |operation, table, rowid| {
+                updates
+                    .lock()
+                    .unwrap()
+                    .push((operation, table.to_string(), rowid))
+            });
+        }
+        db.execute("INSERT INTO persons (name) VALUES ('Alice'), ('Bob')", ());
+        db.execute("UPDATE persons SET name = 'Charlie' WHERE id = 2", ());
+        db.execute("DELETE FROM persons WHERE id = 1", ());
+        assert_eq!(
+            *updates.lock().unwrap(),
+            vec![
+                (UpdateOperation::Insert, "persons".to_string(), 1),
+                (UpdateOperation::Insert, "persons".to_string(), 2),
+                (UpdateOperation::Update, "persons".to_string(), 2),
+                (UpdateOperation::Delete, "persons".to_string(), 1),
+            ]
+        );
+
+        db.clear_hooks();
+        db.execute("INSERT INTO persons (name) VALUES ('Dave')", ());
+        assert_eq!(updates.lock().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_commit_and_rollback_hooks() {
+        let db = test_database();
+        let rollbacks = Arc::new(Mutex::new(0));
+        {
+            let rollbacks = rollbacks.clone();
+            db.on_rollback(move || *rollbacks.lock().unwrap() += 1);
+        }
+        db.on_commit(|| true);
+        db.execute("INSERT INTO persons (name) VALUES ('Alice')", ());
+
+        let tx = db.transaction().unwrap();
+        tx.execute("INSERT INTO persons (name) VALUES ('Bob')", ());
+        drop(tx);
+        assert_eq!(*rollbacks.lock().unwrap(), 1);
+
+        // A commit hook can veto the commit
+        db.on_commit(|| false);
+        assert!(db
+            .try_execute("INSERT INTO persons (name) VALUES ('Charlie')", ())
+            .is_err());
+        assert_eq!(*rollbacks.lock().unwrap(), 2);
+        assert_eq!(db.query_some::<i64>("SELECT COUNT(id) FROM persons", ()), 1);
+    }
+
+    #[test]
+    fn test_set_hooks_while_writing() {
+        let db = test_database();
+        let handle = thread::spawn({
+            let db = db.clone();
+            move || {
+                for _ in 0..1000 {
+                    db.on_update(|_, _, _| {});
+                    db.on_commit(|| true);
+                }
+            }
+        });
+        for _ in 0..1000 {
+            db.execute("INSERT INTO persons (name) VALUES ('Alice')", ());
+        }
+        handle.join().unwrap();
+        assert_eq!(
+            db.query_some::<i64>("SELECT COUNT(id) FROM persons", ()),
+            1000
+        );
+    }
+
+    #[test]
+    fn test_change_feed() {
+        let db = test_database();
+        let changes = db.change_feed();
+
+        db.execute("INSERT INTO persons (name) VALUES ('Alice')", ());
+        let tx = db.transaction().unwrap();
+        tx.execute("INSERT INTO persons (name) VALUES ('Bob')", ());
+        assert!(changes.try_recv().is_ok());
+        assert!(changes.try_recv().is_err());
+        tx.rollback().unwrap();
+
+        let tx = db.transaction().unwrap();
+        tx.execute("UPDATE persons SET name = 'Charlie' WHERE id = 1", ());
+        tx.execute("INSERT INTO persons (name) VALUES ('Dave')", ());
+        tx.commit().unwrap();
+        assert_eq!(
+            changes.try_iter().collect::<Vec<_>>(),
+            vec![
+                Change {
+                    operation: UpdateOperation::Update,
+                    table: "persons".to_string(),
+                    rowid: 1,
+                },
+                Change {
+                    operation: UpdateOperation::Insert,
+                    table: "persons".to_string(),
+                    rowid: 2,
+                },
+            ]
+        );
+
+        // Vetoed commits are not sent
+        db.on_commit(|| false);
+        assert!(db
+            .try_execute("DELETE FROM persons WHERE id = 1", ())
+            .is_err());
+        assert!(changes.try_recv().is_err());
+
+        // Feeds are removed when the receiver is dropped
+        db.on_commit(|| true);
+        drop(changes);
+        db.execute("INSERT INTO persons (name) VALUES ('Eve')", ());
+        assert!(db.hooks().lock().unwrap().feeds.is_empty());
+
+        // Feeds are closed when the hooks are cleared
+        let changes = db.change_feed();
+        db.clear_hooks();
+        assert!(changes.recv().is_err());
+    }
+
+    #[test]
+    fn test_change_feed_failed_commit() {
+        let path =
+            std::env::temp_dir().join(format!("bsqlite_failed_commit_{}.db", std::process::id()));
+        _ = std::fs::remove_file(&path);
+        let db = Connection::open(&path).unwrap();
+        db.execute(
+            "CREATE TABLE persons (id INTEGER PRIMARY KEY, name TEXT NOT NULL) STRICT",
+            (),
+        );
+        let changes = db.change_feed();
+
+        // A commit that fails because an other connection is reading is not sent
+        let other = Connection::open(&path).unwrap();
+        let reader = other.transaction().unwrap();
+        reader.query_some::<i64>("SELECT COUNT(id) FROM persons", ());
+        let tx = db.transaction().unwrap();
+        tx.execute("INSERT INTO persons (name) VALUES ('Alice')", ());
+        let err = tx.try_execute("COMMIT", ()).unwrap_err();
+        assert_eq!(err.kind(), crate::ErrorKind::Busy);
+        assert!(changes.try_recv().is_err());
+        tx.rollback().unwrap();
+        drop(reader);
+        assert!(changes.try_recv().is_err());
+
+        // Changes are sent once the commit succeeds
+        db.execute("INSERT INTO persons (name) VALUES ('Bob')", ());
+        assert_eq!(changes.try_recv().unwrap().rowid, 1);
+        assert!(changes.try_recv().is_err());
+
+        drop((db, other));
+        _ = std::fs::remove_file(&path);
+    }
+}
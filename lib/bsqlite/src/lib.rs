@@ -6,17 +6,41 @@
 
 #![doc = include_str!("../README.md")]
 
-pub use crate::bind::Bind;
+pub use crate::backup::BackupProgress;
+pub use crate::bind::{Bind, BindValue};
+pub use crate::blob::Blob;
 pub use crate::connection::{Connection, ConnectionError};
+pub use crate::error::{ConstraintKind, Error, ErrorKind, Result};
 pub use crate::from_row::FromRow;
+pub use crate::function::FunctionResult;
+pub use crate::hook::{Change, UpdateOperation};
+pub use crate::pool::{Pool, PoolBuilder, PoolError, PooledConnection, SetupFn};
 pub use crate::statement::{RawStatement, Statement};
-pub use crate::value::{Value, ValueError};
+pub use crate::transaction::{Transaction, TransactionMode};
+#[cfg(feature = "serde_json")]
+pub use crate::value::Json;
+pub use crate::value::{Value, ValueError, ValueRef};
 
+mod backup;
 mod bind;
+mod blob;
+mod cache;
 mod connection;
+mod error;
 mod from_row;
+mod function;
+mod hook;
+mod pool;
 mod statement;
+mod transaction;
 mod value;
 
+pub mod fts;
+pub mod migrate;
+pub mod query;
+
 #[cfg(feature = "derive")]
 pub use bsqlite_derive::{FromRow, FromValue};
+
+#[cfg(feature = "checked")]
+pub use bsqlite_derive::query_checked;
@@ -0,0 +1,385 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Schema migrations runner
+//!
+//! Migrations are applied in order and tracked in the `bsqlite_migrations` table,
+//! already applied SQL migrations are verified by their checksum.
+//!
+//! ```rs
+//! use bsqlite::migrate::Migrator;
+//!
+//! let migrator = Migrator::new()
+//!     .sql("create_persons", include_str!("../migrations/0001_create_persons.sql"))
+//!     .closure("seed_persons", |tx| tx.try_execute("INSERT INTO persons (name) VALUES ('Alice')", ()));
+//! migrator.run(&db).expect("Can't run migrations");
+//! ```
+
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Formatter};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{Connection, Error, Transaction, TransactionMode};
+
+/// A closure migration
+pub type MigrationFn = fn(&Transaction<'_>) -> Result<(), Error>;
+
+// MARK: MigrateError
+/// A migration error
+#[derive(Debug)]
+pub enum MigrateError {
+    /// A database error
+    Database(Error),
+    /// An applied migration has a different name
+    NameMismatch {
+        /// Migration version
+        version: i64,
+        /// Expected migration name
+        expected: String,
+        /// Applied migration name
+        applied: String,
+    },
+    /// An applied migration has a different checksum
+    ChecksumMismatch {
+        /// Migration version
+        version: i64,
+        /// Migration name
+        name: String,
+    },
+    /// An applied migration is unknown
+    Unknown {
+        /// Migration version
+        version: i64,
+        /// Applied migration name
+        name: String,
+    },
+}
+
+impl Display for MigrateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrateError::Database(err) => write!(f, "Migration database error: {}", err),
+            MigrateError::NameMismatch {
+                version,
+                expected,
+                applied,
+            } => write!(
+                f,
+                "Migration {} should be named {} but {} is applied",
+                version, expected, applied
+            ),
+            MigrateError::ChecksumMismatch { version, name } => write!(
+                f,
+                "Migration {} {} is changed after it was applied",
+                version, name
+            ),
+            MigrateError::Unknown { version, name } => {
+                write!(f, "Migration {} {} is applied but unknown", version, name)
+            }
+        }
+    }
+}
+
+impl StdError for MigrateError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            MigrateError::Database(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for MigrateError {
+    fn from(err: Error) -> Self {
+        MigrateError::Database(err)
+    }
+}
+
+// MARK: Migration
+enum MigrationKind {
+    Sql(&'static str),
+    Closure(MigrationFn),
+}
+
+/// A migration
+pub struct Migration {
+    version: i64,
+    name: &'static str,
+    kind: MigrationKind,
+}
+
+impl Migration {
+    /// Get version
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+
+    /// Get name
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn checksum(&self) -> Option<i64> {
+        match self.kind {
+            MigrationKind::Sql(sql) => Some(fnv1a(sql.as_bytes()) as i64),
+            MigrationKind::Closure(_) => None,
+        }
+    }
+
+    fn apply(&self, tx: &Transaction<'_>) -> Result<(), Error> {
+        match self.kind {
+            MigrationKind::Sql(sql) => tx.execute_batch(sql),
+            MigrationKind::Closure(f) => f(tx),
+        }
+    }
+}
+
+// MARK: Migrator
+/// A migrator that applies migrations in order
+#[derive(Default)]
+pub struct Migrator {
+    migrations: Vec<Migration>,
+}
+
+impl Migrator {
+    /// Create new migrator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add SQL migration, the SQL can contain multiple statements
+    pub fn sql(self, name: &'static str, sql: &'static str) -> Self {
+        self.migration(name, MigrationKind::Sql(sql))
+    }
+
+    /// Add closure migration
+    pub fn closure(self, name: &'static str, f: MigrationFn) -> Self {
+        self.migration(name, MigrationKind::Closure(f))
+    }
+
+    fn migration(mut self, name: &'static str, kind: MigrationKind) -> Self {
+        self.migrations.push(Migration {
+            version: self.migrations.len() as i64 + 1,
+            name,
+            kind,
+        });
+        self
+    }
+
+    /// Get migrations
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// Verify applied migrations and list pending migrations without applying them
+    pub fn pending(&self, db: &Connection) -> Result<&[Migration], MigrateError> {
+        let applied = self.verify(db)?;
+        Ok(&self.migrations[applied..])
+    }
+
+    /// Verify applied migrations and apply pending migrations each in their own transaction
+    pub fn run(&self, db: &Connection) -> Result<&[Migration], MigrateError> {
+        create_migrations_table(db)?;
+        let applied = self.verify(db)?;
+        for migration in &self.migrations[applied..] {
+            let tx = db.transaction_with(TransactionMode::Immediate)?;
+            let is_applied = tx
+                .query_optional::<i64>(
+                    "SELECT version FROM bsqlite_migrations WHERE version = ?",
+                    migration.version,
+                )?
+                .is_some();
+            if is_applied {
+                continue;
+            }
+            migration.apply(&tx)?;
+            tx.try_execute(
+                "INSERT INTO bsqlite_migrations (version, name, checksum, applied_at) VALUES (?, ?, ?, ?)",
+                (
+                    migration.version,
+                    migration.name.to_string(),
+                    migration.checksum(),
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |duration| duration.as_secs() as i64),
+                ),
+            )?;
+            tx.commit()?;
+        }
+        Ok(&self.migrations[applied..])
+    }
+
+    /// Verify applied migrations and return the amount of applied migrations
+    fn verify(&self, db: &Connection) -> Result<usize, MigrateError> {
+        let has_table = db
+            .query_optional::<i64>(
+                "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'bsqlite_migrations'",
+                (),
+            )?
+            .is_some();
+        if !has_table {
+            return Ok(0);
+        }
+
+        let mut statement = db.try_query::<(i64, String, Option<i64>)>(
+            "SELECT version, name, checksum FROM bsqlite_migrations ORDER BY version",
+            (),
+        )?;
+        let mut applied = Vec::new();
+        while let Some(row) = statement.try_next()? {
+            applied.push(row);
+        }
+        for (version, name, checksum) in &applied {
+            let migration = self
+                .migrations
+                .get((*version - 1) as usize)
+                .ok_or_else(|| MigrateError::Unknown {
+                    version: *version,
+                    name: name.clone(),
+                })?;
+            if migration.name != name {
+                return Err(MigrateError::NameMismatch {
+                    version: *version,
+                    expected: migration.name.to_string(),
+                    applied: name.clone(),
+                });
+            }
+            if checksum.is_some() && migration.checksum() != *checksum {
+                return Err(MigrateError::ChecksumMismatch {
+                    version: *version,
+                    name: name.clone(),
+                });
+            }
+        }
+        Ok(applied.len())
+    }
+}
+
+fn create_migrations_table(db: &Connection) -> Result<(), Error> {
+    db.try_execute(
+        "CREATE TABLE IF NOT EXISTS bsqlite_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            checksum INTEGER,
+            applied_at INTEGER NOT NULL
+        ) STRICT",
+        (),
+    )
+}
+
+fn fnv1a(data: &[u8]) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    for byte in data {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CREATE_PERSONS: &str =
+        "CREATE TABLE persons (id INTEGER PRIMARY KEY, name TEXT NOT NULL) STRICT;
+        CREATE INDEX persons_name ON persons (name);";
+
+    fn migrator() -> Migrator {
+        Migrator::new()
+            .sql("create_persons", CREATE_PERSONS)
+            .closure("seed_persons", |tx| {
+                tx.try_execute("INSERT INTO persons (name) VALUES ('Alice')", ())
+            })
+    }
+
+    #[test]
+    fn test_migrate_run() {
+        let db = Connection::open_memory().unwrap();
+        let migrator = migrator();
+
+        let pending = migrator.pending(&db).unwrap();
+        assert_eq!(
+            pending.iter().map(|m| m.name()).collect::<Vec<_>>(),
+            vec!["create_persons", "seed_persons"]
+        );
+        assert_eq!(
+            db.query_some::<i64>(
+                "SELECT COUNT(name) FROM sqlite_master WHERE name = 'persons'",
+                ()
+            ),
+            0
+        );
+
+        let applied = migrator.run(&db).unwrap();
+        assert_eq!(applied.len(), 2);
+        assert_eq!(db.query_some::<i64>("SELECT COUNT(id) FROM persons", ()), 1);
+
+        // Running again applies nothing
+        assert!(migrator.pending(&db).unwrap().is_empty());
+        assert!(migrator.run(&db).unwrap().is_empty());
+        assert_eq!(db.query_some::<i64>("SELECT COUNT(id) FROM persons", ()), 1);
+
+        // New migrations are applied
+        let migrator = migrator.sql("add_age", "ALTER TABLE persons ADD COLUMN age INTEGER");
+        let applied = migrator.run(&db).unwrap();
+        assert_eq!(applied.len(), 1);
+        assert_eq!(applied[0].version(), 3);
+        db.execute("INSERT INTO persons (name, age) VALUES ('Bob', 40)", ());
+    }
+
+    #[test]
+    fn test_migrate_failed_migration_rolls_back() {
+        let db = Connection::open_memory().unwrap();
+        let migrator = Migrator::new().sql(
+            "broken",
+            "CREATE TABLE persons (id INTEGER PRIMARY KEY) STRICT; INSERT INTO unknown VALUES (1);",
+        );
+        assert!(matches!(migrator.run(&db), Err(MigrateError::Database(_))));
+        assert_eq!(
+            db.query_some::<i64>(
+                "SELECT COUNT(name) FROM sqlite_master WHERE name = 'persons'",
+                ()
+            ),
+            0
+        );
+        assert_eq!(migrator.pending(&db).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_migrate_verify() {
+        let db = Connection::open_memory().unwrap();
+        migrator().run(&db).unwrap();
+
+        // Changed SQL
+        let changed = Migrator::new()
+            .sql(
+                "create_persons",
+                "CREATE TABLE persons (id INTEGER PRIMARY KEY) STRICT",
+            )
+            .closure("seed_persons", |_| Ok(()));
+        assert!(matches!(
+            changed.run(&db),
+            Err(MigrateError::ChecksumMismatch { version: 1, .. })
+        ));
+
+        // Renamed migration
+        let renamed = Migrator::new()
+            .sql("create_persons", CREATE_PERSONS)
+            .closure("seed_people", |_| Ok(()));
+        assert!(matches!(
+            renamed.pending(&db),
+            Err(MigrateError::NameMismatch { version: 2, .. })
+        ));
+
+        // Missing migration
+        let missing = Migrator::new().sql("create_persons", CREATE_PERSONS);
+        assert!(matches!(
+            missing.run(&db),
+            Err(MigrateError::Unknown { version: 2, .. })
+        ));
+    }
+}
@@ -0,0 +1,349 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Formatter};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{Connection, ConnectionError, Error};
+
+/// A connection setup function
+pub type SetupFn = fn(&Connection) -> Result<(), Error>;
+
+// MARK: PoolError
+/// A pool error
+#[derive(Debug)]
+pub enum PoolError {
+    /// A connection can't be opened
+    Open(ConnectionError),
+    /// A connection can't be set up
+    Setup(Error),
+    /// No connection became available before the checkout timeout
+    Timeout,
+}
+
+impl Display for PoolError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PoolError::Open(err) => write!(f, "Pool error: {}", err),
+            PoolError::Setup(err) => write!(f, "Pool error: can't set up connection: {}", err),
+            PoolError::Timeout => write!(f, "Pool error: connection checkout timed out"),
+        }
+    }
+}
+
+impl StdError for PoolError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            PoolError::Open(err) => Some(err),
+            PoolError::Setup(err) => Some(err),
+            PoolError::Timeout => None,
+        }
+    }
+}
+
+// MARK: Slots
+struct Slots {
+    idle: Mutex<Vec<Connection>>,
+    available: Condvar,
+}
+
+impl Slots {
+    fn new(connections: Vec<Connection>) -> Arc<Self> {
+        Arc::new(Self {
+            idle: Mutex::new(connections),
+            available: Condvar::new(),
+        })
+    }
+
+    fn checkout(self: &Arc<Self>, timeout: Duration) -> Result<PooledConnection, PoolError> {
+        let deadline = Instant::now() + timeout;
+        let mut idle = self.idle.lock().expect("Can't lock");
+        loop {
+            if let Some(connection) = idle.pop() {
+                return Ok(PooledConnection {
+                    connection: Some(connection),
+                    slots: self.clone(),
+                });
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(PoolError::Timeout);
+            }
+            idle = self
+                .available
+                .wait_timeout(idle, deadline - now)
+                .expect("Can't lock")
+                .0;
+        }
+    }
+
+    fn checkin(&self, connection: Connection) {
+        self.idle.lock().expect("Can't lock").push(connection);
+        self.available.notify_one();
+    }
+}
+
+// MARK: PooledConnection
+/// A connection checked out from a pool that is returned when dropped
+pub struct PooledConnection {
+    connection: Option<Connection>,
+    slots: Arc<Slots>,
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Self::Target {
+        self.connection.as_ref().expect("Should be some")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            // Don't leak an unfinished transaction to the next user
+            if connection.in_transaction() {
+                _ = connection.try_execute("ROLLBACK", ());
+            }
+            self.slots.checkin(connection);
+        }
+    }
+}
+
+// MARK: PoolBuilder
+/// A pool builder
+pub struct PoolBuilder {
+    path: PathBuf,
+    readers: usize,
+    checkout_timeout: Duration,
+    setup: Option<SetupFn>,
+}
+
+impl PoolBuilder {
+    /// Set amount of reader connections, zero means readers share the writer connection
+    pub fn readers(mut self, readers: usize) -> Self {
+        self.readers = readers;
+        self
+    }
+
+    /// Set checkout timeout
+    pub fn checkout_timeout(mut self, timeout: Duration) -> Self {
+        self.checkout_timeout = timeout;
+        self
+    }
+
+    /// Set setup function that is run for every connection after the default pragmas
+    pub fn setup(mut self, setup: SetupFn) -> Self {
+        self.setup = Some(setup);
+        self
+    }
+
+    /// Open pool connections
+    pub fn build(self) -> Result<Pool, PoolError> {
+        let is_memory = self.path == Path::new(":memory:");
+        let open = |read_only: bool| -> Result<Connection, PoolError> {
+            let connection = Connection::open(&self.path).map_err(PoolError::Open)?;
+            if !is_memory {
+                connection.enable_wal_logging();
+                connection.apply_various_performance_settings();
+            }
+            if let Some(setup) = self.setup {
+                setup(&connection).map_err(PoolError::Setup)?;
+            }
+            if read_only {
+                connection
+                    .try_execute("PRAGMA query_only = ON", ())
+                    .map_err(PoolError::Setup)?;
+            }
+            Ok(connection)
+        };
+
+        // Every memory database connection is its own database so only use a writer
+        let writer = open(false)?;
+        let readers = if self.readers > 0 && !is_memory {
+            let connections = (0..self.readers)
+                .map(|_| open(true))
+                .collect::<Result<Vec<_>, _>>()?;
+            Some(Slots::new(connections))
+        } else {
+            None
+        };
+        Ok(Pool(Arc::new(InnerPool {
+            writer: Slots::new(vec![writer]),
+            readers,
+            checkout_timeout: self.checkout_timeout,
+        })))
+    }
+}
+
+// MARK: Pool
+struct InnerPool {
+    writer: Arc<Slots>,
+    readers: Option<Arc<Slots>>,
+    checkout_timeout: Duration,
+}
+
+/// A connection pool with a single writer connection and multiple reader connections
+///
+/// The connections use Write-Ahead Logging so readers don't block the writer.
+#[derive(Clone)]
+pub struct Pool(Arc<InnerPool>);
+
+impl Pool {
+    /// Create new pool builder with 4 readers and a checkout timeout of 5 seconds
+    pub fn builder(path: impl AsRef<Path>) -> PoolBuilder {
+        PoolBuilder {
+            path: path.as_ref().to_path_buf(),
+            readers: 4,
+            checkout_timeout: Duration::from_secs(5),
+            setup: None,
+        }
+    }
+
+    /// Open pool with default settings
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, PoolError> {
+        Self::builder(path).build()
+    }
+
+    /// Open pool with a single memory database connection
+    pub fn open_memory() -> Result<Self, PoolError> {
+        Self::builder(":memory:").build()
+    }
+
+    /// Checkout the writer connection
+    pub fn writer(&self) -> Result<PooledConnection, PoolError> {
+        self.0.writer.checkout(self.0.checkout_timeout)
+    }
+
+    /// Checkout a read only connection
+    pub fn reader(&self) -> Result<PooledConnection, PoolError> {
+        self.0
+            .readers
+            .as_ref()
+            .unwrap_or(&self.0.writer)
+            .checkout(self.0.checkout_timeout)
+    }
+}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "bsqlite_pool_{}_{}.db",
+                name,
+                std::process::id()
+            ));
+            _ = std::fs::remove_file(&path);
+            Self(path)
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            for suffix in ["", "-wal", "-shm"] {
+                let mut path = self.0.clone().into_os_string();
+                path.push(suffix);
+                _ = std::fs::remove_file(path);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pool_writer_and_readers() {
+        let path = TempPath::new("readers");
+        let pool = Pool::builder(&path.0)
+            .readers(2)
+            .setup(|connection| connection.try_execute("PRAGMA foreign_keys = ON", ()))
+            .build()
+            .unwrap();
+
+        let writer = pool.writer().unwrap();
+        writer.execute("CREATE TABLE persons (name TEXT NOT NULL) STRICT", ());
+        writer.execute("INSERT INTO persons (name) VALUES ('Alice')", ());
+        drop(writer);
+
+        // Readers see committed writes and can't write
+        let reader = pool.reader().unwrap();
+        assert_eq!(
+            reader.query_some::<i64>("SELECT COUNT(name) FROM persons", ()),
+            1
+        );
+        assert!(reader
+            .try_execute("INSERT INTO persons (name) VALUES ('Bob')", ())
+            .is_err());
+        assert_eq!(reader.query_some::<i64>("PRAGMA foreign_keys", ()), 1);
+
+        // Readers can read while the writer has an open transaction
+        let writer = pool.writer().unwrap();
+        let tx = writer.transaction().unwrap();
+        tx.execute("INSERT INTO persons (name) VALUES ('Bob')", ());
+        assert_eq!(
+            reader.query_some::<i64>("SELECT COUNT(name) FROM persons", ()),
+            1
+        );
+        tx.commit().unwrap();
+        assert_eq!(
+            reader.query_some::<i64>("SELECT COUNT(name) FROM persons", ()),
+            2
+        );
+    }
+
+    #[test]
+    fn test_pool_checkout_timeout() {
+        let path = TempPath::new("timeout");
+        let pool = Pool::builder(&path.0)
+            .readers(1)
+            .checkout_timeout(Duration::from_millis(50))
+            .build()
+            .unwrap();
+
+        let writer = pool.writer().unwrap();
+        assert!(matches!(pool.writer(), Err(PoolError::Timeout)));
+        let reader = pool.reader().unwrap();
+        assert!(matches!(pool.reader(), Err(PoolError::Timeout)));
+        drop(reader);
+        assert!(pool.reader().is_ok());
+
+        // A waiting checkout gets the connection when it is returned
+        let handle = {
+            let pool = pool.clone();
+            thread::spawn(move || pool.writer().is_ok())
+        };
+        thread::sleep(Duration::from_millis(10));
+        drop(writer);
+        assert!(handle.join().unwrap());
+    }
+
+    #[test]
+    fn test_pool_rollback_unfinished_transaction() {
+        let pool = Pool::open_memory().unwrap();
+        let writer = pool.writer().unwrap();
+        writer.execute("CREATE TABLE persons (name TEXT NOT NULL) STRICT", ());
+        writer.execute("BEGIN", ());
+        writer.execute("INSERT INTO persons (name) VALUES ('Alice')", ());
+        drop(writer);
+
+        // Memory pool readers share the writer connection
+        let reader = pool.reader().unwrap();
+        assert!(!reader.in_transaction());
+        assert_eq!(
+            reader.query_some::<i64>("SELECT COUNT(name) FROM persons", ()),
+            0
+        );
+    }
+}
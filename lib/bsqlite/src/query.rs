@@ -0,0 +1,471 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Query builder for dynamic filters and pagination
+//!
+//! The builder produces SQL text with `?` placeholders and the values to bind in order.
+//!
+//! ```rs
+//! use bsqlite::query::Select;
+//!
+//! let select = Select::from("persons")
+//!     .columns(Person::columns())
+//!     .filter_contains("name", &search)
+//!     .filter("age >= ?", 18)
+//!     .order_by("name")
+//!     .page(page, 20);
+//! let page = select.fetch_page::<Person>(&db)?;
+//! println!("{} of {}", page.data.len(), page.total);
+//! ```
+
+use crate::{BindValue, Connection, Error, FromRow, Result, Value};
+
+// MARK: IntoValues
+/// A trait for converting parameters into a list of values
+pub trait IntoValues {
+    /// Convert into values
+    fn into_values(self) -> Result<Vec<Value>>;
+}
+
+impl IntoValues for () {
+    fn into_values(self) -> Result<Vec<Value>> {
+        Ok(Vec::new())
+    }
+}
+
+impl IntoValues for Vec<Value> {
+    fn into_values(self) -> Result<Vec<Value>> {
+        Ok(self)
+    }
+}
+
+impl<T: BindValue> IntoValues for T {
+    fn into_values(self) -> Result<Vec<Value>> {
+        Ok(vec![self.into_value()?])
+    }
+}
+
+macro_rules! impl_into_values_for_tuple {
+    ($($n:tt: $t:ident),*) => (
+        impl<$($t,)*> IntoValues for ($($t,)*)
+        where
+            $($t: BindValue,)+
+        {
+            fn into_values(self) -> Result<Vec<Value>> {
+                Ok(vec![$( self.$n.into_value()?, )*])
+            }
+        }
+    );
+}
+impl_into_values_for_tuple!(0: A);
+impl_into_values_for_tuple!(0: A, 1: B);
+impl_into_values_for_tuple!(0: A, 1: B, 2: C);
+impl_into_values_for_tuple!(0: A, 1: B, 2: C, 3: D);
+impl_into_values_for_tuple!(0: A, 1: B, 2: C, 3: D, 4: E);
+impl_into_values_for_tuple!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F);
+impl_into_values_for_tuple!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G);
+impl_into_values_for_tuple!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H);
+
+/// Escape `\`, `%` and `_` in text so it can be used in a `LIKE ... ESCAPE '\'` pattern
+pub fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// MARK: Page
+/// A page of rows with the total amount of matching rows
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    /// Rows
+    pub data: Vec<T>,
+    /// Total amount of rows without limit, offset and keyset
+    pub total: i64,
+}
+
+// MARK: Select
+/// A select query builder, value conversion errors are returned when the query is run
+#[derive(Debug, Clone)]
+pub struct Select {
+    table: String,
+    columns: String,
+    filters: Vec<String>,
+    values: Vec<Value>,
+    keyset: Option<(String, Vec<Value>)>,
+    order_by: Vec<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    error: Option<Error>,
+}
+
+impl Select {
+    /// Create select query builder for a table that selects all columns
+    pub fn from(table: impl Into<String>) -> Self {
+        Self {
+            table: table.into(),
+            columns: "*".to_string(),
+            filters: Vec::new(),
+            values: Vec::new(),
+            keyset: None,
+            order_by: Vec::new(),
+            limit: None,
+            offset: None,
+            error: None,
+        }
+    }
+
+    /// Set selected columns, for example from [FromRow] `columns()`
+    pub fn columns(mut self, columns: impl Into<String>) -> Self {
+        self.columns = columns.into();
+        self
+    }
+
+    /// Add filter condition with `?` placeholders and their values, filters are combined with `AND`
+    pub fn filter(mut self, condition: impl Into<String>, values: impl IntoValues) -> Self {
+        match values.into_values() {
+            Ok(values) => {
+                self.filters.push(condition.into());
+                self.values.extend(values);
+                self
+            }
+            Err(err) => self.with_error(err),
+        }
+    }
+
+    /// Add filter that matches when the column contains the text
+    pub fn filter_contains(self, column: &str, text: &str) -> Self {
+        self.filter(
+            format!("{} LIKE ? ESCAPE '\\'", column),
+            format!("%{}%", escape_like(text)),
+        )
+    }
+
+    /// Add filter that matches when the column starts with the text
+    pub fn filter_starts_with(self, column: &str, text: &str) -> Self {
+        self.filter(
+            format!("{} LIKE ? ESCAPE '\\'", column),
+            format!("{}%", escape_like(text)),
+        )
+    }
+
+    /// Add filter that matches when the column is one of the values
+    pub fn filter_in<T: BindValue>(
+        self,
+        column: &str,
+        values: impl IntoIterator<Item = T>,
+    ) -> Self {
+        match values
+            .into_iter()
+            .map(BindValue::into_value)
+            .collect::<Result<Vec<_>>>()
+        {
+            Ok(values) => {
+                let placeholders = vec!["?"; values.len()].join(", ");
+                self.filter(format!("{} IN ({})", column, placeholders), values)
+            }
+            Err(err) => self.with_error(err),
+        }
+    }
+
+    /// Add order by term, for example `name` or `created_at DESC`
+    pub fn order_by(mut self, term: impl Into<String>) -> Self {
+        self.order_by.push(term.into());
+        self
+    }
+
+    /// Set limit
+    pub fn limit(mut self, limit: i64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Set offset
+    pub fn offset(mut self, offset: i64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Set limit and offset for a page, the first page is 1
+    pub fn page(self, page: i64, limit: i64) -> Self {
+        self.limit(limit).offset((page.max(1) - 1) * limit)
+    }
+
+    /// Select rows after the row with the given key values for keyset pagination
+    ///
+    /// The columns should be the columns of an ascending order by with a unique last column,
+    /// for example `.order_by("created_at").order_by("id").after(&["created_at", "id"], (created_at, id))`.
+    pub fn after(self, columns: &[&str], values: impl IntoValues) -> Self {
+        self.keyset(columns, ">", values)
+    }
+
+    /// Select rows before the row with the given key values for descending keyset pagination
+    pub fn before(self, columns: &[&str], values: impl IntoValues) -> Self {
+        self.keyset(columns, "<", values)
+    }
+
+    fn keyset(mut self, columns: &[&str], operator: &str, values: impl IntoValues) -> Self {
+        let values = match values.into_values() {
+            Ok(values) => values,
+            Err(err) => return self.with_error(err),
+        };
+        assert_eq!(
+            columns.len(),
+            values.len(),
+            "Keyset columns and values should have the same length"
+        );
+        let placeholders = vec!["?"; values.len()].join(", ");
+        self.keyset = Some((
+            format!("({}) {} ({})", columns.join(", "), operator, placeholders),
+            values,
+        ));
+        self
+    }
+
+    fn with_error(mut self, err: Error) -> Self {
+        self.error.get_or_insert(err);
+        self
+    }
+
+    fn check_error(&self) -> Result<()> {
+        match &self.error {
+            Some(err) => Err(err.clone()),
+            None => Ok(()),
+        }
+    }
+
+    fn where_clause(&self, keyset: bool) -> (String, Vec<Value>) {
+        let mut conditions = self.filters.clone();
+        let mut values = self.values.clone();
+        if let (true, Some((condition, keyset_values))) = (keyset, &self.keyset) {
+            conditions.push(condition.clone());
+            values.extend(keyset_values.iter().cloned());
+        }
+        let sql = match conditions.len() {
+            0 => String::new(),
+            1 => format!(" WHERE {}", conditions[0]),
+            _ => format!(
+                " WHERE {}",
+                conditions
+                    .iter()
+                    .map(|condition| format!("({})", condition))
+                    .collect::<Vec<_>>()
+                    .join(" AND ")
+            ),
+        };
+        (sql, values)
+    }
+
+    /// Build query SQL text and values
+    pub fn to_sql(&self) -> (String, Vec<Value>) {
+        let (where_clause, mut values) = self.where_clause(true);
+        let mut sql = format!(
+            "SELECT {} FROM {}{}",
+            self.columns, self.table, where_clause
+        );
+        if !self.order_by.is_empty() {
+            sql.push_str(&format!(" ORDER BY {}", self.order_by.join(", ")));
+        }
+        if self.limit.is_some() || self.offset.is_some() {
+            sql.push_str(" LIMIT ?");
+            values.push(Value::Integer(self.limit.unwrap_or(-1)));
+        }
+        if let Some(offset) = self.offset {
+            sql.push_str(" OFFSET ?");
+            values.push(Value::Integer(offset));
+        }
+        (sql, values)
+    }
+
+    /// Build count query SQL text and values, without order by, limit, offset and keyset
+    pub fn to_count_sql(&self) -> (String, Vec<Value>) {
+        let (where_clause, values) = self.where_clause(false);
+        (
+            format!("SELECT COUNT(*) FROM {}{}", self.table, where_clause),
+            values,
+        )
+    }
+
+    /// Run query and read all rows
+    pub fn fetch<T: FromRow>(&self, connection: &Connection) -> Result<Vec<T>> {
+        self.check_error()?;
+        let (sql, values) = self.to_sql();
+        let mut statement = connection.try_query::<T>(sql, values)?;
+        let mut rows = Vec::new();
+        while let Some(row) = statement.try_next()? {
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+
+    /// Run count query
+    pub fn count(&self, connection: &Connection) -> Result<i64> {
+        self.check_error()?;
+        let (sql, values) = self.to_count_sql();
+        Ok(connection
+            .try_query::<i64>(sql, values)?
+            .try_next()?
+            .unwrap_or(0))
+    }
+
+    /// Run query and count query in one read transaction for a consistent total,
+    /// a deferred transaction is started when none is open yet
+    pub fn fetch_page<T: FromRow>(&self, connection: &Connection) -> Result<Page<T>> {
+        if connection.in_transaction() {
+            return Ok(Page {
+                total: self.count(connection)?,
+                data: self.fetch(connection)?,
+            });
+        }
+        let transaction = connection.transaction()?;
+        let page = self.fetch_page(&transaction)?;
+        transaction.commit()?;
+        Ok(page)
+    }
+}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use libsqlite3_sys::SQLITE_MISMATCH;
+
+    use super::*;
+
+    fn test_database() -> Connection {
+        let db = Connection::open_memory().unwrap();
+        db.execute_batch(
+            "CREATE TABLE persons (id INTEGER PRIMARY KEY, name TEXT NOT NULL, age INTEGER NOT NULL) STRICT;
+            INSERT INTO persons (name, age) VALUES ('Alice', 30), ('Bob', 40), ('Charlie', 25), ('100% Dave', 50), ('Eve_1', 20);",
+        )
+        .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!(escape_like("100% a_b\\c"), "100\\% a\\_b\\\\c");
+    }
+
+    #[test]
+    fn test_to_sql() {
+        let (sql, values) = Select::from("persons").to_sql();
+        assert_eq!(sql, "SELECT * FROM persons");
+        assert!(values.is_empty());
+
+        let select = Select::from("persons")
+            .columns("id, name")
+            .filter("age >= ?", 18)
+            .filter_in("name", ["Alice".to_string(), "Bob".to_string()])
+            .order_by("name")
+            .order_by("id DESC")
+            .page(3, 10);
+        let (sql, values) = select.to_sql();
+        assert_eq!(
+            sql,
+            "SELECT id, name FROM persons WHERE (age >= ?) AND (name IN (?, ?)) ORDER BY name, id DESC LIMIT ? OFFSET ?"
+        );
+        assert_eq!(
+            values,
+            vec![
+                Value::Integer(18),
+                Value::Text("Alice".to_string()),
+                Value::Text("Bob".to_string()),
+                Value::Integer(10),
+                Value::Integer(20),
+            ]
+        );
+        let (sql, values) = select.to_count_sql();
+        assert_eq!(
+            sql,
+            "SELECT COUNT(*) FROM persons WHERE (age >= ?) AND (name IN (?, ?))"
+        );
+        assert_eq!(values.len(), 3);
+    }
+
+    #[test]
+    fn test_fetch_filters() {
+        let db = test_database();
+        let names = |select: Select| {
+            select
+                .columns("name")
+                .order_by("id")
+                .fetch::<String>(&db)
+                .unwrap()
+        };
+        assert_eq!(
+            names(Select::from("persons").filter_contains("name", "%")),
+            vec!["100% Dave"]
+        );
+        assert_eq!(
+            names(Select::from("persons").filter_starts_with("name", "Eve_")),
+            vec!["Eve_1"]
+        );
+        assert_eq!(
+            names(Select::from("persons").filter_in("age", [25, 30])),
+            vec!["Alice", "Charlie"]
+        );
+        assert!(names(Select::from("persons").filter_in("age", Vec::<i64>::new())).is_empty());
+        assert_eq!(
+            names(Select::from("persons").filter("age BETWEEN ? AND ?", (30, 40))),
+            vec!["Alice", "Bob"]
+        );
+    }
+
+    #[test]
+    fn test_fetch_page() {
+        let db = test_database();
+        let select = Select::from("persons")
+            .columns("id, name")
+            .filter("age > ?", 20)
+            .order_by("id")
+            .page(2, 2);
+        let page = select.fetch_page::<(i64, String)>(&db).unwrap();
+        assert_eq!(page.total, 4);
+        assert_eq!(
+            page.data,
+            vec![(3, "Charlie".to_string()), (4, "100% Dave".to_string())]
+        );
+
+        // Keyset pagination doesn't change the total
+        let select = Select::from("persons")
+            .columns("id")
+            .order_by("age DESC")
+            .order_by("id DESC")
+            .before(&["age", "id"], (40, 2))
+            .limit(2);
+        let page = select.fetch_page::<i64>(&db).unwrap();
+        assert_eq!(page.total, 5);
+        assert_eq!(page.data, vec![1, 3]);
+        assert!(!db.in_transaction());
+
+        // An open transaction is reused
+        let transaction = db.transaction().unwrap();
+        let page = select.fetch_page::<i64>(&transaction).unwrap();
+        assert_eq!(page.total, 5);
+        assert!(transaction.in_transaction());
+        transaction.commit().unwrap();
+    }
+
+    #[test]
+    fn test_fetch_value_error() {
+        let db = test_database();
+        let err = Select::from("persons")
+            .filter("age > ?", u64::MAX)
+            .count(&db)
+            .unwrap_err();
+        assert_eq!(err.code(), SQLITE_MISMATCH);
+
+        let err = Select::from("persons")
+            .filter_in("id", [1u64, u64::MAX])
+            .fetch::<i64>(&db)
+            .unwrap_err();
+        assert_eq!(err.code(), SQLITE_MISMATCH);
+    }
+}
@@ -5,19 +5,28 @@
  */
 
 use std::ffi::{c_char, c_void, CStr, CString};
+use std::fmt::Display;
 use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
 
 use libsqlite3_sys::*;
 
-use crate::{Bind, FromRow, Value};
+use crate::cache::CacheReturn;
+use crate::error::Result;
+use crate::hook::Hooks;
+use crate::{Bind, BindValue, Error, FromRow, Value, ValueRef};
 
 // MARK: Raw Statement
 /// Raw SQLite statement without type information
-pub struct RawStatement(*mut sqlite3_stmt);
+pub struct RawStatement(*mut sqlite3_stmt, Option<CacheReturn>, Arc<Mutex<Hooks>>);
 
 impl RawStatement {
-    pub(crate) fn new(statement: *mut sqlite3_stmt) -> Self {
-        Self(statement)
+    pub(crate) fn new(
+        statement: *mut sqlite3_stmt,
+        cache: Option<CacheReturn>,
+        hooks: Arc<Mutex<Hooks>>,
+    ) -> Self {
+        Self(statement, cache, hooks)
     }
 
     /// Reset the statement
@@ -27,17 +36,44 @@ impl RawStatement {
 
     /// Bind values to the statement
     pub fn bind(&mut self, params: impl Bind) {
-        params.bind(self);
+        if let Err(err) = self.try_bind(params) {
+            panic!(
+                "bsqlite: Can't bind value to statement!\n  Query: {}\n  Error: {}",
+                self.sql(),
+                err.message()
+            );
+        }
+    }
+
+    /// Try to bind values to the statement
+    pub fn try_bind(&mut self, params: impl Bind) -> Result<()> {
+        params.bind(self)
     }
 
     /// Bind value to the statement
     pub fn bind_value(&mut self, index: i32, value: Value) {
+        if let Err(err) = self.try_bind_value(index, value) {
+            panic!(
+                "bsqlite: Can't bind value to statement!\n  Query: {}\n  Error: {}",
+                self.sql(),
+                err.message()
+            );
+        }
+    }
+
+    /// Try to bind value to the statement
+    pub fn try_bind_value(&mut self, index: i32, value: Value) -> Result<()> {
+        self.try_bind_value_ref(index, ValueRef::from(&value))
+    }
+
+    /// Try to bind borrowed value to the statement, SQLite copies text and blobs while binding
+    pub fn try_bind_value_ref(&mut self, index: i32, value: ValueRef<'_>) -> Result<()> {
         let index = index + 1;
         let result = match value {
-            Value::Null => unsafe { sqlite3_bind_null(self.0, index) },
-            Value::Integer(i) => unsafe { sqlite3_bind_int64(self.0, index, i) },
-            Value::Real(f) => unsafe { sqlite3_bind_double(self.0, index, f) },
-            Value::Text(s) => unsafe {
+            ValueRef::Null => unsafe { sqlite3_bind_null(self.0, index) },
+            ValueRef::Integer(i) => unsafe { sqlite3_bind_int64(self.0, index, i) },
+            ValueRef::Real(f) => unsafe { sqlite3_bind_double(self.0, index, f) },
+            ValueRef::Text(s) => unsafe {
                 sqlite3_bind_text(
                     self.0,
                     index,
@@ -46,7 +82,7 @@ impl RawStatement {
                     SQLITE_TRANSIENT(),
                 )
             },
-            Value::Blob(b) => unsafe {
+            ValueRef::Blob(b) => unsafe {
                 sqlite3_bind_blob(
                     self.0,
                     index,
@@ -57,24 +93,60 @@ impl RawStatement {
             },
         };
         if result != SQLITE_OK {
-            let query = unsafe { CStr::from_ptr(sqlite3_sql(self.0)) }.to_string_lossy();
-            let error = unsafe { CStr::from_ptr(sqlite3_errmsg(sqlite3_db_handle(self.0))) }
-                .to_string_lossy();
+            return Err(Error::from_statement(self.0, result));
+        }
+        Ok(())
+    }
+
+    /// Bind named value to the statement
+    pub fn bind_named_value(&mut self, name: &str, value: Value) {
+        if let Err(err) = self.try_bind_named_value(name, value) {
             panic!(
                 "bsqlite: Can't bind value to statement!\n  Query: {}\n  Error: {}",
-                query, error
+                self.sql(),
+                err.message()
             );
         }
     }
 
-    /// Bind named value to the statement
-    pub fn bind_named_value(&mut self, name: &str, value: Value) {
+    /// Try to bind named value to the statement
+    pub fn try_bind_named_value(&mut self, name: &str, value: Value) -> Result<()> {
+        let index = self.parameter_index(name)?;
+        self.try_bind_value(index, value)
+    }
+
+    pub(crate) fn parameter_index(&self, name: &str) -> Result<i32> {
         let c_name = CString::new(name).expect("Can't convert to CString");
         let index = unsafe { sqlite3_bind_parameter_index(self.0, c_name.as_ptr()) };
         if index == 0 {
-            panic!("bsqlite: Can't find named parameter: {}", name);
+            return Err(Error::new(
+                SQLITE_RANGE,
+                format!("Can't find named parameter: {}", name),
+            )
+            .with_sql(self.sql()));
+        }
+        Ok(index - 1)
+    }
+
+    /// Get the SQL text of the statement
+    pub fn sql(&self) -> String {
+        unsafe { CStr::from_ptr(sqlite3_sql(self.0)) }
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// Step the statement, returns true when a row is available
+    pub(crate) fn step(&mut self) -> Result<bool> {
+        let result = unsafe { sqlite3_step(self.0) };
+        if result == SQLITE_ROW {
+            return Ok(true);
+        }
+        // A transaction can only have been committed when the statement is finished
+        Hooks::send_committed(&self.2, unsafe { sqlite3_db_handle(self.0) });
+        match result {
+            SQLITE_DONE => Ok(false),
+            code => Err(Error::from_statement(self.0, code)),
         }
-        self.bind_value(index - 1, value);
     }
 
     /// Read a value from the statement
@@ -99,11 +171,80 @@ impl RawStatement {
             r#type => unreachable!("Unknown column type: {}", r#type),
         }
     }
+
+    fn column_name_is(&self, index: i32, name: &str) -> bool {
+        let column = unsafe { sqlite3_column_name(self.0, index) };
+        !column.is_null() && unsafe { CStr::from_ptr(column) }.to_bytes() == name.as_bytes()
+    }
+
+    /// Get the index of a column by its name
+    pub fn column_index(&self, name: &str) -> Option<i32> {
+        (0..unsafe { sqlite3_column_count(self.0) }).find(|index| self.column_name_is(*index, name))
+    }
+
+    /// Get the index of a column that is expected at a position, the column is only
+    /// looked up by name when the name at the position doesn't match, so duplicate
+    /// names and unnamed expressions are read by position
+    #[doc(hidden)]
+    pub fn column_position(&self, index: i32, name: &str) -> Result<i32> {
+        if self.column_name_is(index, name) {
+            return Ok(index);
+        }
+        match self.column_index(name) {
+            Some(index) => Ok(index),
+            None if index < unsafe { sqlite3_column_count(self.0) } => Ok(index),
+            None => Err(
+                Error::new(SQLITE_RANGE, format!("Can't find column: {}", name))
+                    .with_sql(self.sql()),
+            ),
+        }
+    }
+
+    /// Read a value from the statement and convert it
+    pub fn read<T: TryFrom<Value>>(&self, index: i32) -> Result<T>
+    where
+        T::Error: Display,
+    {
+        T::try_from(self.read_value(index)).map_err(|err| {
+            let column = unsafe { sqlite3_column_name(self.0, index) };
+            let column = if column.is_null() {
+                index.to_string()
+            } else {
+                unsafe { CStr::from_ptr(column) }
+                    .to_string_lossy()
+                    .to_string()
+            };
+            Error::new(
+                SQLITE_MISMATCH,
+                format!("Can't read value of column: {}: {}", column, err),
+            )
+            .with_sql(self.sql())
+        })
+    }
+
+    /// Read a value from the statement by column name and convert it
+    pub fn read_named<T: TryFrom<Value>>(&self, name: &str) -> Result<T>
+    where
+        T::Error: Display,
+    {
+        match self.column_index(name) {
+            Some(index) => self.read(index),
+            None => Err(
+                Error::new(SQLITE_RANGE, format!("Can't find column: {}", name))
+                    .with_sql(self.sql()),
+            ),
+        }
+    }
 }
 
 impl Drop for RawStatement {
     fn drop(&mut self) {
-        unsafe { sqlite3_finalize(self.0) };
+        match self.1.take() {
+            Some(cache) => cache.give_back(self.0),
+            None => unsafe {
+                sqlite3_finalize(self.0);
+            },
+        }
     }
 }
 
@@ -112,8 +253,12 @@ impl Drop for RawStatement {
 pub struct Statement<T>(RawStatement, PhantomData<T>);
 
 impl<T> Statement<T> {
-    pub(crate) fn new(statement: *mut sqlite3_stmt) -> Self {
-        Self(RawStatement::new(statement), PhantomData)
+    pub(crate) fn new(
+        statement: *mut sqlite3_stmt,
+        cache: Option<CacheReturn>,
+        hooks: Arc<Mutex<Hooks>>,
+    ) -> Self {
+        Self(RawStatement::new(statement, cache, hooks), PhantomData)
     }
 
     /// Reset the statement
@@ -126,14 +271,47 @@ impl<T> Statement<T> {
         self.0.bind(params);
     }
 
+    /// Try to bind values to the statement
+    pub fn try_bind(&mut self, params: impl Bind) -> Result<()> {
+        self.0.try_bind(params)
+    }
+
     /// Bind value to the statement
-    pub fn bind_value(&mut self, index: i32, value: impl Into<Value>) {
-        self.0.bind_value(index, value.into());
+    pub fn bind_value(&mut self, index: i32, value: impl BindValue) {
+        if let Err(err) = self.try_bind_value(index, value) {
+            panic!(
+                "bsqlite: Can't bind value to statement!\n  Query: {}\n  Error: {}",
+                self.sql(),
+                err.message()
+            );
+        }
+    }
+
+    /// Try to bind value to the statement
+    pub fn try_bind_value(&mut self, index: i32, value: impl BindValue) -> Result<()> {
+        value.bind_value(&mut self.0, index)
     }
 
     /// Bind named value to the statement
-    pub fn bind_named_value(&mut self, name: &str, value: impl Into<Value>) {
-        self.0.bind_named_value(name, value.into());
+    pub fn bind_named_value(&mut self, name: &str, value: impl BindValue) {
+        if let Err(err) = self.try_bind_named_value(name, value) {
+            panic!(
+                "bsqlite: Can't bind value to statement!\n  Query: {}\n  Error: {}",
+                self.sql(),
+                err.message()
+            );
+        }
+    }
+
+    /// Try to bind named value to the statement
+    pub fn try_bind_named_value(&mut self, name: &str, value: impl BindValue) -> Result<()> {
+        let index = self.0.parameter_index(name)?;
+        value.bind_value(&mut self.0, index)
+    }
+
+    /// Get the SQL text of the statement
+    pub fn sql(&self) -> String {
+        self.0.sql()
     }
 
     /// Read a value from the statement
@@ -142,23 +320,28 @@ impl<T> Statement<T> {
     }
 }
 
+impl<T: FromRow> Statement<T> {
+    /// Try to read the next row
+    pub fn try_next(&mut self) -> Result<Option<T>> {
+        if self.0.step()? {
+            Ok(Some(T::from_row(&mut self.0)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
 impl<T: FromRow> Iterator for Statement<T> {
     type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let result = unsafe { sqlite3_step(self.0 .0) };
-        if result == SQLITE_ROW {
-            Some(T::from_row(&mut self.0))
-        } else if result == SQLITE_DONE {
-            None
-        } else {
-            let query = unsafe { CStr::from_ptr(sqlite3_sql(self.0 .0)) }.to_string_lossy();
-            let error = unsafe { CStr::from_ptr(sqlite3_errmsg(sqlite3_db_handle(self.0 .0))) }
-                .to_string_lossy();
-            panic!(
+        match self.try_next() {
+            Ok(row) => row,
+            Err(err) => panic!(
                 "bsqlite: Can't step statement!\n  Query: {}\n  Error: {}",
-                query, error
-            );
+                self.0.sql(),
+                err.message()
+            ),
         }
     }
 }
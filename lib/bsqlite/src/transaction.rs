@@ -0,0 +1,332 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::ops::Deref;
+use std::thread;
+use std::time::Duration;
+
+use crate::{Connection, Error, ErrorKind};
+
+/// Maximum amount of attempts of [Connection::with_transaction] when the database is busy
+const BUSY_MAX_ATTEMPTS: u32 = 5;
+
+/// Initial backoff of [Connection::with_transaction] when the database is busy
+const BUSY_BACKOFF: Duration = Duration::from_millis(10);
+
+// MARK: TransactionMode
+/// A transaction mode
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum TransactionMode {
+    /// Acquire locks when the database is first read or written
+    #[default]
+    Deferred,
+    /// Acquire the write lock when the transaction starts
+    Immediate,
+    /// Acquire the write lock and block readers when the transaction starts
+    Exclusive,
+}
+
+impl TransactionMode {
+    fn begin_query(&self) -> &'static str {
+        match self {
+            TransactionMode::Deferred => "BEGIN DEFERRED",
+            TransactionMode::Immediate => "BEGIN IMMEDIATE",
+            TransactionMode::Exclusive => "BEGIN EXCLUSIVE",
+        }
+    }
+}
+
+// MARK: Transaction
+/// A transaction or savepoint guard that rolls back when dropped without commit
+///
+/// A transaction covers all statements run on the connection, so don't share
+/// the connection with other threads while a transaction is open.
+pub struct Transaction<'a> {
+    connection: &'a Connection,
+    depth: usize,
+    finished: bool,
+}
+
+impl<'a> Transaction<'a> {
+    fn begin(connection: &'a Connection, mode: TransactionMode) -> Result<Self, Error> {
+        connection.try_execute(mode.begin_query(), ())?;
+        Ok(Self {
+            connection,
+            depth: 0,
+            finished: false,
+        })
+    }
+
+    fn savepoint_name(&self) -> String {
+        format!("bsqlite_savepoint_{}", self.depth)
+    }
+
+    /// Start a nested savepoint
+    pub fn savepoint(&mut self) -> Result<Transaction<'_>, Error> {
+        let savepoint = Transaction {
+            connection: self.connection,
+            depth: self.depth + 1,
+            finished: false,
+        };
+        self.connection
+            .try_execute(format!("SAVEPOINT {}", savepoint.savepoint_name()), ())?;
+        Ok(savepoint)
+    }
+
+    /// Commit the transaction or release the savepoint
+    pub fn commit(mut self) -> Result<(), Error> {
+        if self.depth == 0 {
+            self.connection.try_execute("COMMIT", ())?;
+        } else {
+            self.connection
+                .try_execute(format!("RELEASE {}", self.savepoint_name()), ())?;
+        }
+        self.finished = true;
+        Ok(())
+    }
+
+    /// Roll back the transaction or savepoint
+    pub fn rollback(mut self) -> Result<(), Error> {
+        self.finished = true;
+        self.rollback_inner()
+    }
+
+    fn rollback_inner(&self) -> Result<(), Error> {
+        if self.depth == 0 {
+            self.connection.try_execute("ROLLBACK", ())
+        } else {
+            let name = self.savepoint_name();
+            self.connection
+                .try_execute(format!("ROLLBACK TO {}", name), ())?;
+            self.connection.try_execute(format!("RELEASE {}", name), ())
+        }
+    }
+}
+
+impl Deref for Transaction<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Self::Target {
+        self.connection
+    }
+}
+
+impl Drop for Transaction<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            _ = self.rollback_inner();
+        }
+    }
+}
+
+// MARK: Connection
+impl Connection {
+    /// Start a deferred transaction
+    pub fn transaction(&self) -> Result<Transaction<'_>, Error> {
+        Transaction::begin(self, TransactionMode::Deferred)
+    }
+
+    /// Start a transaction with mode
+    pub fn transaction_with(&self, mode: TransactionMode) -> Result<Transaction<'_>, Error> {
+        Transaction::begin(self, mode)
+    }
+
+    /// Run a closure in an immediate transaction that is committed when it returns Ok,
+    /// the whole transaction is retried with backoff when the database is busy
+    pub fn with_transaction<T>(
+        &self,
+        mut f: impl FnMut(&Transaction<'_>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mut attempt = 0;
+        loop {
+            let result = self
+                .transaction_with(TransactionMode::Immediate)
+                .and_then(|tx| {
+                    let value = f(&tx)?;
+                    tx.commit()?;
+                    Ok(value)
+                });
+            match result {
+                Err(err) if err.kind() == ErrorKind::Busy && attempt + 1 < BUSY_MAX_ATTEMPTS => {
+                    thread::sleep(BUSY_BACKOFF * 2u32.pow(attempt));
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Barrier};
+
+    use super::*;
+
+    fn test_database() -> Connection {
+        let db = Connection::open_memory().unwrap();
+        db.execute(
+            "CREATE TABLE persons (name TEXT NOT NULL UNIQUE) STRICT",
+            (),
+        );
+        db
+    }
+
+    fn count(db: &Connection) -> i64 {
+        db.query_some::<i64>("SELECT COUNT(name) FROM persons", ())
+    }
+
+    #[test]
+    fn test_transaction_commit() {
+        let db = test_database();
+        let tx = db.transaction().unwrap();
+        assert!(tx.in_transaction());
+        tx.execute("INSERT INTO persons (name) VALUES ('Alice')", ());
+        tx.execute("INSERT INTO persons (name) VALUES ('Bob')", ());
+        tx.commit().unwrap();
+        assert!(!db.in_transaction());
+        assert_eq!(count(&db), 2);
+    }
+
+    #[test]
+    fn test_transaction_rollback_on_drop() {
+        let db = test_database();
+        {
+            let tx = db.transaction_with(TransactionMode::Exclusive).unwrap();
+            tx.execute("INSERT INTO persons (name) VALUES ('Alice')", ());
+        }
+        assert!(!db.in_transaction());
+        assert_eq!(count(&db), 0);
+
+        let tx = db.transaction_with(TransactionMode::Immediate).unwrap();
+        tx.execute("INSERT INTO persons (name) VALUES ('Alice')", ());
+        tx.rollback().unwrap();
+        assert_eq!(count(&db), 0);
+    }
+
+    #[test]
+    fn test_transaction_savepoints() {
+        let db = test_database();
+        let mut tx = db.transaction().unwrap();
+        tx.execute("INSERT INTO persons (name) VALUES ('Alice')", ());
+        {
+            let mut sp = tx.savepoint().unwrap();
+            sp.execute("INSERT INTO persons (name) VALUES ('Bob')", ());
+            {
+                let sp = sp.savepoint().unwrap();
+                sp.execute("INSERT INTO persons (name) VALUES ('Charlie')", ());
+            }
+            sp.commit().unwrap();
+        }
+        {
+            let sp = tx.savepoint().unwrap();
+            sp.execute("INSERT INTO persons (name) VALUES ('Dave')", ());
+            sp.rollback().unwrap();
+        }
+        tx.commit().unwrap();
+
+        let names = db
+            .query::<String>("SELECT name FROM persons ORDER BY name", ())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Alice".to_string(), "Bob".to_string()]);
+    }
+
+    #[test]
+    fn test_with_transaction() {
+        let db = test_database();
+        db.with_transaction(|tx| {
+            tx.try_execute("INSERT INTO persons (name) VALUES ('Alice')", ())?;
+            tx.try_execute("INSERT INTO persons (name) VALUES ('Bob')", ())
+        })
+        .unwrap();
+        assert_eq!(count(&db), 2);
+
+        let err = db
+            .with_transaction(|tx| {
+                tx.try_execute("INSERT INTO persons (name) VALUES ('Charlie')", ())?;
+                tx.try_execute("INSERT INTO persons (name) VALUES ('Alice')", ())
+            })
+            .unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ErrorKind::Constraint(crate::ConstraintKind::Unique)
+        );
+        assert!(!db.in_transaction());
+        assert_eq!(count(&db), 2);
+    }
+
+    #[test]
+    fn test_with_transaction_busy_retry() {
+        let path =
+            std::env::temp_dir().join(format!("bsqlite_busy_retry_{}.db", std::process::id()));
+        _ = std::fs::remove_file(&path);
+        let db = Connection::open(&path).unwrap();
+        db.execute(
+            "CREATE TABLE persons (name TEXT NOT NULL UNIQUE) STRICT",
+            (),
+        );
+        let other = Connection::open(&path).unwrap();
+        other.execute("PRAGMA busy_timeout = 0", ());
+
+        // The first attempt fails with the busy error of an other connection, so it is
+        // rolled back and retried
+        let mut attempts = 0;
+        db.with_transaction(|tx| {
+            attempts += 1;
+            tx.try_execute("INSERT INTO persons (name) VALUES ('Alice')", ())?;
+            if attempts == 1 {
+                let err = other
+                    .transaction_with(TransactionMode::Immediate)
+                    .err()
+                    .unwrap();
+                assert_eq!(err.kind(), ErrorKind::Busy);
+                return Err(err);
+            }
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(attempts, 2);
+        assert_eq!(count(&db), 1);
+
+        drop((db, other));
+        _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_with_transaction_waits_for_lock() {
+        let path =
+            std::env::temp_dir().join(format!("bsqlite_wait_lock_{}.db", std::process::id()));
+        _ = std::fs::remove_file(&path);
+        let db = Connection::open(&path).unwrap();
+        db.execute(
+            "CREATE TABLE persons (name TEXT NOT NULL UNIQUE) STRICT",
+            (),
+        );
+
+        // Hold the write lock from another connection until the transaction is started
+        let other = Connection::open(&path).unwrap();
+        let barrier = Arc::new(Barrier::new(2));
+        let handle = thread::spawn({
+            let barrier = barrier.clone();
+            move || {
+                let tx = other.transaction_with(TransactionMode::Immediate).unwrap();
+                tx.execute("INSERT INTO persons (name) VALUES ('Bob')", ());
+                barrier.wait();
+                tx.commit().unwrap();
+            }
+        });
+        barrier.wait();
+
+        db.with_transaction(|tx| tx.try_execute("INSERT INTO persons (name) VALUES ('Alice')", ()))
+            .unwrap();
+        handle.join().unwrap();
+        assert_eq!(count(&db), 2);
+
+        drop(db);
+        _ = std::fs::remove_file(&path);
+    }
+}
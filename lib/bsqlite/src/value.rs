@@ -8,6 +8,7 @@ use std::error::Error;
 use std::fmt::{self, Display, Formatter};
 
 /// A SQLite value
+#[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     /// A NULL value
     Null,
@@ -21,16 +22,149 @@ pub enum Value {
     Blob(Vec<u8>),
 }
 
+impl Value {
+    /// Get the SQLite type name of the value
+    pub fn type_name(&self) -> &'static str {
+        ValueRef::from(self).type_name()
+    }
+}
+
+// MARK: ValueRef
+/// A borrowed SQLite value that is bound without copying it first
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueRef<'a> {
+    /// A NULL value
+    Null,
+    /// An 64-bit integer value
+    Integer(i64),
+    /// A 64-bit floating point value
+    Real(f64),
+    /// A borrowed text value
+    Text(&'a str),
+    /// A borrowed blob value
+    Blob(&'a [u8]),
+}
+
+impl ValueRef<'_> {
+    /// Get the SQLite type name of the value
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ValueRef::Null => "NULL",
+            ValueRef::Integer(_) => "INTEGER",
+            ValueRef::Real(_) => "REAL",
+            ValueRef::Text(_) => "TEXT",
+            ValueRef::Blob(_) => "BLOB",
+        }
+    }
+
+    pub(crate) fn to_value(self) -> Value {
+        match self {
+            ValueRef::Null => Value::Null,
+            ValueRef::Integer(i) => Value::Integer(i),
+            ValueRef::Real(f) => Value::Real(f),
+            ValueRef::Text(s) => Value::Text(s.to_string()),
+            ValueRef::Blob(b) => Value::Blob(b.to_vec()),
+        }
+    }
+}
+
+impl<'a> From<&'a Value> for ValueRef<'a> {
+    fn from(value: &'a Value) -> Self {
+        match value {
+            Value::Null => ValueRef::Null,
+            Value::Integer(i) => ValueRef::Integer(*i),
+            Value::Real(f) => ValueRef::Real(*f),
+            Value::Text(s) => ValueRef::Text(s),
+            Value::Blob(b) => ValueRef::Blob(b),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for ValueRef<'a> {
+    fn from(value: &'a str) -> Self {
+        ValueRef::Text(value)
+    }
+}
+
+impl<'a> From<&'a String> for ValueRef<'a> {
+    fn from(value: &'a String) -> Self {
+        ValueRef::Text(value)
+    }
+}
+
+impl<'a> From<&'a [u8]> for ValueRef<'a> {
+    fn from(value: &'a [u8]) -> Self {
+        ValueRef::Blob(value)
+    }
+}
+
+impl<'a> From<&'a Vec<u8>> for ValueRef<'a> {
+    fn from(value: &'a Vec<u8>) -> Self {
+        ValueRef::Blob(value)
+    }
+}
+
+impl<'a, T: Into<ValueRef<'a>>> From<Option<T>> for ValueRef<'a> {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(v) => v.into(),
+            None => ValueRef::Null,
+        }
+    }
+}
+
 // MARK: ValueError
 type Result<T> = std::result::Result<T, ValueError>;
 
 /// A value error
-#[derive(Debug)]
-pub struct ValueError;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValueError {
+    /// The value has a type that can't be converted
+    Type {
+        /// Expected Rust type
+        expected: &'static str,
+        /// Found SQLite type
+        found: &'static str,
+    },
+    /// The integer doesn't fit in the type
+    Overflow {
+        /// Rust type
+        target: &'static str,
+        /// Integer value
+        value: i128,
+    },
+    /// The value can't be parsed as the type
+    Invalid {
+        /// Rust type
+        target: &'static str,
+        /// Parse error message
+        message: String,
+    },
+}
+
+impl ValueError {
+    /// Create type error for a value
+    pub fn type_mismatch(expected: &'static str, value: &Value) -> Self {
+        ValueError::Type {
+            expected,
+            found: value.type_name(),
+        }
+    }
+}
 
 impl Display for ValueError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "Value error")
+        match self {
+            ValueError::Type { expected, found } => {
+                write!(f, "Value error: can't convert {} to {}", found, expected)
+            }
+            ValueError::Overflow { target, value } => {
+                write!(f, "Value error: {} is out of range for {}", value, target)
+            }
+            ValueError::Invalid { target, message } => {
+                write!(f, "Value error: invalid {}: {}", target, message)
+            }
+        }
     }
 }
 
@@ -47,7 +181,7 @@ impl TryFrom<Value> for i64 {
     fn try_from(value: Value) -> Result<Self> {
         match value {
             Value::Integer(v) => Ok(v),
-            _ => Err(ValueError),
+            value => Err(ValueError::type_mismatch("i64", &value)),
         }
     }
 }
@@ -62,7 +196,8 @@ impl TryFrom<Value> for f64 {
     fn try_from(value: Value) -> Result<Self> {
         match value {
             Value::Real(v) => Ok(v),
-            _ => Err(ValueError),
+            Value::Integer(v) => Ok(v as f64),
+            value => Err(ValueError::type_mismatch("f64", &value)),
         }
     }
 }
@@ -77,7 +212,7 @@ impl TryFrom<Value> for String {
     fn try_from(value: Value) -> Result<Self> {
         match value {
             Value::Text(v) => Ok(v),
-            _ => Err(ValueError),
+            value => Err(ValueError::type_mismatch("String", &value)),
         }
     }
 }
@@ -92,87 +227,129 @@ impl TryFrom<Value> for Vec<u8> {
     fn try_from(value: Value) -> Result<Self> {
         match value {
             Value::Blob(v) => Ok(v),
-            _ => Err(ValueError),
+            value => Err(ValueError::type_mismatch("Vec<u8>", &value)),
         }
     }
 }
 
-// MARK: From Option<T>
-impl From<Option<i64>> for Value {
-    fn from(value: Option<i64>) -> Self {
-        match value {
-            Some(v) => Value::Integer(v),
-            None => Value::Null,
-        }
+// MARK: From bool
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Integer(value as i64)
     }
 }
-impl TryFrom<Value> for Option<i64> {
+impl TryFrom<Value> for bool {
     type Error = ValueError;
     fn try_from(value: Value) -> Result<Self> {
         match value {
-            Value::Integer(v) => Ok(Some(v)),
-            Value::Null => Ok(None),
-            _ => Err(ValueError),
+            Value::Integer(v) => Ok(v != 0),
+            value => Err(ValueError::type_mismatch("bool", &value)),
         }
     }
 }
 
-impl From<Option<f64>> for Value {
-    fn from(value: Option<f64>) -> Self {
-        match value {
-            Some(v) => Value::Real(v),
-            None => Value::Null,
+// MARK: From integers
+macro_rules! impl_value_for_integer {
+    ($($t:ty),*) => ($(
+        impl From<$t> for Value {
+            fn from(value: $t) -> Self {
+                Value::Integer(value.into())
+            }
         }
+        impl TryFrom<Value> for $t {
+            type Error = ValueError;
+            fn try_from(value: Value) -> Result<Self> {
+                match value {
+                    Value::Integer(v) => <$t>::try_from(v).map_err(|_| ValueError::Overflow {
+                        target: stringify!($t),
+                        value: v.into(),
+                    }),
+                    value => Err(ValueError::type_mismatch(stringify!($t), &value)),
+                }
+            }
+        }
+    )*);
+}
+impl_value_for_integer!(i8, i16, i32, u8, u16, u32);
+
+impl TryFrom<u64> for Value {
+    type Error = ValueError;
+    fn try_from(value: u64) -> Result<Self> {
+        i64::try_from(value)
+            .map(Value::Integer)
+            .map_err(|_| ValueError::Overflow {
+                target: "i64",
+                value: value.into(),
+            })
     }
 }
-impl TryFrom<Value> for Option<f64> {
+impl TryFrom<Value> for u64 {
     type Error = ValueError;
     fn try_from(value: Value) -> Result<Self> {
         match value {
-            Value::Real(v) => Ok(Some(v)),
-            Value::Null => Ok(None),
-            _ => Err(ValueError),
+            Value::Integer(v) => u64::try_from(v).map_err(|_| ValueError::Overflow {
+                target: "u64",
+                value: v.into(),
+            }),
+            value => Err(ValueError::type_mismatch("u64", &value)),
         }
     }
 }
 
-impl From<Option<String>> for Value {
-    fn from(value: Option<String>) -> Self {
-        match value {
-            Some(v) => Value::Text(v),
-            None => Value::Null,
-        }
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        Value::Real(value.into())
     }
 }
-impl TryFrom<Value> for Option<String> {
+impl TryFrom<Value> for f32 {
     type Error = ValueError;
     fn try_from(value: Value) -> Result<Self> {
         match value {
-            Value::Text(v) => Ok(Some(v)),
-            Value::Null => Ok(None),
-            _ => Err(ValueError),
+            Value::Real(v) => Ok(v as f32),
+            Value::Integer(v) => Ok(v as f32),
+            value => Err(ValueError::type_mismatch("f32", &value)),
         }
     }
 }
 
-impl From<Option<Vec<u8>>> for Value {
-    fn from(value: Option<Vec<u8>>) -> Self {
+// MARK: From Option<T>
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
         match value {
-            Some(v) => Value::Blob(v),
+            Some(v) => v.into(),
             None => Value::Null,
         }
     }
 }
-impl TryFrom<Value> for Option<Vec<u8>> {
-    type Error = ValueError;
-    fn try_from(value: Value) -> Result<Self> {
-        match value {
-            Value::Blob(v) => Ok(Some(v)),
-            Value::Null => Ok(None),
-            _ => Err(ValueError),
+
+macro_rules! impl_try_from_value_for_option {
+    ($($t:ty),*) => ($(
+        impl TryFrom<Value> for Option<$t> {
+            type Error = ValueError;
+            fn try_from(value: Value) -> Result<Self> {
+                match value {
+                    Value::Null => Ok(None),
+                    value => <$t>::try_from(value).map(Some),
+                }
+            }
         }
-    }
+    )*);
 }
+impl_try_from_value_for_option!(
+    i64,
+    f64,
+    String,
+    Vec<u8>,
+    bool,
+    i8,
+    i16,
+    i32,
+    u8,
+    u16,
+    u32,
+    u64,
+    f32
+);
 
 // MARK: From Uuid
 #[cfg(feature = "uuid")]
@@ -186,34 +363,63 @@ impl TryFrom<Value> for uuid::Uuid {
     type Error = ValueError;
     fn try_from(value: Value) -> Result<Self> {
         match value {
-            Value::Blob(v) => Ok(uuid::Uuid::from_slice(&v).map_err(|_| ValueError)?),
-            _ => Err(ValueError),
+            Value::Blob(v) => uuid::Uuid::from_slice(&v).map_err(|err| ValueError::Invalid {
+                target: "Uuid",
+                message: err.to_string(),
+            }),
+            value => Err(ValueError::type_mismatch("Uuid", &value)),
         }
     }
 }
-
 #[cfg(feature = "uuid")]
-impl From<Option<uuid::Uuid>> for Value {
-    fn from(value: Option<uuid::Uuid>) -> Self {
-        match value {
-            Some(v) => Value::Blob(v.into_bytes().to_vec()),
-            None => Value::Null,
-        }
+impl_try_from_value_for_option!(uuid::Uuid);
+
+// MARK: From Decimal
+#[cfg(feature = "rust_decimal")]
+impl From<rust_decimal::Decimal> for Value {
+    fn from(value: rust_decimal::Decimal) -> Self {
+        Value::Text(value.to_string())
     }
 }
-#[cfg(feature = "uuid")]
-impl TryFrom<Value> for Option<uuid::Uuid> {
+#[cfg(feature = "rust_decimal")]
+impl TryFrom<Value> for rust_decimal::Decimal {
     type Error = ValueError;
     fn try_from(value: Value) -> Result<Self> {
+        let parse = |text: &str| {
+            text.parse::<rust_decimal::Decimal>()
+                .map_err(|err| ValueError::Invalid {
+                    target: "Decimal",
+                    message: err.to_string(),
+                })
+        };
         match value {
-            Value::Blob(v) => Ok(Some(uuid::Uuid::from_slice(&v).map_err(|_| ValueError)?)),
-            Value::Null => Ok(None),
-            _ => Err(ValueError),
+            Value::Text(v) => parse(&v),
+            Value::Integer(v) => Ok(v.into()),
+            Value::Real(v) => parse(&v.to_string()),
+            value => Err(ValueError::type_mismatch("Decimal", &value)),
         }
     }
 }
+#[cfg(feature = "rust_decimal")]
+impl_try_from_value_for_option!(rust_decimal::Decimal);
 
 // MARK: From time
+#[cfg(feature = "chrono")]
+fn timestamp_to_datetime(
+    target: &'static str,
+    value: Value,
+) -> Result<chrono::DateTime<chrono::Utc>> {
+    match value {
+        Value::Integer(i) => {
+            chrono::DateTime::<chrono::Utc>::from_timestamp(i, 0).ok_or(ValueError::Overflow {
+                target,
+                value: i.into(),
+            })
+        }
+        value => Err(ValueError::type_mismatch(target, &value)),
+    }
+}
+
 #[cfg(feature = "chrono")]
 impl From<chrono::NaiveDate> for Value {
     fn from(value: chrono::NaiveDate) -> Self {
@@ -230,13 +436,21 @@ impl From<chrono::NaiveDate> for Value {
 impl TryFrom<Value> for chrono::NaiveDate {
     type Error = ValueError;
     fn try_from(value: Value) -> Result<Self> {
-        match value {
-            Value::Integer(i) => Ok(chrono::DateTime::<chrono::Utc>::from_timestamp(i, 0)
-                .expect("Should be some")
-                .naive_utc()
-                .date()),
-            _ => Err(ValueError),
-        }
+        timestamp_to_datetime("NaiveDate", value).map(|datetime| datetime.naive_utc().date())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::NaiveDateTime> for Value {
+    fn from(value: chrono::NaiveDateTime) -> Self {
+        Value::Integer(value.and_utc().timestamp())
+    }
+}
+#[cfg(feature = "chrono")]
+impl TryFrom<Value> for chrono::NaiveDateTime {
+    type Error = ValueError;
+    fn try_from(value: Value) -> Result<Self> {
+        timestamp_to_datetime("NaiveDateTime", value).map(|datetime| datetime.naive_utc())
     }
 }
 
@@ -250,62 +464,200 @@ impl From<chrono::DateTime<chrono::Utc>> for Value {
 impl TryFrom<Value> for chrono::DateTime<chrono::Utc> {
     type Error = ValueError;
     fn try_from(value: Value) -> Result<Self> {
-        match value {
-            Value::Integer(i) => Ok(Self::from_timestamp(i, 0).expect("Should be some")),
-            _ => Err(ValueError),
-        }
+        timestamp_to_datetime("DateTime<Utc>", value)
     }
 }
 
 #[cfg(feature = "chrono")]
-impl From<Option<chrono::NaiveDate>> for Value {
-    fn from(value: Option<chrono::NaiveDate>) -> Self {
-        match value {
-            Some(v) => Value::Integer(
-                v.and_hms_opt(0, 0, 0)
-                    .expect("Should be some")
-                    .and_utc()
-                    .timestamp(),
-            ),
-            None => Value::Null,
+impl_try_from_value_for_option!(
+    chrono::NaiveDate,
+    chrono::NaiveDateTime,
+    chrono::DateTime<chrono::Utc>
+);
+
+// MARK: From Json
+/// A value that is stored as JSON text
+#[cfg(feature = "serde_json")]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Json<T>(pub T);
+
+#[cfg(feature = "serde_json")]
+fn json_error(err: serde_json::Error) -> ValueError {
+    ValueError::Invalid {
+        target: "JSON",
+        message: err.to_string(),
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl<T: serde::Serialize> From<Json<T>> for Value {
+    fn from(value: Json<T>) -> Self {
+        match serde_json::to_string(&value.0).expect("Can't serialize JSON") {
+            json if json == "null" => Value::Null,
+            json => Value::Text(json),
         }
     }
 }
-#[cfg(feature = "chrono")]
-impl TryFrom<Value> for Option<chrono::NaiveDate> {
+#[cfg(feature = "serde_json")]
+impl<T: serde::de::DeserializeOwned> TryFrom<Value> for Json<T> {
     type Error = ValueError;
     fn try_from(value: Value) -> Result<Self> {
         match value {
-            #[allow(deprecated)]
-            Value::Integer(i) => Ok(Some(
-                chrono::DateTime::<chrono::Utc>::from_timestamp(i, 0)
-                    .expect("Should be some")
-                    .naive_utc()
-                    .date(),
-            )),
-            Value::Null => Ok(None),
-            _ => Err(ValueError),
+            Value::Text(v) => serde_json::from_str(&v).map(Json).map_err(json_error),
+            Value::Blob(v) => serde_json::from_slice(&v).map(Json).map_err(json_error),
+            Value::Null => serde_json::from_str("null").map(Json).map_err(json_error),
+            value => Err(ValueError::type_mismatch("JSON", &value)),
         }
     }
 }
 
-#[cfg(feature = "chrono")]
-impl From<Option<chrono::DateTime<chrono::Utc>>> for Value {
-    fn from(value: Option<chrono::DateTime<chrono::Utc>>) -> Self {
-        match value {
-            Some(v) => Value::Integer(v.timestamp()),
-            None => Value::Null,
-        }
+#[cfg(feature = "serde_json")]
+impl From<serde_json::Value> for Value {
+    fn from(value: serde_json::Value) -> Self {
+        Json(value).into()
     }
 }
-#[cfg(feature = "chrono")]
-impl TryFrom<Value> for Option<chrono::DateTime<chrono::Utc>> {
+#[cfg(feature = "serde_json")]
+impl TryFrom<Value> for serde_json::Value {
     type Error = ValueError;
     fn try_from(value: Value) -> Result<Self> {
         match value {
-            Value::Integer(i) => Ok(chrono::DateTime::<chrono::Utc>::from_timestamp(i, 0)),
-            Value::Null => Ok(None),
-            _ => Err(ValueError),
+            Value::Null => Ok(serde_json::Value::Null),
+            Value::Integer(v) => Ok(v.into()),
+            Value::Real(v) => serde_json::Number::from_f64(v)
+                .map(serde_json::Value::Number)
+                .ok_or(ValueError::Invalid {
+                    target: "JSON",
+                    message: format!("{} is not a JSON number", v),
+                }),
+            value => Json::try_from(value).map(|json| json.0),
         }
     }
 }
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(Value::from(true), Value::Integer(1));
+        assert!(bool::try_from(Value::Integer(2)).unwrap());
+        assert_eq!(Value::from(-5i8), Value::Integer(-5));
+        assert_eq!(u8::try_from(Value::Integer(255)).unwrap(), 255);
+        assert_eq!(
+            u8::try_from(Value::Integer(256)).unwrap_err(),
+            ValueError::Overflow {
+                target: "u8",
+                value: 256
+            }
+        );
+        assert_eq!(i32::try_from(Value::Integer(-7)).unwrap(), -7);
+        assert_eq!(
+            i16::try_from(Value::Text("1".to_string())).unwrap_err(),
+            ValueError::Type {
+                expected: "i16",
+                found: "TEXT"
+            }
+        );
+        assert_eq!(
+            Value::try_from(i64::MAX as u64).unwrap(),
+            Value::Integer(i64::MAX)
+        );
+        assert!(Value::try_from(u64::MAX).is_err());
+        assert!(u64::try_from(Value::Integer(-1)).is_err());
+        assert_eq!(Option::<u32>::try_from(Value::Null).unwrap(), None);
+        assert_eq!(Value::from(None::<u32>), Value::Null);
+        assert_eq!(f64::try_from(Value::Integer(2)).unwrap(), 2.0);
+    }
+
+    #[test]
+    fn test_borrowed() {
+        let name = "Alice".to_string();
+        assert_eq!(ValueRef::from(name.as_str()), ValueRef::Text("Alice"));
+        assert_eq!(ValueRef::from(&name), ValueRef::Text("Alice"));
+        assert_eq!(ValueRef::from(&[1u8, 2][..]), ValueRef::Blob(&[1, 2]));
+        assert_eq!(ValueRef::from(Some("Bob")), ValueRef::Text("Bob"));
+        assert_eq!(ValueRef::from(None::<&str>), ValueRef::Null);
+        assert_eq!(
+            ValueRef::Text("Bob").to_value(),
+            Value::Text("Bob".to_string())
+        );
+        let value = Value::Blob(vec![3]);
+        assert_eq!(ValueRef::from(&value), ValueRef::Blob(&[3]));
+    }
+
+    #[test]
+    fn test_error_display() {
+        assert_eq!(
+            ValueError::type_mismatch("String", &Value::Null).to_string(),
+            "Value error: can't convert NULL to String"
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_naive_date_time() {
+        let datetime = chrono::NaiveDate::from_ymd_opt(2025, 2, 13)
+            .unwrap()
+            .and_hms_opt(12, 30, 0)
+            .unwrap();
+        let value = Value::from(datetime);
+        assert_eq!(value, Value::Integer(1739449800));
+        assert_eq!(chrono::NaiveDateTime::try_from(value).unwrap(), datetime);
+        assert!(chrono::NaiveDateTime::try_from(Value::Text("2025".to_string())).is_err());
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn test_decimal() {
+        let decimal = rust_decimal::Decimal::new(1050, 2);
+        let value = Value::from(decimal);
+        assert_eq!(value, Value::Text("10.50".to_string()));
+        assert_eq!(rust_decimal::Decimal::try_from(value).unwrap(), decimal);
+        assert_eq!(
+            rust_decimal::Decimal::try_from(Value::Integer(3)).unwrap(),
+            rust_decimal::Decimal::from(3)
+        );
+        assert_eq!(
+            rust_decimal::Decimal::try_from(Value::Real(0.25)).unwrap(),
+            rust_decimal::Decimal::new(25, 2)
+        );
+        assert!(matches!(
+            rust_decimal::Decimal::try_from(Value::Text("ten".to_string())),
+            Err(ValueError::Invalid {
+                target: "Decimal",
+                ..
+            })
+        ));
+        assert_eq!(
+            Option::<rust_decimal::Decimal>::try_from(Value::Null).unwrap(),
+            None
+        );
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn test_serde_json_value() {
+        let json = serde_json::json!({ "name": "Alice", "tags": [1, 2] });
+        let value = Value::from(json.clone());
+        assert_eq!(
+            value,
+            Value::Text("{\"name\":\"Alice\",\"tags\":[1,2]}".to_string())
+        );
+        assert_eq!(serde_json::Value::try_from(value).unwrap(), json);
+        assert_eq!(
+            serde_json::Value::try_from(Value::Integer(5)).unwrap(),
+            serde_json::json!(5)
+        );
+        assert_eq!(
+            serde_json::Value::try_from(Value::Null).unwrap(),
+            serde_json::Value::Null
+        );
+        assert!(matches!(
+            serde_json::Value::try_from(Value::Text("{".to_string())),
+            Err(ValueError::Invalid { target: "JSON", .. })
+        ));
+    }
+}
@@ -0,0 +1,75 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Test the query_checked! macro against the schema.sql file.
+
+#![cfg(feature = "checked")]
+
+use bsqlite::{query_checked, Connection, Value};
+
+fn test_database() -> Connection {
+    let db = Connection::open_memory().expect("Can't open database");
+    db.execute_batch(include_str!("../schema.sql"))
+        .expect("Can't create schema");
+    db.execute(
+        "INSERT INTO persons (name, age) VALUES ('Alice', 30), ('Bob', 17), ('Charlie', 40)",
+        (),
+    );
+    db
+}
+
+#[test]
+fn test_query_checked_positional_args() {
+    let db = test_database();
+    let ids = query_checked!(
+        i64,
+        db,
+        "SELECT id FROM persons WHERE age > ? ORDER BY id",
+        18
+    )
+    .collect::<Vec<_>>();
+    assert_eq!(ids, vec![1, 3]);
+
+    // Tuple and Vec<Value> variables are counted when the query is run
+    let args = ("Alice".to_string(), 18);
+    let ids = query_checked!(
+        i64,
+        db,
+        "SELECT id FROM persons WHERE name = ? AND age > ?",
+        args
+    )
+    .collect::<Vec<_>>();
+    assert_eq!(ids, vec![1]);
+    let args = vec![Value::Integer(18), Value::Integer(35)];
+    let names = query_checked!(
+        String,
+        db,
+        "SELECT name FROM persons WHERE age > ? AND age < ?",
+        args
+    )
+    .collect::<Vec<_>>();
+    assert_eq!(names, vec!["Alice"]);
+}
+
+#[test]
+fn test_query_checked_named_args() {
+    let db = test_database();
+    let name = "Bob".to_string();
+    let rows = query_checked!(
+        (i64, String, i64),
+        db,
+        "SELECT id, name, age FROM persons WHERE name = :name OR age >= :age ORDER BY id",
+        Args {
+            name: &name,
+            age: 40,
+        }
+    )
+    .collect::<Vec<_>>();
+    assert_eq!(
+        rows,
+        vec![(2, "Bob".to_string(), 17), (3, "Charlie".to_string(), 40)]
+    );
+}
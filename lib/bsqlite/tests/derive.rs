@@ -0,0 +1,358 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Test the derive generated helpers.
+
+#![cfg(feature = "derive")]
+
+use bsqlite::{Connection, ConstraintKind, FromRow, FromValue, Value};
+
+#[derive(Debug, Clone, PartialEq, FromRow)]
+#[sqlite(table = "persons", primary_key = "id")]
+struct Person {
+    #[sqlite(default)]
+    id: i64,
+    name: String,
+    #[sqlite(rename = "age")]
+    age_in_years: i64,
+    #[sqlite(default)]
+    created_at: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, FromRow)]
+#[sqlite(table = "tags", primary_key = "name")]
+struct Tag {
+    name: String,
+    color: Option<String>,
+    #[sqlite(skip)]
+    usage_count: i64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, FromValue)]
+#[sqlite(as_text)]
+enum Relation {
+    Me,
+    Friend,
+    #[sqlite(rename = "family")]
+    FamilyMember,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, FromValue)]
+struct PersonId(i64);
+
+#[derive(Debug, Clone, PartialEq, FromRow)]
+struct Address {
+    street: String,
+    city: String,
+}
+
+#[derive(Debug, Clone, PartialEq, FromRow)]
+struct Contact {
+    id: PersonId,
+    relation: Relation,
+    #[sqlite(flatten)]
+    address: Address,
+}
+
+fn test_database() -> Connection {
+    let db = Connection::open_memory().expect("Can't open database");
+    db.execute_batch(
+        "CREATE TABLE persons (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            age INTEGER NOT NULL,
+            created_at INTEGER NOT NULL DEFAULT 1234
+        ) STRICT;
+        CREATE TABLE tags (name TEXT PRIMARY KEY, color TEXT) STRICT;",
+    )
+    .expect("Can't create tables");
+    db
+}
+
+#[test]
+fn test_derive_crud() {
+    let db = test_database();
+    assert_eq!(Person::table(), "persons");
+    assert_eq!(Person::columns(), "id, name, age, created_at");
+
+    // Insert fills default columns
+    let mut alice = Person {
+        id: 0,
+        name: "Alice".to_string(),
+        age_in_years: 30,
+        created_at: 0,
+    }
+    .insert(&db)
+    .unwrap();
+    assert_eq!(alice.id, 1);
+    assert_eq!(alice.created_at, 1234);
+    let bob = Person {
+        id: 0,
+        name: "Bob".to_string(),
+        age_in_years: 40,
+        created_at: 0,
+    }
+    .insert(&db)
+    .unwrap();
+    assert_eq!(bob.id, 2);
+
+    // Find and update
+    assert_eq!(Person::find_by_id(&db, 1).unwrap(), Some(alice.clone()));
+    assert_eq!(Person::find_by_id(&db, 3).unwrap(), None);
+    alice.age_in_years = 31;
+    alice.update(&db).unwrap();
+    assert_eq!(
+        Person::find_by_id(&db, 1).unwrap().unwrap().age_in_years,
+        31
+    );
+
+    // Constraint errors are returned
+    let mut duplicate = bob.clone();
+    duplicate.name = "Alice".to_string();
+    assert!(duplicate
+        .update(&db)
+        .unwrap_err()
+        .is_constraint(ConstraintKind::Unique));
+
+    // All and delete
+    assert_eq!(Person::all(&db).unwrap(), vec![alice.clone(), bob.clone()]);
+    alice.delete(&db).unwrap();
+    assert_eq!(Person::all(&db).unwrap(), vec![bob]);
+}
+
+#[test]
+fn test_derive_crud_text_primary_key() {
+    let db = test_database();
+    let tag = Tag {
+        name: "rust".to_string(),
+        color: Some("orange".to_string()),
+        usage_count: 5,
+    };
+    let inserted = tag.insert(&db).unwrap();
+    assert_eq!(inserted.usage_count, 0);
+
+    let mut tag = Tag::find_by_id(&db, "rust".to_string()).unwrap().unwrap();
+    assert_eq!(tag.color.as_deref(), Some("orange"));
+    tag.color = None;
+    tag.update(&db).unwrap();
+    assert_eq!(Tag::all(&db).unwrap(), vec![tag.clone()]);
+    tag.delete(&db).unwrap();
+    assert!(Tag::all(&db).unwrap().is_empty());
+}
+
+#[test]
+fn test_derive_text_enum_and_newtype() {
+    assert_eq!(
+        Value::from(Relation::Friend),
+        Value::Text("Friend".to_string())
+    );
+    assert_eq!(
+        Value::from(Relation::FamilyMember),
+        Value::Text("family".to_string())
+    );
+    assert_eq!(
+        Relation::try_from(Value::Text("family".to_string())).unwrap(),
+        Relation::FamilyMember
+    );
+    assert!(Relation::try_from(Value::Text("Enemy".to_string())).is_err());
+    assert!(Relation::try_from(Value::Integer(0)).is_err());
+
+    assert_eq!(Value::from(PersonId(5)), Value::Integer(5));
+    assert_eq!(PersonId::try_from(Value::Integer(5)).unwrap(), PersonId(5));
+    assert!(PersonId::try_from(Value::Null).is_err());
+}
+
+#[test]
+fn test_derive_flatten() {
+    assert_eq!(Contact::columns(), "id, relation, street, city");
+    assert_eq!(Contact::values(), "?, ?, ?, ?");
+
+    let db = Connection::open_memory().expect("Can't open database");
+    db.execute(
+        "CREATE TABLE contacts (city TEXT NOT NULL, street TEXT NOT NULL, relation TEXT NOT NULL, id INTEGER PRIMARY KEY) STRICT",
+        (),
+    );
+    let contact = Contact {
+        id: PersonId(1),
+        relation: Relation::FamilyMember,
+        address: Address {
+            street: "Main Street 1".to_string(),
+            city: "Amsterdam".to_string(),
+        },
+    };
+    db.execute(
+        format!(
+            "INSERT INTO contacts ({}) VALUES ({})",
+            Contact::columns(),
+            Contact::values()
+        ),
+        contact.clone(),
+    );
+
+    // Columns in another order are read by name
+    assert_eq!(
+        db.query_some::<Contact>("SELECT * FROM contacts", ()),
+        contact
+    );
+    assert_eq!(
+        db.query_some::<String>("SELECT relation FROM contacts", ()),
+        "family"
+    );
+}
+
+#[test]
+fn test_derive_flatten_duplicate_columns() {
+    #[derive(Debug, Clone, PartialEq, FromRow)]
+    struct Building {
+        id: i64,
+        street: String,
+    }
+
+    #[derive(Debug, Clone, PartialEq, FromRow)]
+    struct Owner {
+        id: i64,
+        name: String,
+        #[sqlite(flatten)]
+        building: Building,
+    }
+
+    // Duplicate column names and expressions are read by position
+    let db = Connection::open_memory().expect("Can't open database");
+    assert_eq!(
+        db.query_some::<Owner>(
+            "SELECT 1 AS id, 'A' AS name, 99 AS id, 'Main' AS street",
+            ()
+        ),
+        Owner {
+            id: 1,
+            name: "A".to_string(),
+            building: Building {
+                id: 99,
+                street: "Main".to_string(),
+            },
+        }
+    );
+    assert_eq!(
+        db.query_some::<Building>("SELECT 40 + 2, upper('main')", ()),
+        Building {
+            id: 42,
+            street: "MAIN".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_derive_conversion_error() {
+    let db = test_database();
+    db.execute("INSERT INTO tags (name, color) VALUES ('rust', NULL)", ());
+    let err = db
+        .try_query::<Address>("SELECT name AS street, color AS city FROM tags", ())
+        .expect("Can't prepare query")
+        .try_next()
+        .unwrap_err();
+    assert!(err.to_string().contains("Can't read value of column: city"));
+    assert!(db
+        .try_query::<Address>("SELECT name AS street FROM tags", ())
+        .expect("Can't prepare query")
+        .try_next()
+        .unwrap_err()
+        .to_string()
+        .contains("Can't find column: city"));
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_derive_json() {
+    use bsqlite::Json;
+
+    #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Settings {
+        theme: String,
+        tags: Vec<String>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, FromRow)]
+    #[sqlite(table = "users", primary_key = "id")]
+    struct User {
+        #[sqlite(default)]
+        id: i64,
+        #[sqlite(json)]
+        settings: Settings,
+        #[sqlite(json)]
+        nickname: Option<String>,
+    }
+
+    let db = Connection::open_memory().expect("Can't open database");
+    db.execute(
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, settings TEXT NOT NULL, nickname TEXT) STRICT",
+        (),
+    );
+    let user = User {
+        id: 0,
+        settings: Settings {
+            theme: "dark".to_string(),
+            tags: vec!["a".to_string(), "b".to_string()],
+        },
+        nickname: None,
+    }
+    .insert(&db)
+    .unwrap();
+    assert_eq!(User::find_by_id(&db, user.id).unwrap(), Some(user.clone()));
+    assert_eq!(
+        db.query_some::<String>("SELECT settings->>'theme' FROM users", ()),
+        "dark"
+    );
+    assert_eq!(
+        db.query_some::<Option<String>>("SELECT nickname FROM users", ()),
+        None
+    );
+
+    // Json can also be used directly
+    let Json(tags) = db.query_some::<Json<Vec<String>>>("SELECT settings->'tags' FROM users", ());
+    assert_eq!(tags, vec!["a", "b"]);
+}
+
+#[test]
+fn test_derive_fts_table() {
+    #[derive(Debug, Clone, PartialEq, FromRow)]
+    #[sqlite(
+        table = "notes",
+        primary_key = "id",
+        fts = "notes_fts",
+        fts_rowid = "id"
+    )]
+    struct Note {
+        #[sqlite(default)]
+        id: i64,
+        #[sqlite(fts)]
+        title: String,
+        #[sqlite(fts, rename = "content")]
+        body: String,
+    }
+
+    let db = Connection::open_memory().expect("Can't open database");
+    db.execute(
+        "CREATE TABLE notes (id INTEGER PRIMARY KEY, title TEXT NOT NULL, content TEXT NOT NULL) STRICT",
+        (),
+    );
+    let fts = Note::fts_table();
+    fts.create(&db).unwrap();
+    let note = Note {
+        id: 0,
+        title: "Shopping".to_string(),
+        body: "Buy apples and pears".to_string(),
+    }
+    .insert(&db)
+    .unwrap();
+
+    let hits = fts.search::<Note>(&db, "apples", 10, 0).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].row, note);
+    assert_eq!(
+        hits[0].highlights,
+        vec!["Shopping", "Buy <mark>apples</mark> and pears"]
+    );
+}
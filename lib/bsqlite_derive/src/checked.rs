@@ -0,0 +1,502 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+//! The [query_checked!] macro that checks queries against the database schema at compile time
+
+#![allow(unsafe_code)]
+
+use std::collections::HashSet;
+use std::ffi::{CStr, CString};
+use std::path::{Path, PathBuf};
+use std::{env, fs, ptr};
+
+use libsqlite3_sys::*;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use syn::{braced, Expr, Ident, LitStr, Token, Type};
+
+/// Types that are read from a single column
+const SCALAR_TYPES: &[&str] = &[
+    "bool",
+    "i8",
+    "i16",
+    "i32",
+    "i64",
+    "u8",
+    "u16",
+    "u32",
+    "u64",
+    "f32",
+    "f64",
+    "String",
+    "Vec",
+    "Option",
+    "Value",
+    "Uuid",
+    "DateTime",
+    "NaiveDate",
+    "NaiveTime",
+    "NaiveDateTime",
+];
+
+// MARK: Input
+struct NamedArg {
+    ident: Ident,
+    value: Expr,
+}
+
+impl Parse for NamedArg {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let ident = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let value = input.parse()?;
+        Ok(Self { ident, value })
+    }
+}
+
+enum Args {
+    None,
+    Positional(Expr),
+    Named(Vec<NamedArg>),
+}
+
+pub(crate) struct QueryChecked {
+    ty: Type,
+    db: Expr,
+    query: LitStr,
+    args: Args,
+}
+
+impl Parse for QueryChecked {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let ty = input.parse()?;
+        input.parse::<Comma>()?;
+        let db = input.parse()?;
+        input.parse::<Comma>()?;
+        let query = input.parse()?;
+
+        let mut args = Args::None;
+        if input.parse::<Option<Comma>>()?.is_some() && !input.is_empty() {
+            let fork = input.fork();
+            if fork.parse::<Ident>().map_or(false, |ident| ident == "Args")
+                && fork.peek(syn::token::Brace)
+            {
+                input.parse::<Ident>()?;
+                let content;
+                braced!(content in input);
+                args = Args::Named(
+                    Punctuated::<NamedArg, Comma>::parse_terminated(&content)?
+                        .into_iter()
+                        .collect(),
+                );
+            } else {
+                args = Args::Positional(input.parse()?);
+            }
+            input.parse::<Option<Comma>>()?;
+        }
+        Ok(Self {
+            ty,
+            db,
+            query,
+            args,
+        })
+    }
+}
+
+// MARK: Database
+struct Database(*mut sqlite3);
+
+impl Database {
+    fn open(path: &str, flags: i32) -> Result<Self, String> {
+        let path = CString::new(path).map_err(|_| "Invalid database path".to_string())?;
+        let mut db = ptr::null_mut();
+        let result = unsafe { sqlite3_open_v2(path.as_ptr(), &mut db, flags, ptr::null()) };
+        let database = Self(db);
+        if result != SQLITE_OK {
+            return Err(format!("Can't open database: {}", database.error_message()));
+        }
+        Ok(database)
+    }
+
+    fn open_memory() -> Result<Self, String> {
+        Self::open(":memory:", SQLITE_OPEN_READWRITE | SQLITE_OPEN_CREATE)
+    }
+
+    fn open_read_only(path: &Path) -> Result<Self, String> {
+        Self::open(
+            path.to_str().ok_or("Invalid database path")?,
+            SQLITE_OPEN_READONLY,
+        )
+    }
+
+    fn error_message(&self) -> String {
+        unsafe { CStr::from_ptr(sqlite3_errmsg(self.0)) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn execute_batch(&self, sql: &str) -> Result<(), String> {
+        let sql = CString::new(sql).map_err(|_| "Schema contains a nul byte".to_string())?;
+        let result =
+            unsafe { sqlite3_exec(self.0, sql.as_ptr(), None, ptr::null_mut(), ptr::null_mut()) };
+        if result != SQLITE_OK {
+            return Err(self.error_message());
+        }
+        Ok(())
+    }
+
+    fn describe(&self, sql: &str) -> Result<Description, String> {
+        let sql = CString::new(sql).map_err(|_| "Query contains a nul byte".to_string())?;
+        let mut statement = ptr::null_mut();
+        let mut tail = ptr::null();
+        let result =
+            unsafe { sqlite3_prepare_v2(self.0, sql.as_ptr(), -1, &mut statement, &mut tail) };
+        if result != SQLITE_OK {
+            return Err(self.error_message());
+        }
+        if statement.is_null() {
+            return Err("Query is empty".to_string());
+        }
+
+        let read_str = |str: *const std::os::raw::c_char| {
+            if str.is_null() {
+                None
+            } else {
+                Some(
+                    unsafe { CStr::from_ptr(str) }
+                        .to_string_lossy()
+                        .into_owned(),
+                )
+            }
+        };
+        let description = unsafe {
+            Description {
+                parameters: (1..=sqlite3_bind_parameter_count(statement))
+                    .map(|index| read_str(sqlite3_bind_parameter_name(statement, index)))
+                    .collect(),
+                columns: (0..sqlite3_column_count(statement))
+                    .map(|index| {
+                        read_str(sqlite3_column_name(statement, index)).unwrap_or_default()
+                    })
+                    .collect(),
+            }
+        };
+        let has_tail = !read_str(tail).unwrap_or_default().trim().is_empty();
+        unsafe { sqlite3_finalize(statement) };
+        if has_tail {
+            return Err("Query contains multiple statements".to_string());
+        }
+        Ok(description)
+    }
+}
+
+impl Drop for Database {
+    fn drop(&mut self) {
+        unsafe { sqlite3_close(self.0) };
+    }
+}
+
+struct Description {
+    parameters: Vec<Option<String>>,
+    columns: Vec<String>,
+}
+
+// MARK: Schema
+/// Open the schema database, from the `DATABASE_URL` SQLite file or else from
+/// the `schema.sql` file or the `migrations/*.sql` files of the crate
+fn open_schema(manifest_dir: &Path) -> Result<(Database, Vec<PathBuf>), String> {
+    if let Ok(url) = env::var("DATABASE_URL") {
+        let path = url
+            .strip_prefix("sqlite://")
+            .or_else(|| url.strip_prefix("sqlite:"))
+            .unwrap_or(&url);
+        let path = manifest_dir.join(path);
+        return Ok((Database::open_read_only(&path)?, vec![path]));
+    }
+
+    let schema_path = manifest_dir.join("schema.sql");
+    let paths = if schema_path.exists() {
+        vec![schema_path]
+    } else {
+        let mut paths = fs::read_dir(manifest_dir.join("migrations"))
+            .map_err(|_| {
+                "No schema found, set DATABASE_URL or add a schema.sql file or migrations directory"
+                    .to_string()
+            })?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().map_or(false, |ext| ext == "sql"))
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths
+    };
+
+    let database = Database::open_memory()?;
+    for path in &paths {
+        let sql = fs::read_to_string(path)
+            .map_err(|err| format!("Can't read {}: {}", path.display(), err))?;
+        database
+            .execute_batch(&sql)
+            .map_err(|err| format!("Can't apply {}: {}", path.display(), err))?;
+    }
+    Ok((database, paths))
+}
+
+// MARK: Checks
+fn check_args(description: &Description, args: &Args, span: Span) -> syn::Result<()> {
+    match args {
+        Args::Named(args) => {
+            let mut names = HashSet::new();
+            for parameter in &description.parameters {
+                match parameter.as_deref().filter(|name| !name.starts_with('?')) {
+                    Some(name) => _ = names.insert(name[1..].to_string()),
+                    None => {
+                        return Err(syn::Error::new(
+                            span,
+                            "Query has positional parameters, use positional arguments instead of Args { .. }",
+                        ))
+                    }
+                }
+            }
+            for arg in args {
+                if !names.remove(&arg.ident.to_string()) {
+                    return Err(syn::Error::new(
+                        arg.ident.span(),
+                        format!("Query has no parameter named :{}", arg.ident),
+                    ));
+                }
+            }
+            if let Some(name) = names.iter().min() {
+                return Err(syn::Error::new(
+                    span,
+                    format!("Missing argument for query parameter :{}", name),
+                ));
+            }
+        }
+        args => {
+            // Only literal tuples and values can be counted, a variable can hold a
+            // tuple or a Vec<Value> so its count is checked when the query is run
+            let count = match args {
+                Args::Positional(Expr::Tuple(tuple)) => Some(tuple.elems.len()),
+                Args::Positional(Expr::Lit(_)) => Some(1),
+                Args::Positional(_) => None,
+                _ => Some(0),
+            };
+            if description
+                .parameters
+                .iter()
+                .flatten()
+                .any(|name| !name.starts_with('?'))
+            {
+                return Err(syn::Error::new(
+                    span,
+                    "Query has named parameters, use Args { .. } instead of positional arguments",
+                ));
+            }
+            if let Some(count) = count.filter(|count| *count != description.parameters.len()) {
+                return Err(syn::Error::new(
+                    span,
+                    format!(
+                        "Query has {} parameters but {} arguments are given",
+                        description.parameters.len(),
+                        count
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check the result columns, returns the columns that a [FromRow] struct must have
+fn check_columns(description: &Description, ty: &Type, span: Span) -> syn::Result<Option<String>> {
+    let expected = match ty {
+        Type::Tuple(tuple) if tuple.elems.is_empty() => return Ok(None),
+        Type::Tuple(tuple) => tuple.elems.len(),
+        Type::Path(path)
+            if path.qself.is_none()
+                && path.path.segments.last().map_or(false, |segment| {
+                    SCALAR_TYPES.contains(&segment.ident.to_string().as_str())
+                }) =>
+        {
+            1
+        }
+        _ => return Ok(Some(description.columns.join(", "))),
+    };
+    if description.columns.len() != expected {
+        return Err(syn::Error::new(
+            span,
+            format!(
+                "Query returns {} columns but {} are expected",
+                description.columns.len(),
+                expected
+            ),
+        ));
+    }
+    Ok(None)
+}
+
+// MARK: Expand
+pub(crate) fn expand(input: QueryChecked) -> syn::Result<TokenStream> {
+    let QueryChecked {
+        ty,
+        db,
+        query,
+        args,
+    } = input;
+    let span = query.span();
+
+    let manifest_dir =
+        PathBuf::from(env::var("CARGO_MANIFEST_DIR").map_err(|err| syn::Error::new(span, err))?);
+    let (database, paths) =
+        open_schema(&manifest_dir).map_err(|err| syn::Error::new(Span::call_site(), err))?;
+    let description = database
+        .describe(&query.value())
+        .map_err(|err| syn::Error::new(span, format!("Invalid query: {}", err)))?;
+    check_args(&description, &args, span)?;
+    let columns = check_columns(&description, &ty, span)?;
+
+    // Include the schema files so the crate is rebuilt when they change
+    let paths = paths.iter().map(|path| path.to_string_lossy().into_owned());
+    let columns_check = columns.map(|columns| {
+        let message = format!(
+            "query_checked!: the query columns `{}` don't match the columns() of the FromRow type",
+            columns
+        );
+        quote! {
+            const _: () = {
+                const fn columns_eq(a: &str, b: &str) -> bool {
+                    let (a, b) = (a.as_bytes(), b.as_bytes());
+                    if a.len() != b.len() {
+                        return false;
+                    }
+                    let mut i = 0;
+                    while i < a.len() {
+                        if a[i] != b[i] {
+                            return false;
+                        }
+                        i += 1;
+                    }
+                    true
+                }
+                assert!(columns_eq(<#ty>::columns(), #columns), #message);
+            };
+        }
+    });
+    let statement = match args {
+        Args::None => quote! { #db.query::<#ty>(#query, ()) },
+        Args::Positional(args) => quote! { #db.query::<#ty>(#query, #args) },
+        Args::Named(args) => {
+            let binds = args.iter().map(|arg| {
+                let name = description
+                    .parameters
+                    .iter()
+                    .flatten()
+                    .find(|name| arg.ident == name[1..])
+                    .expect("Should be some");
+                let value = &arg.value;
+                quote! { statement.bind_named_value(#name, #value); }
+            });
+            quote! {{
+                let mut statement = #db.prepare_cached::<#ty>(#query);
+                #( #binds )*
+                statement
+            }}
+        }
+    };
+    Ok(quote! {{
+        #( const _: &[u8] = include_bytes!(#paths); )*
+        #columns_check
+        #statement
+    }})
+}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn describe(sql: &str) -> Result<Description, String> {
+        let database = Database::open_memory().unwrap();
+        database
+            .execute_batch(
+                "CREATE TABLE persons (id INTEGER PRIMARY KEY, name TEXT NOT NULL, age INTEGER) STRICT;",
+            )
+            .unwrap();
+        database.describe(sql)
+    }
+
+    #[test]
+    fn test_describe() {
+        let description = describe("SELECT * FROM persons WHERE name = :name AND age > ?").unwrap();
+        assert_eq!(description.columns, vec!["id", "name", "age"]);
+        assert_eq!(
+            description.parameters,
+            vec![Some(":name".to_string()), None]
+        );
+
+        assert!(describe("SELECT nmae FROM persons")
+            .err()
+            .unwrap_or_default()
+            .contains("no such column: nmae"));
+        assert!(describe("SELECT 1; SELECT 2").is_err());
+    }
+
+    #[test]
+    fn test_check_args() {
+        let span = Span::call_site();
+        let description = describe("SELECT id FROM persons WHERE id = ? AND age > ?").unwrap();
+        let args = Args::Positional(syn::parse_quote!((1, 2)));
+        assert!(check_args(&description, &args, span).is_ok());
+        let args = Args::Positional(syn::parse_quote!(1));
+        assert!(check_args(&description, &args, span).is_err());
+        assert!(check_args(&description, &Args::None, span).is_err());
+        let args = Args::Positional(syn::parse_quote!(args));
+        assert!(check_args(&description, &args, span).is_ok());
+
+        let description =
+            describe("SELECT id FROM persons WHERE name LIKE :query LIMIT :limit").unwrap();
+        let args = Args::Named(vec![
+            syn::parse_quote!(query: "%a%"),
+            syn::parse_quote!(limit: 10),
+        ]);
+        assert!(check_args(&description, &args, span).is_ok());
+        let args = Args::Named(vec![syn::parse_quote!(query: "%a%")]);
+        assert!(check_args(&description, &args, span)
+            .unwrap_err()
+            .to_string()
+            .contains(":limit"));
+        let args = Args::Named(vec![
+            syn::parse_quote!(query: "%a%"),
+            syn::parse_quote!(limit: 10),
+            syn::parse_quote!(offset: 0),
+        ]);
+        assert!(check_args(&description, &args, span).is_err());
+        let args = Args::Positional(syn::parse_quote!(("%a%", 10)));
+        assert!(check_args(&description, &args, span).is_err());
+    }
+
+    #[test]
+    fn test_check_columns() {
+        let span = Span::call_site();
+        let description = describe("SELECT id, name AS person_name FROM persons").unwrap();
+        assert!(check_columns(&description, &syn::parse_quote!(()), span)
+            .unwrap()
+            .is_none());
+        assert!(check_columns(&description, &syn::parse_quote!((i64, String)), span).is_ok());
+        assert!(check_columns(&description, &syn::parse_quote!((i64, String, i64)), span).is_err());
+        assert!(check_columns(&description, &syn::parse_quote!(Option<i64>), span).is_err());
+        assert_eq!(
+            check_columns(&description, &syn::parse_quote!(Person), span).unwrap(),
+            Some("id, person_name".to_string())
+        );
+
+        let description = describe("SELECT COUNT(id) FROM persons").unwrap();
+        assert!(check_columns(&description, &syn::parse_quote!(i64), span).is_ok());
+    }
+}
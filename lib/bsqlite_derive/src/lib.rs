@@ -5,19 +5,78 @@
  */
 
 #![doc = include_str!("../README.md")]
-#![forbid(unsafe_code)]
+#![deny(unsafe_code)]
 
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{parse_macro_input, DeriveInput};
 
+#[cfg(feature = "checked")]
+mod checked;
+
+struct Field {
+    ident: syn::Ident,
+    ty: syn::Type,
+    column: String,
+    is_default: bool,
+    is_json: bool,
+    is_flatten: bool,
+    is_fts: bool,
+}
+
+fn parse_attr_list(
+    attr: &syn::Attribute,
+) -> syn::punctuated::Punctuated<syn::Meta, syn::token::Comma> {
+    attr.parse_args_with(syn::punctuated::Punctuated::<_, syn::token::Comma>::parse_terminated)
+        .expect("Invalid attribute")
+}
+
+fn meta_str_value(nv: &syn::MetaNameValue, name: &str) -> String {
+    if let syn::Expr::Lit(syn::ExprLit {
+        lit: syn::Lit::Str(lit_str),
+        ..
+    }) = &nv.value
+    {
+        lit_str.value()
+    } else {
+        panic!("Invalid #[sqlite({})] value", name)
+    }
+}
+
 /// [FromRow] derive for structs
 #[proc_macro_derive(FromRow, attributes(sqlite))]
 pub fn from_row_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = input.ident;
 
-    // Parse fields and handle #[sqlite(skip)] and #[sqlite(rename = "example")] attributes
+    // Parse #[sqlite(table = "persons", primary_key = "id", fts = "persons_fts", fts_rowid = "seq")] struct attribute
+    let mut table = None;
+    let mut primary_key = None;
+    let mut fts = None;
+    let mut fts_rowid = None;
+    for attr in &input.attrs {
+        if attr.path().is_ident("sqlite") {
+            for meta in parse_attr_list(attr) {
+                if let syn::Meta::NameValue(nv) = &meta {
+                    if nv.path.is_ident("table") {
+                        table = Some(meta_str_value(nv, "table"));
+                    }
+                    if nv.path.is_ident("primary_key") {
+                        primary_key = Some(meta_str_value(nv, "primary_key"));
+                    }
+                    if nv.path.is_ident("fts") {
+                        fts = Some(meta_str_value(nv, "fts"));
+                    }
+                    if nv.path.is_ident("fts_rowid") {
+                        fts_rowid = Some(meta_str_value(nv, "fts_rowid"));
+                    }
+                }
+            }
+        }
+    }
+
+    // Parse fields and handle #[sqlite(skip)], #[sqlite(rename = "example")], #[sqlite(default)],
+    // #[sqlite(json)], #[sqlite(flatten)] and #[sqlite(fts)] attributes
     let (fields, has_skipped) = match input.data {
         syn::Data::Struct(data) => {
             let fields_len = data.fields.len();
@@ -25,42 +84,49 @@ pub fn from_row_derive(input: TokenStream) -> TokenStream {
                 .fields
                 .into_iter()
                 .filter_map(|field| {
-                    let mut field_name = field
-                        .ident
-                        .as_ref()
-                        .expect("Invalid field")
-                        .to_string()
-                        .replace("r#", "");
+                    let ident = field.ident.clone().expect("Invalid field");
+                    let mut column = ident.to_string().replace("r#", "");
+                    let mut is_default = false;
+                    let mut is_json = false;
+                    let mut is_flatten = false;
+                    let mut is_fts = false;
                     for attr in &field.attrs {
                         if attr.path().is_ident("sqlite") {
-                            let list = attr
-                                .parse_args_with(
-                                    syn::punctuated::Punctuated::<_, syn::token::Comma>::parse_terminated,
-                                )
-                                .expect("Invalid attribute");
-                            for meta in list {
+                            for meta in parse_attr_list(attr) {
                                 if let syn::Meta::Path(path) = &meta {
                                     if path.is_ident("skip") {
                                         return None;
                                     }
+                                    if path.is_ident("default") {
+                                        is_default = true;
+                                    }
+                                    if path.is_ident("json") {
+                                        is_json = true;
+                                    }
+                                    if path.is_ident("flatten") {
+                                        is_flatten = true;
+                                    }
+                                    if path.is_ident("fts") {
+                                        is_fts = true;
+                                    }
                                 }
                                 if let syn::Meta::NameValue(nv) = &meta {
                                     if nv.path.is_ident("rename") {
-                                        if let syn::Expr::Lit(syn::ExprLit {
-                                            lit: syn::Lit::Str(lit_str),
-                                            ..
-                                        }) = &nv.value
-                                        {
-                                            field_name = lit_str.value();
-                                        } else {
-                                            panic!("Invalid #[sqlite(rename)] value")
-                                        }
+                                        column = meta_str_value(nv, "rename");
                                     }
                                 }
                             }
                         }
                     }
-                    Some((field, field_name))
+                    Some(Field {
+                        ident,
+                        ty: field.ty,
+                        column,
+                        is_default,
+                        is_json,
+                        is_flatten,
+                        is_fts,
+                    })
                 })
                 .collect::<Vec<_>>();
             let has_skipped = fields.len() != fields_len;
@@ -70,93 +136,420 @@ pub fn from_row_derive(input: TokenStream) -> TokenStream {
     };
 
     // Generate code
+    let has_flatten = fields.iter().any(|field| field.is_flatten);
     let columns = fields
         .iter()
-        .map(|(_, field_name)| field_name.clone())
+        .map(|field| field.column.clone())
         .collect::<Vec<_>>()
         .join(", ");
-    let values = vec!["?"; fields.len()].join(", ");
+    let (columns_fn, values_fn) = if has_flatten {
+        // Flattened structs add their columns, so join them at compile time
+        let column_parts = fields.iter().map(|field| {
+            let ty = &field.ty;
+            let column = &field.column;
+            if field.is_flatten {
+                quote! { <#ty>::columns() }
+            } else {
+                quote! { #column }
+            }
+        });
+        let value_parts = fields.iter().map(|field| {
+            let ty = &field.ty;
+            if field.is_flatten {
+                quote! { <#ty>::values() }
+            } else {
+                quote! { "?" }
+            }
+        });
+        (const_join(column_parts), const_join(value_parts))
+    } else {
+        let values = vec!["?"; fields.len()].join(", ");
+        (quote! { #columns }, quote! { #values })
+    };
 
-    let binds = fields.iter().enumerate().map(|(index, (field, _))| {
-        let ident = field.ident.as_ref().expect("Invalid field");
-        quote! { statement.bind_value(#index as i32, self.#ident.into()) }
+    let binds = fields.iter().map(|field| {
+        let ident = &field.ident;
+        if field.is_flatten {
+            quote! { let index = self.#ident.bind_at(statement, index)?; }
+        } else if field.is_json {
+            quote! {
+                statement.try_bind_value(index, bsqlite::Json(self.#ident).into())?;
+                let index = index + 1;
+            }
+        } else {
+            quote! {
+                statement.try_bind_value(index, self.#ident.into())?;
+                let index = index + 1;
+            }
+        }
     });
 
-    let from_rows = fields
-        .iter()
-        .enumerate()
-        .map(|(index, (field, field_name))| {
-            let ident = field.ident.as_ref().expect("Invalid field");
-            quote! { #ident: statement.read_value(#index as i32).try_into().unwrap_or_else(|_| panic!(
-                "Can't read value of column: {}", #field_name
-            )) }
-        });
+    let from_rows = fields.iter().map(|field| {
+        let ident = &field.ident;
+        let ty = &field.ty;
+        let column = &field.column;
+        if field.is_flatten {
+            quote! {
+                #ident: {
+                    let (value, next) = <#ty>::from_row_at(statement, index)?;
+                    index = next;
+                    value
+                }
+            }
+        } else {
+            let read = if field.is_json {
+                quote! { statement.read::<bsqlite::Json<#ty>>(position)?.0 }
+            } else {
+                quote! { statement.read(position)? }
+            };
+            quote! {
+                #ident: {
+                    let position = statement.column_position(index, #column)?;
+                    index += 1;
+                    #read
+                }
+            }
+        }
+    });
     let from_rows_default = if has_skipped {
         quote! { ..Default::default() }
     } else {
         quote! {}
     };
 
+    if has_flatten && table.is_some() {
+        panic!("#[sqlite(flatten)] fields can't be used with #[sqlite(table = \"...\")]");
+    }
+    let fts = fts.map(|fts| {
+        fts_method(
+            &fts,
+            table
+                .as_deref()
+                .expect("#[sqlite(fts = \"...\")] needs #[sqlite(table = \"...\")]"),
+            fts_rowid.as_deref(),
+            &fields,
+        )
+    });
+    if fts.is_none() && fts_rowid.is_some() {
+        panic!("#[sqlite(fts_rowid = \"...\")] needs #[sqlite(fts = \"...\")]");
+    }
+    let crud = table.map(|table| crud_methods(&table, primary_key.as_deref(), &fields, &columns));
+
     TokenStream::from(quote! {
         impl #name {
             pub const fn columns() -> &'static str {
-                #columns
+                #columns_fn
             }
             pub const fn values() -> &'static str {
-                #values
+                #values_fn
             }
+            /// Bind values starting at a parameter index and return the next index
+            #[doc(hidden)]
+            pub fn bind_at(self, statement: &mut bsqlite::RawStatement, index: i32) -> bsqlite::Result<i32> {
+                #( #binds )*
+                Ok(index)
+            }
+            /// Read columns starting at a column index and return the next index
+            #[doc(hidden)]
+            #[allow(unused_mut)]
+            pub fn from_row_at(statement: &mut bsqlite::RawStatement, index: i32) -> bsqlite::Result<(Self, i32)> {
+                let mut index = index;
+                let row = Self {
+                    #( #from_rows, )*
+                    #from_rows_default
+                };
+                Ok((row, index))
+            }
+            #crud
+            #fts
         }
         impl bsqlite::Bind for #name {
-            fn bind(self, statement: &mut bsqlite::RawStatement) {
-                #( #binds; )*
+            fn bind(self, statement: &mut bsqlite::RawStatement) -> bsqlite::Result<()> {
+                self.bind_at(statement, 0)?;
+                Ok(())
             }
         }
         impl bsqlite::FromRow for #name {
-            fn from_row(statement: &mut bsqlite::RawStatement) -> Self {
-                Self {
-                    #( #from_rows, )*
-                    #from_rows_default
-                }
+            fn from_row(statement: &mut bsqlite::RawStatement) -> bsqlite::Result<Self> {
+                Ok(Self::from_row_at(statement, 0)?.0)
             }
         }
     })
 }
 
-/// [FromValue] derive for enums
-#[proc_macro_derive(FromValue)]
+/// Join string parts with ", " in a const expression
+fn const_join(parts: impl Iterator<Item = proc_macro2::TokenStream>) -> proc_macro2::TokenStream {
+    quote! {
+        const PARTS: &[&str] = &[#(#parts),*];
+        const LEN: usize = {
+            let mut len = 0;
+            let mut i = 0;
+            while i < PARTS.len() {
+                if i > 0 {
+                    len += 2;
+                }
+                len += PARTS[i].len();
+                i += 1;
+            }
+            len
+        };
+        const BYTES: [u8; LEN] = {
+            let mut bytes = [0; LEN];
+            let mut position = 0;
+            let mut i = 0;
+            while i < PARTS.len() {
+                if i > 0 {
+                    bytes[position] = b',';
+                    bytes[position + 1] = b' ';
+                    position += 2;
+                }
+                let part = PARTS[i].as_bytes();
+                let mut j = 0;
+                while j < part.len() {
+                    bytes[position] = part[j];
+                    position += 1;
+                    j += 1;
+                }
+                i += 1;
+            }
+            bytes
+        };
+        match std::str::from_utf8(&BYTES) {
+            Ok(joined) => joined,
+            Err(_) => panic!("Invalid UTF-8"),
+        }
+    }
+}
+
+fn fts_method(
+    fts: &str,
+    table: &str,
+    rowid: Option<&str>,
+    fields: &[Field],
+) -> proc_macro2::TokenStream {
+    let columns = fields
+        .iter()
+        .filter(|field| field.is_fts)
+        .map(|field| field.column.as_str())
+        .collect::<Vec<_>>();
+    if columns.is_empty() {
+        panic!("#[sqlite(fts = \"...\")] needs at least one #[sqlite(fts)] field");
+    }
+    let content_rowid = rowid.map(|rowid| quote! { .content_rowid(#rowid) });
+    quote! {
+        /// Get the FTS5 external content table of the #[sqlite(fts)] fields
+        pub fn fts_table() -> bsqlite::fts::FtsTable {
+            bsqlite::fts::FtsTable::new(#fts, #table)
+                .columns(&[#(#columns),*])
+                #content_rowid
+        }
+    }
+}
+
+fn crud_bind(index: usize, field: &Field) -> proc_macro2::TokenStream {
+    let ident = &field.ident;
+    if field.is_json {
+        quote! { statement.try_bind_value(#index as i32, bsqlite::Json(self.#ident.clone()))? }
+    } else {
+        quote! { statement.try_bind_value(#index as i32, self.#ident.clone())? }
+    }
+}
+
+fn crud_methods(
+    table: &str,
+    primary_key: Option<&str>,
+    fields: &[Field],
+    columns: &str,
+) -> proc_macro2::TokenStream {
+    let primary_key = primary_key.unwrap_or("id");
+    let primary_field = fields
+        .iter()
+        .find(|field| field.column == primary_key)
+        .unwrap_or_else(|| panic!("Can't find primary key column: {}", primary_key));
+    let primary_ident = &primary_field.ident;
+    let primary_ty = &primary_field.ty;
+
+    // Insert without columns that are filled by the database and read the full row back
+    let insert_fields = fields
+        .iter()
+        .filter(|field| !field.is_default)
+        .collect::<Vec<_>>();
+    let insert_query = format!(
+        "INSERT INTO {} ({}) VALUES ({}) RETURNING {}",
+        table,
+        insert_fields
+            .iter()
+            .map(|field| field.column.as_str())
+            .collect::<Vec<_>>()
+            .join(", "),
+        vec!["?"; insert_fields.len()].join(", "),
+        columns
+    );
+    let insert_binds = insert_fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| crud_bind(index, field));
+
+    let update_fields = fields
+        .iter()
+        .filter(|field| field.column != primary_key)
+        .collect::<Vec<_>>();
+    let update_query = format!(
+        "UPDATE {} SET {} WHERE {} = ?",
+        table,
+        update_fields
+            .iter()
+            .map(|field| format!("{} = ?", field.column))
+            .collect::<Vec<_>>()
+            .join(", "),
+        primary_key
+    );
+    let update_primary_index = update_fields.len();
+    let update_binds = update_fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| crud_bind(index, field));
+
+    let delete_query = format!("DELETE FROM {} WHERE {} = ?", table, primary_key);
+    let find_query = format!(
+        "SELECT {} FROM {} WHERE {} = ? LIMIT 1",
+        columns, table, primary_key
+    );
+    let all_query = format!("SELECT {} FROM {}", columns, table);
+
+    quote! {
+        /// Get table name
+        pub const fn table() -> &'static str {
+            #table
+        }
+
+        /// Insert row and read it back with the columns filled by the database
+        pub fn insert(&self, db: &bsqlite::Connection) -> bsqlite::Result<Self> {
+            let mut statement = db.try_prepare_cached::<Self>(#insert_query)?;
+            #( #insert_binds; )*
+            Ok(statement.try_next()?.expect("Should be some"))
+        }
+
+        /// Update row by primary key
+        pub fn update(&self, db: &bsqlite::Connection) -> bsqlite::Result<()> {
+            let mut statement = db.try_prepare_cached::<()>(#update_query)?;
+            #( #update_binds; )*
+            statement.try_bind_value(#update_primary_index as i32, self.#primary_ident.clone())?;
+            statement.try_next()?;
+            Ok(())
+        }
+
+        /// Delete row by primary key
+        pub fn delete(&self, db: &bsqlite::Connection) -> bsqlite::Result<()> {
+            db.try_execute(#delete_query, self.#primary_ident.clone())
+        }
+
+        /// Find row by primary key
+        pub fn find_by_id(db: &bsqlite::Connection, id: #primary_ty) -> bsqlite::Result<Option<Self>> {
+            db.query_optional::<Self>(#find_query, id)
+        }
+
+        /// Get all rows
+        pub fn all(db: &bsqlite::Connection) -> bsqlite::Result<Vec<Self>> {
+            let mut statement = db.try_query::<Self>(#all_query, ())?;
+            let mut rows = Vec::new();
+            while let Some(row) = statement.try_next()? {
+                rows.push(row);
+            }
+            Ok(rows)
+        }
+    }
+}
+
+/// [FromValue] derive for enums and newtype structs
+#[proc_macro_derive(FromValue, attributes(sqlite))]
 pub fn from_value_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = input.ident;
 
-    let variants = if let syn::Data::Enum(data) = input.data {
-        data.variants
-    } else {
-        panic!("FromValue can only be used on enums");
+    // Newtype structs convert like their inner type
+    let variants = match input.data {
+        syn::Data::Enum(data) => data.variants,
+        syn::Data::Struct(data) => return newtype_from_value(&name, data.fields),
+        _ => panic!("FromValue can only be used on enums and newtype structs"),
     };
 
-    let from_impls = variants.iter().map(|variant| {
-        let variant_name = &variant.ident;
-        let discriminant = if let Some((_, expr)) = &variant.discriminant {
-            quote! { #expr }
-        } else {
-            panic!("Enum variants must have discriminants");
-        };
-        quote! {
-            bsqlite::Value::Integer(#discriminant) => Ok(#name::#variant_name),
+    // Parse #[sqlite(as_text)] enum attribute
+    let mut as_text = false;
+    for attr in &input.attrs {
+        if attr.path().is_ident("sqlite") {
+            for meta in parse_attr_list(attr) {
+                if let syn::Meta::Path(path) = &meta {
+                    if path.is_ident("as_text") {
+                        as_text = true;
+                    }
+                }
+            }
         }
-    });
+    }
 
-    let to_impls = variants.iter().map(|variant| {
-        let variant_name = &variant.ident;
-        let discriminant = if let Some((_, expr)) = &variant.discriminant {
-            quote! { #expr }
-        } else {
-            panic!("Enum variants must have discriminants");
-        };
+    let (from_impls, to_impls): (Vec<_>, Vec<_>) = variants
+        .iter()
+        .map(|variant| {
+            let variant_name = &variant.ident;
+            if as_text {
+                // Parse #[sqlite(rename = "example")] variant attribute
+                let mut text = variant_name.to_string();
+                for attr in &variant.attrs {
+                    if attr.path().is_ident("sqlite") {
+                        for meta in parse_attr_list(attr) {
+                            if let syn::Meta::NameValue(nv) = &meta {
+                                if nv.path.is_ident("rename") {
+                                    text = meta_str_value(nv, "rename");
+                                }
+                            }
+                        }
+                    }
+                }
+                (
+                    quote! { #text => Ok(#name::#variant_name), },
+                    quote! { #name::#variant_name => bsqlite::Value::Text(#text.to_string()), },
+                )
+            } else {
+                let discriminant = if let Some((_, expr)) = &variant.discriminant {
+                    quote! { #expr }
+                } else {
+                    panic!("Enum variants must have discriminants or use #[sqlite(as_text)]");
+                };
+                (
+                    quote! { bsqlite::Value::Integer(#discriminant) => Ok(#name::#variant_name), },
+                    quote! { #name::#variant_name => bsqlite::Value::Integer(#discriminant), },
+                )
+            }
+        })
+        .unzip();
+
+    let name_str = name.to_string();
+    let from_match = if as_text {
         quote! {
-            #name::#variant_name => bsqlite::Value::Integer(#discriminant),
+            match value {
+                bsqlite::Value::Text(text) => match text.as_str() {
+                    #( #from_impls )*
+                    _ => Err(bsqlite::ValueError::Invalid {
+                        target: #name_str,
+                        message: format!("unknown variant: {}", text),
+                    }),
+                },
+                value => Err(bsqlite::ValueError::type_mismatch(#name_str, &value)),
+            }
         }
-    });
+    } else {
+        quote! {
+            match value {
+                #( #from_impls )*
+                bsqlite::Value::Integer(discriminant) => Err(bsqlite::ValueError::Invalid {
+                    target: #name_str,
+                    message: format!("unknown discriminant: {}", discriminant),
+                }),
+                value => Err(bsqlite::ValueError::type_mismatch(#name_str, &value)),
+            }
+        }
+    };
 
     TokenStream::from(quote! {
         impl From<#name> for bsqlite::Value {
@@ -169,11 +562,59 @@ pub fn from_value_derive(input: TokenStream) -> TokenStream {
         impl TryFrom<bsqlite::Value> for #name {
             type Error = bsqlite::ValueError;
             fn try_from(value: bsqlite::Value) -> Result<Self, Self::Error> {
-                match value {
-                    #( #from_impls )*
-                    _ => Err(bsqlite::ValueError),
-                }
+                #from_match
             }
         }
     })
 }
+
+fn newtype_from_value(name: &syn::Ident, fields: syn::Fields) -> TokenStream {
+    let field = match fields.iter().collect::<Vec<_>>().as_slice() {
+        [field] => (*field).clone(),
+        _ => panic!("FromValue can only be used on structs with a single field"),
+    };
+    let ty = &field.ty;
+    let (access, construct) = match &field.ident {
+        Some(ident) => (quote! { #ident }, quote! { |#ident| Self { #ident } }),
+        None => (quote! { 0 }, quote! { Self }),
+    };
+    TokenStream::from(quote! {
+        impl From<#name> for bsqlite::Value {
+            fn from(value: #name) -> Self {
+                value.#access.into()
+            }
+        }
+        impl TryFrom<bsqlite::Value> for #name {
+            type Error = bsqlite::ValueError;
+            fn try_from(value: bsqlite::Value) -> Result<Self, Self::Error> {
+                <#ty>::try_from(value)
+                    .map(#construct)
+                    .map_err(Into::into)
+            }
+        }
+    })
+}
+
+/// Query macro that checks the query against the database schema at compile time
+///
+/// The schema is read from the `DATABASE_URL` SQLite file or else from the `schema.sql`
+/// file or the `migrations/*.sql` files in the crate directory. The parameters must match
+/// the given arguments and the result columns must match the `columns()` of a [FromRow]
+/// struct, a tuple with the same amount of columns or a single column type.
+/// Only the arguments of a literal tuple or value are counted at compile time, a variable
+/// that holds a tuple or `Vec<Value>` is counted when the query is run.
+///
+/// Changes to the schema files are tracked, but a new migration file is only picked up
+/// when the crate is rebuilt. Add `println!("cargo::rerun-if-changed=migrations");` to
+/// the `build.rs` of the crate to rebuild it when the migrations directory changes.
+///
+/// ```rs
+/// let person = query_checked!(Person, db, "SELECT id, name, age FROM persons WHERE id = ?", id).next();
+/// let persons = query_checked!(Person, db, "SELECT id, name, age FROM persons WHERE age > :age", Args { age: 18 });
+/// ```
+#[cfg(feature = "checked")]
+#[proc_macro]
+pub fn query_checked(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as checked::QueryChecked);
+    TokenStream::from(checked::expand(input).unwrap_or_else(|err| err.to_compile_error()))
+}
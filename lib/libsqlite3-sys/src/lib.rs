@@ -12,9 +12,28 @@ use std::ffi::{c_char, c_int, c_void};
 
 pub type sqlite3 = c_void;
 pub type sqlite3_stmt = c_void;
+pub type sqlite3_context = c_void;
+pub type sqlite3_value = c_void;
+pub type sqlite3_backup = c_void;
+pub type sqlite3_blob = c_void;
+pub type sqlite3_mutex = c_void;
 pub type sqlite3_destructor_type = Option<unsafe extern "C" fn(*mut c_void)>;
 
 pub const SQLITE_OK: i32 = 0;
+pub const SQLITE_ERROR: i32 = 1;
+pub const SQLITE_BUSY: i32 = 5;
+pub const SQLITE_LOCKED: i32 = 6;
+pub const SQLITE_CONSTRAINT: i32 = 19;
+pub const SQLITE_MISMATCH: i32 = 20;
+pub const SQLITE_CANTOPEN: i32 = 14;
+pub const SQLITE_MISUSE: i32 = 21;
+pub const SQLITE_RANGE: i32 = 25;
+pub const SQLITE_CONSTRAINT_CHECK: i32 = SQLITE_CONSTRAINT | (1 << 8);
+pub const SQLITE_CONSTRAINT_FOREIGNKEY: i32 = SQLITE_CONSTRAINT | (3 << 8);
+pub const SQLITE_CONSTRAINT_NOTNULL: i32 = SQLITE_CONSTRAINT | (5 << 8);
+pub const SQLITE_CONSTRAINT_PRIMARYKEY: i32 = SQLITE_CONSTRAINT | (6 << 8);
+pub const SQLITE_CONSTRAINT_UNIQUE: i32 = SQLITE_CONSTRAINT | (8 << 8);
+pub const SQLITE_OPEN_READONLY: i32 = 0x00000001;
 pub const SQLITE_OPEN_CREATE: i32 = 0x00000004;
 pub const SQLITE_OPEN_READWRITE: i32 = 0x00000002;
 pub const SQLITE_OPEN_FULLMUTEX: i32 = 0x00010000;
@@ -25,6 +44,11 @@ pub const SQLITE_FLOAT: i32 = 2;
 pub const SQLITE_TEXT: i32 = 3;
 pub const SQLITE_BLOB: i32 = 4;
 pub const SQLITE_NULL: i32 = 5;
+pub const SQLITE_UTF8: i32 = 1;
+pub const SQLITE_DELETE: i32 = 9;
+pub const SQLITE_INSERT: i32 = 18;
+pub const SQLITE_UPDATE: i32 = 23;
+pub const SQLITE_DETERMINISTIC: i32 = 0x000000800;
 
 pub fn SQLITE_TRANSIENT() -> sqlite3_destructor_type {
     Some(unsafe { std::mem::transmute::<isize, unsafe extern "C" fn(*mut c_void)>(-1) })
@@ -45,10 +69,44 @@ unsafe extern "C" {
         ppStmt: *mut *mut sqlite3_stmt,
         pzTail: *mut *const c_char,
     ) -> c_int;
+    pub fn sqlite3_exec(
+        db: *mut sqlite3,
+        sql: *const c_char,
+        callback: Option<
+            unsafe extern "C" fn(*mut c_void, c_int, *mut *mut c_char, *mut *mut c_char) -> c_int,
+        >,
+        arg: *mut c_void,
+        errmsg: *mut *mut c_char,
+    ) -> c_int;
     pub fn sqlite3_changes(db: *mut sqlite3) -> i32;
     pub fn sqlite3_last_insert_rowid(db: *mut sqlite3) -> i64;
+    pub fn sqlite3_get_autocommit(db: *mut sqlite3) -> c_int;
     pub fn sqlite3_errmsg(db: *mut sqlite3) -> *const c_char;
+    pub fn sqlite3_errcode(db: *mut sqlite3) -> c_int;
+    pub fn sqlite3_extended_errcode(db: *mut sqlite3) -> c_int;
+    pub fn sqlite3_errstr(rc: c_int) -> *const c_char;
+    pub fn sqlite3_db_mutex(db: *mut sqlite3) -> *mut sqlite3_mutex;
+    pub fn sqlite3_mutex_enter(mutex: *mut sqlite3_mutex);
+    pub fn sqlite3_mutex_leave(mutex: *mut sqlite3_mutex);
     pub fn sqlite3_close(db: *mut sqlite3) -> c_int;
+    pub fn sqlite3_close_v2(db: *mut sqlite3) -> c_int;
+    pub fn sqlite3_update_hook(
+        db: *mut sqlite3,
+        xCallback: Option<
+            unsafe extern "C" fn(*mut c_void, c_int, *const c_char, *const c_char, i64),
+        >,
+        pArg: *mut c_void,
+    ) -> *mut c_void;
+    pub fn sqlite3_commit_hook(
+        db: *mut sqlite3,
+        xCallback: Option<unsafe extern "C" fn(*mut c_void) -> c_int>,
+        pArg: *mut c_void,
+    ) -> *mut c_void;
+    pub fn sqlite3_rollback_hook(
+        db: *mut sqlite3,
+        xCallback: Option<unsafe extern "C" fn(*mut c_void)>,
+        pArg: *mut c_void,
+    ) -> *mut c_void;
 
     // sqlite3_stmt
     pub fn sqlite3_db_handle(pStmt: *mut sqlite3_stmt) -> *mut sqlite3;
@@ -56,7 +114,10 @@ unsafe extern "C" {
     pub fn sqlite3_step(pStmt: *mut sqlite3_stmt) -> c_int;
     pub fn sqlite3_reset(pStmt: *mut sqlite3_stmt) -> c_int;
     pub fn sqlite3_finalize(pStmt: *mut sqlite3_stmt) -> c_int;
+    pub fn sqlite3_clear_bindings(pStmt: *mut sqlite3_stmt) -> c_int;
+    pub fn sqlite3_bind_parameter_count(pStmt: *mut sqlite3_stmt) -> c_int;
     pub fn sqlite3_bind_parameter_index(pStmt: *mut sqlite3_stmt, zName: *const c_char) -> c_int;
+    pub fn sqlite3_bind_parameter_name(pStmt: *mut sqlite3_stmt, i: c_int) -> *const c_char;
     pub fn sqlite3_bind_null(pStmt: *mut sqlite3_stmt, i: c_int) -> c_int;
     pub fn sqlite3_bind_int64(pStmt: *mut sqlite3_stmt, i: c_int, value: i64) -> c_int;
     pub fn sqlite3_bind_double(pStmt: *mut sqlite3_stmt, i: c_int, value: f64) -> c_int;
@@ -74,10 +135,97 @@ unsafe extern "C" {
         n: c_int,
         xDel: sqlite3_destructor_type,
     ) -> c_int;
+    pub fn sqlite3_column_count(pStmt: *mut sqlite3_stmt) -> c_int;
+    pub fn sqlite3_column_name(pStmt: *mut sqlite3_stmt, iCol: c_int) -> *const c_char;
     pub fn sqlite3_column_type(pStmt: *mut sqlite3_stmt, iCol: c_int) -> c_int;
     pub fn sqlite3_column_int64(pStmt: *mut sqlite3_stmt, iCol: c_int) -> i64;
     pub fn sqlite3_column_double(pStmt: *mut sqlite3_stmt, iCol: c_int) -> f64;
     pub fn sqlite3_column_text(pStmt: *mut sqlite3_stmt, iCol: c_int) -> *const c_char;
     pub fn sqlite3_column_blob(pStmt: *mut sqlite3_stmt, iCol: c_int) -> *const c_void;
     pub fn sqlite3_column_bytes(pStmt: *mut sqlite3_stmt, iCol: c_int) -> c_int;
+
+    // sqlite3_function
+    pub fn sqlite3_create_function_v2(
+        db: *mut sqlite3,
+        zFunctionName: *const c_char,
+        nArg: c_int,
+        eTextRep: c_int,
+        pApp: *mut c_void,
+        xFunc: Option<unsafe extern "C" fn(*mut sqlite3_context, c_int, *mut *mut sqlite3_value)>,
+        xStep: Option<unsafe extern "C" fn(*mut sqlite3_context, c_int, *mut *mut sqlite3_value)>,
+        xFinal: Option<unsafe extern "C" fn(*mut sqlite3_context)>,
+        xDestroy: Option<unsafe extern "C" fn(*mut c_void)>,
+    ) -> c_int;
+    pub fn sqlite3_create_collation_v2(
+        db: *mut sqlite3,
+        zName: *const c_char,
+        eTextRep: c_int,
+        pArg: *mut c_void,
+        xCompare: Option<
+            unsafe extern "C" fn(*mut c_void, c_int, *const c_void, c_int, *const c_void) -> c_int,
+        >,
+        xDestroy: Option<unsafe extern "C" fn(*mut c_void)>,
+    ) -> c_int;
+    pub fn sqlite3_user_data(ctx: *mut sqlite3_context) -> *mut c_void;
+    pub fn sqlite3_aggregate_context(ctx: *mut sqlite3_context, nBytes: c_int) -> *mut c_void;
+    pub fn sqlite3_value_type(value: *mut sqlite3_value) -> c_int;
+    pub fn sqlite3_value_int64(value: *mut sqlite3_value) -> i64;
+    pub fn sqlite3_value_double(value: *mut sqlite3_value) -> f64;
+    pub fn sqlite3_value_text(value: *mut sqlite3_value) -> *const c_char;
+    pub fn sqlite3_value_blob(value: *mut sqlite3_value) -> *const c_void;
+    pub fn sqlite3_value_bytes(value: *mut sqlite3_value) -> c_int;
+    pub fn sqlite3_result_null(ctx: *mut sqlite3_context);
+    pub fn sqlite3_result_int64(ctx: *mut sqlite3_context, value: i64);
+    pub fn sqlite3_result_double(ctx: *mut sqlite3_context, value: f64);
+    pub fn sqlite3_result_text(
+        ctx: *mut sqlite3_context,
+        z: *const c_char,
+        n: c_int,
+        xDel: sqlite3_destructor_type,
+    );
+    pub fn sqlite3_result_blob(
+        ctx: *mut sqlite3_context,
+        z: *const c_void,
+        n: c_int,
+        xDel: sqlite3_destructor_type,
+    );
+    pub fn sqlite3_result_error(ctx: *mut sqlite3_context, z: *const c_char, n: c_int);
+
+    // sqlite3_backup
+    pub fn sqlite3_backup_init(
+        pDest: *mut sqlite3,
+        zDestName: *const c_char,
+        pSource: *mut sqlite3,
+        zSourceName: *const c_char,
+    ) -> *mut sqlite3_backup;
+    pub fn sqlite3_backup_step(p: *mut sqlite3_backup, nPage: c_int) -> c_int;
+    pub fn sqlite3_backup_finish(p: *mut sqlite3_backup) -> c_int;
+    pub fn sqlite3_backup_remaining(p: *mut sqlite3_backup) -> c_int;
+    pub fn sqlite3_backup_pagecount(p: *mut sqlite3_backup) -> c_int;
+
+    // sqlite3_blob
+    pub fn sqlite3_blob_open(
+        db: *mut sqlite3,
+        zDb: *const c_char,
+        zTable: *const c_char,
+        zColumn: *const c_char,
+        iRow: i64,
+        flags: c_int,
+        ppBlob: *mut *mut sqlite3_blob,
+    ) -> c_int;
+    pub fn sqlite3_blob_reopen(pBlob: *mut sqlite3_blob, iRow: i64) -> c_int;
+    pub fn sqlite3_blob_close(pBlob: *mut sqlite3_blob) -> c_int;
+    pub fn sqlite3_blob_bytes(pBlob: *mut sqlite3_blob) -> c_int;
+    pub fn sqlite3_blob_read(
+        pBlob: *mut sqlite3_blob,
+        Z: *mut c_void,
+        N: c_int,
+        iOffset: c_int,
+    ) -> c_int;
+    pub fn sqlite3_blob_write(
+        pBlob: *mut sqlite3_blob,
+        z: *const c_void,
+        n: c_int,
+        iOffset: c_int,
+    ) -> c_int;
 }
@@ -0,0 +1,234 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+//! A minimal replacement for the [rust_decimal](https://crates.io/crates/rust_decimal) crate
+
+#![forbid(unsafe_code)]
+
+use std::fmt::{self, Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+const MAX_SCALE: u32 = 28;
+const MAX_MANTISSA: i128 = (1 << 96) - 1;
+
+// MARK: Decimal
+/// Decimal number with a 96-bit mantissa and a scale of up to 28 digits
+#[derive(Clone, Copy)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    /// Zero
+    pub const ZERO: Decimal = Decimal {
+        mantissa: 0,
+        scale: 0,
+    };
+
+    /// Create decimal from mantissa and scale
+    pub fn new(num: i64, scale: u32) -> Decimal {
+        Self::from_i128_with_scale(num.into(), scale)
+    }
+
+    /// Create decimal from i128 mantissa and scale
+    pub fn from_i128_with_scale(num: i128, scale: u32) -> Decimal {
+        Self::try_from_i128_with_scale(num, scale).expect("Decimal out of range")
+    }
+
+    /// Try to create decimal from i128 mantissa and scale
+    pub fn try_from_i128_with_scale(num: i128, scale: u32) -> Result<Decimal, Error> {
+        if scale > MAX_SCALE {
+            return Err(Error::ScaleExceedsMaximumPrecision(scale));
+        }
+        if !(-MAX_MANTISSA..=MAX_MANTISSA).contains(&num) {
+            return Err(Error::ExceedsMaximumPossibleValue);
+        }
+        Ok(Decimal {
+            mantissa: num,
+            scale,
+        })
+    }
+
+    /// Get mantissa
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    /// Get scale
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Remove trailing zeros from the fraction
+    pub fn normalize(&self) -> Decimal {
+        let mut decimal = *self;
+        while decimal.scale > 0 && decimal.mantissa % 10 == 0 {
+            decimal.mantissa /= 10;
+            decimal.scale -= 1;
+        }
+        decimal
+    }
+}
+
+impl Default for Decimal {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        let (a, b) = (self.normalize(), other.normalize());
+        a.mantissa == b.mantissa && a.scale == b.scale
+    }
+}
+
+impl Eq for Decimal {}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let decimal = self.normalize();
+        decimal.mantissa.hash(state);
+        decimal.scale.hash(state);
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(value: i64) -> Self {
+        Self::new(value, 0)
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        let digits = if digits.len() <= scale {
+            format!("{}{}", "0".repeat(scale + 1 - digits.len()), digits)
+        } else {
+            digits
+        };
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        if self.mantissa < 0 {
+            f.write_str("-")?;
+        }
+        f.write_str(integer)?;
+        if !fraction.is_empty() {
+            write!(f, ".{}", fraction)?;
+        }
+        Ok(())
+    }
+}
+
+impl Debug for Decimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, s) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        let (integer, fraction) = s.split_once('.').unwrap_or((s, ""));
+        if integer.is_empty() && fraction.is_empty() {
+            return Err(Error::InvalidNumber);
+        }
+        let mut mantissa: i128 = 0;
+        for c in integer.chars().chain(fraction.chars()) {
+            let digit = c.to_digit(10).ok_or(Error::InvalidNumber)?;
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add(digit.into()))
+                .ok_or(Error::ExceedsMaximumPossibleValue)?;
+        }
+        let scale = u32::try_from(fraction.len()).map_err(|_| Error::InvalidNumber)?;
+        Self::try_from_i128_with_scale(if negative { -mantissa } else { mantissa }, scale)
+    }
+}
+
+// MARK: Error
+/// Decimal error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The string is not a valid decimal number
+    InvalidNumber,
+    /// The value doesn't fit in the 96-bit mantissa
+    ExceedsMaximumPossibleValue,
+    /// The scale is larger than 28
+    ScaleExceedsMaximumPrecision(u32),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidNumber => write!(f, "Invalid decimal number"),
+            Error::ExceedsMaximumPossibleValue => {
+                write!(f, "Number exceeds maximum value that can be represented")
+            }
+            Error::ScaleExceedsMaximumPrecision(scale) => {
+                write!(
+                    f,
+                    "Scale exceeds maximum precision: {} > {}",
+                    scale, MAX_SCALE
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_string() {
+        assert_eq!(Decimal::new(12345, 2).to_string(), "123.45");
+        assert_eq!(Decimal::new(-5, 3).to_string(), "-0.005");
+        assert_eq!(Decimal::new(42, 0).to_string(), "42");
+        assert_eq!(Decimal::ZERO.to_string(), "0");
+    }
+
+    #[test]
+    fn test_parse_string() {
+        assert_eq!("123.45".parse::<Decimal>().unwrap(), Decimal::new(12345, 2));
+        assert_eq!("-0.005".parse::<Decimal>().unwrap(), Decimal::new(-5, 3));
+        assert_eq!("+42".parse::<Decimal>().unwrap(), Decimal::from(42));
+        assert_eq!("1.50".parse::<Decimal>().unwrap().to_string(), "1.50");
+        assert_eq!(".5".parse::<Decimal>().unwrap(), Decimal::new(5, 1));
+    }
+
+    #[test]
+    fn test_parse_invalid_string() {
+        assert_eq!("".parse::<Decimal>(), Err(Error::InvalidNumber));
+        assert_eq!("1.2.3".parse::<Decimal>(), Err(Error::InvalidNumber));
+        assert_eq!("1e5".parse::<Decimal>(), Err(Error::InvalidNumber));
+        assert_eq!(
+            "99999999999999999999999999999".parse::<Decimal>(),
+            Err(Error::ExceedsMaximumPossibleValue)
+        );
+        assert_eq!(
+            "0.00000000000000000000000000001".parse::<Decimal>(),
+            Err(Error::ScaleExceedsMaximumPrecision(29))
+        );
+    }
+
+    #[test]
+    fn test_equality() {
+        assert_eq!(Decimal::new(150, 2), Decimal::new(15, 1));
+        assert_ne!(Decimal::new(150, 2), Decimal::new(15, 2));
+        assert_eq!(Decimal::new(1500, 3).normalize().to_string(), "1.5");
+    }
+}
@@ -9,7 +9,7 @@ use std::fmt::{self, Display, Formatter};
 use std::io::{BufRead, BufReader, Read, Write};
 use std::net::TcpStream;
 
-use crate::enums::{Status, Version};
+use crate::enums::{Method, Status, Version};
 use crate::header_map::HeaderMap;
 use crate::request::Request;
 use crate::KEEP_ALIVE_TIMEOUT;
@@ -208,15 +208,20 @@ impl Response {
             _ = write!(stream, "{}: {}\r\n", name, value);
         }
         _ = write!(stream, "\r\n");
-        _ = stream.write_all(&self.body);
+        if req.method != Method::Head {
+            _ = stream.write_all(&self.body);
+        }
     }
 
     fn finish_headers(&mut self, req: &Request, keep_alive: bool) {
         #[cfg(feature = "date")]
         self.headers
             .insert("Date".to_string(), chrono::Utc::now().to_rfc2822());
-        self.headers
-            .insert("Content-Length".to_string(), self.body.len().to_string());
+        // HEAD responses keep the Content-Length of the GET response they are stripped from
+        if req.method != Method::Head || self.headers.get("Content-Length").is_none() {
+            self.headers
+                .insert("Content-Length".to_string(), self.body.len().to_string());
+        }
         if req.version == Version::Http1_1 {
             if keep_alive && req.headers.get("Connection").map(|v| v.as_str()) != Some("close") {
                 if self.headers.get("Connection").is_none() {
@@ -336,6 +341,22 @@ mod test {
         assert!(response_text.contains("\r\n\r\nHello, world!"));
     }
 
+    #[test]
+    fn test_write_head_response() {
+        let mut response = Response::with_status(Status::Ok).header("Content-Length", "13");
+        let mut response_stream = Vec::new();
+        let request = Request {
+            version: Version::Http1_1,
+            method: Method::Head,
+            ..Default::default()
+        };
+        response.write_to_stream(&mut response_stream, &request, true);
+
+        let response_text = String::from_utf8(response_stream).unwrap();
+        assert!(response_text.contains("Content-Length: 13"));
+        assert!(response_text.ends_with("\r\n\r\n"));
+    }
+
     #[test]
     fn test_write_response_with_headers() {
         let mut response = Response::with_status(Status::NotFound)
@@ -0,0 +1,241 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::error::Error as StdError;
+use std::fmt::{self, Debug, Display, Formatter};
+
+#[cfg(feature = "json")]
+use serde::Serialize;
+use small_http::{Response, Status};
+
+use crate::response::IntoResponse;
+
+// MARK: Problem
+/// Problem details object (RFC 9457), responses are JSON when the `json` feature is enabled
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+pub struct Problem {
+    /// Problem type URI
+    #[cfg_attr(feature = "json", serde(rename = "type"))]
+    pub r#type: String,
+    /// Short human-readable summary
+    pub title: String,
+    /// HTTP status code
+    pub status: u16,
+    /// Human-readable explanation of this occurrence
+    #[cfg_attr(feature = "json", serde(skip_serializing_if = "Option::is_none"))]
+    pub detail: Option<String>,
+    /// URI reference of this occurrence
+    #[cfg_attr(feature = "json", serde(skip_serializing_if = "Option::is_none"))]
+    pub instance: Option<String>,
+    /// Extension members
+    #[cfg(feature = "json")]
+    #[serde(flatten)]
+    pub extensions: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Problem {
+    /// Create new problem with status
+    pub fn new(status: Status) -> Self {
+        Self {
+            r#type: "about:blank".to_string(),
+            title: status_reason(status),
+            status: status as u16,
+            detail: None,
+            instance: None,
+            #[cfg(feature = "json")]
+            extensions: serde_json::Map::new(),
+        }
+    }
+
+    /// Set problem type URI
+    pub fn r#type(mut self, r#type: impl Into<String>) -> Self {
+        self.r#type = r#type.into();
+        self
+    }
+
+    /// Set title
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Set detail
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Set instance
+    pub fn instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Set extension member
+    #[cfg(feature = "json")]
+    pub fn extension(mut self, name: impl Into<String>, value: impl Serialize) -> Self {
+        self.extensions.insert(
+            name.into(),
+            serde_json::to_value(value).expect("Can't serialize json"),
+        );
+        self
+    }
+}
+
+impl IntoResponse for Problem {
+    #[cfg(feature = "json")]
+    fn into_response(self) -> Response {
+        Response::with_status(Status::try_from(self.status as i32).unwrap_or_default())
+            .header("Content-Type", "application/problem+json")
+            .body(serde_json::to_vec(&self).expect("Can't serialize json"))
+    }
+
+    #[cfg(not(feature = "json"))]
+    fn into_response(self) -> Response {
+        let body = match &self.detail {
+            Some(detail) => format!("{}: {}", self.title, detail),
+            None => self.title,
+        };
+        Response::with_status(Status::try_from(self.status as i32).unwrap_or_default())
+            .header("Content-Type", "text/plain")
+            .body(body)
+    }
+}
+
+pub(crate) fn status_reason(status: Status) -> String {
+    let status = status.to_string();
+    match status.split_once(' ') {
+        Some((_, reason)) => reason.to_string(),
+        None => status,
+    }
+}
+
+// MARK: Error
+/// Router error that is turned into a response by the error mapper
+pub struct Error {
+    problem: Box<Problem>,
+    source: Option<Box<dyn StdError + Send + Sync>>,
+}
+
+impl Error {
+    /// Create new error with status
+    pub fn new(status: Status) -> Self {
+        Self {
+            problem: Box::new(Problem::new(status)),
+            source: None,
+        }
+    }
+
+    /// Create new 400 Bad Request error with detail
+    pub fn bad_request(detail: impl Into<String>) -> Self {
+        Self::new(Status::BadRequest).detail(detail)
+    }
+
+    /// Create new 401 Unauthorized error
+    pub fn unauthorized() -> Self {
+        Self::new(Status::Unauthorized)
+    }
+
+    /// Create new 403 Forbidden error
+    pub fn forbidden() -> Self {
+        Self::new(Status::Forbidden)
+    }
+
+    /// Create new 404 Not Found error
+    pub fn not_found() -> Self {
+        Self::new(Status::NotFound)
+    }
+
+    /// Create new 409 Conflict error with detail
+    pub fn conflict(detail: impl Into<String>) -> Self {
+        Self::new(Status::Conflict).detail(detail)
+    }
+
+    /// Create new 500 Internal Server Error error
+    pub fn internal() -> Self {
+        Self::new(Status::InternalServerError)
+    }
+
+    /// Set detail
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.problem.detail = Some(detail.into());
+        self
+    }
+
+    /// Set source error
+    pub fn source(mut self, source: impl StdError + Send + Sync + 'static) -> Self {
+        self.source = Some(Box::new(source));
+        self
+    }
+
+    /// Get status
+    pub fn status(&self) -> Status {
+        Status::try_from(self.problem.status as i32).unwrap_or_default()
+    }
+
+    /// Get problem details
+    pub fn problem(&self) -> &Problem {
+        &self.problem
+    }
+
+    /// Get source error
+    pub fn source_error(&self) -> Option<&(dyn StdError + Send + Sync + 'static)> {
+        self.source.as_deref()
+    }
+
+    /// Convert into problem details
+    pub fn into_problem(self) -> Problem {
+        *self.problem
+    }
+}
+
+impl<E: StdError + Send + Sync + 'static> From<E> for Error {
+    fn from(err: E) -> Self {
+        Self::internal().source(err)
+    }
+}
+
+impl From<Problem> for Error {
+    fn from(problem: Problem) -> Self {
+        Self {
+            problem: Box::new(problem),
+            source: None,
+        }
+    }
+}
+
+impl Debug for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Error")
+            .field("problem", &self.problem)
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.problem.status, self.problem.title)?;
+        if let Some(detail) = &self.problem.detail {
+            write!(f, ": {}", detail)?;
+        }
+        if let Some(source) = &self.source {
+            write!(f, " ({})", source)?;
+        }
+        Ok(())
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        self.problem.into_response()
+    }
+
+    fn into_result(self) -> Result<Response, Error> {
+        Err(self)
+    }
+}
@@ -0,0 +1,56 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::sync::Arc;
+
+use small_http::{Request, Response};
+
+use crate::{ErrorMapperFn, Handler};
+
+// MARK: Layer
+/// Layer that wraps the handler of a route
+pub trait Layer<T>: Send + Sync {
+    /// Handle request, call `next.run` to continue to the inner layers and handler
+    fn call(&self, req: &Request, ctx: &mut T, next: Next<'_, T>) -> Response;
+}
+
+// MARK: Next
+/// The remaining layers and handler of a route
+pub struct Next<'a, T> {
+    layers: &'a [Arc<dyn Layer<T>>],
+    handler: &'a Handler<T>,
+    error_mapper: ErrorMapperFn<T>,
+}
+
+impl<'a, T> Next<'a, T> {
+    pub(crate) fn new(
+        layers: &'a [Arc<dyn Layer<T>>],
+        handler: &'a Handler<T>,
+        error_mapper: ErrorMapperFn<T>,
+    ) -> Self {
+        Self {
+            layers,
+            handler,
+            error_mapper,
+        }
+    }
+
+    /// Run the next layer or the handler
+    pub fn run(self, req: &Request, ctx: &mut T) -> Response {
+        match self.layers.split_first() {
+            Some((layer, layers)) => layer.call(
+                req,
+                ctx,
+                Next {
+                    layers,
+                    handler: self.handler,
+                    error_mapper: self.error_mapper,
+                },
+            ),
+            None => self.handler.call_inner(req, ctx, self.error_mapper),
+        }
+    }
+}
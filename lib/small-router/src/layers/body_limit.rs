@@ -0,0 +1,61 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use small_http::{Request, Response, Status};
+
+use crate::{Error, IntoResponse, Layer, Next};
+
+// MARK: BodyLimit
+/// Layer that rejects requests with a body larger than a limit
+#[derive(Clone)]
+pub struct BodyLimit {
+    limit: usize,
+}
+
+impl BodyLimit {
+    /// Create new body limit layer with limit in bytes
+    pub fn new(limit: usize) -> Self {
+        Self { limit }
+    }
+}
+
+impl<T> Layer<T> for BodyLimit {
+    fn call(&self, req: &Request, ctx: &mut T, next: Next<'_, T>) -> Response {
+        let content_length = req
+            .headers
+            .get("Content-Length")
+            .and_then(|value| value.parse::<usize>().ok())
+            .unwrap_or(0);
+        let body_length = req.body.as_ref().map_or(0, |body| body.len());
+        if content_length.max(body_length) > self.limit {
+            return Error::new(Status::PayloadTooLarge)
+                .detail(format!("Request body is larger than {} bytes", self.limit))
+                .into_response();
+        }
+        next.run(req, ctx)
+    }
+}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::RouterBuilder;
+
+    #[test]
+    fn test_body_limit() {
+        let router = RouterBuilder::new()
+            .layer(BodyLimit::new(4))
+            .post("/", |_, _| "Ok")
+            .build();
+
+        let res = router.handle(&Request::post("http://localhost/").body("1234"));
+        assert_eq!(res.status, Status::Ok);
+
+        let res = router.handle(&Request::post("http://localhost/").body("12345"));
+        assert_eq!(res.status, Status::PayloadTooLarge);
+    }
+}
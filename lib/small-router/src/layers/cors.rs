@@ -0,0 +1,239 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use small_http::{Method, Request, Response, Status};
+
+use crate::{Layer, Next};
+
+// MARK: Cors
+/// Layer that handles CORS preflight requests and adds CORS headers to responses
+#[derive(Clone)]
+pub struct Cors {
+    allowed_origins: Option<Vec<String>>,
+    allowed_methods: Vec<Method>,
+    allowed_headers: Vec<String>,
+    exposed_headers: Vec<String>,
+    allow_credentials: bool,
+    max_age: Option<u64>,
+}
+
+impl Default for Cors {
+    fn default() -> Self {
+        Self {
+            allowed_origins: None,
+            allowed_methods: vec![
+                Method::Get,
+                Method::Head,
+                Method::Post,
+                Method::Put,
+                Method::Delete,
+                Method::Patch,
+            ],
+            allowed_headers: Vec::new(),
+            exposed_headers: Vec::new(),
+            allow_credentials: false,
+            max_age: Some(86400),
+        }
+    }
+}
+
+impl Cors {
+    /// Create new CORS layer that allows any origin
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow origin, when no origin is added any origin is allowed
+    pub fn allow_origin(mut self, origin: impl Into<String>) -> Self {
+        self.allowed_origins
+            .get_or_insert_with(Vec::new)
+            .push(origin.into());
+        self
+    }
+
+    /// Set allowed methods
+    pub fn allow_methods(mut self, methods: &[Method]) -> Self {
+        self.allowed_methods = methods.to_vec();
+        self
+    }
+
+    /// Allow request header
+    pub fn allow_header(mut self, header: impl Into<String>) -> Self {
+        self.allowed_headers.push(header.into());
+        self
+    }
+
+    /// Expose response header
+    pub fn expose_header(mut self, header: impl Into<String>) -> Self {
+        self.exposed_headers.push(header.into());
+        self
+    }
+
+    /// Set allow credentials
+    pub fn allow_credentials(mut self, allow_credentials: bool) -> Self {
+        self.allow_credentials = allow_credentials;
+        self
+    }
+
+    /// Set preflight max age in seconds
+    pub fn max_age(mut self, max_age: Option<u64>) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    fn allowed_origin(&self, req: &Request) -> Option<String> {
+        let origin = req.headers.get("Origin");
+        match &self.allowed_origins {
+            None => {
+                // Credentials can't be combined with a wildcard origin
+                if self.allow_credentials {
+                    origin.cloned()
+                } else {
+                    Some("*".to_string())
+                }
+            }
+            Some(allowed_origins) => origin
+                .filter(|origin| allowed_origins.contains(origin))
+                .cloned(),
+        }
+    }
+
+    fn add_headers(&self, req: &Request, mut res: Response, preflight: bool) -> Response {
+        let allowed_origin = match self.allowed_origin(req) {
+            Some(allowed_origin) => allowed_origin,
+            None => return res,
+        };
+        if allowed_origin != "*" {
+            res = res.header("Vary", "Origin");
+        }
+        res = res.header("Access-Control-Allow-Origin", allowed_origin);
+        if self.allow_credentials {
+            res = res.header("Access-Control-Allow-Credentials", "true");
+        }
+        if preflight {
+            res = res.header(
+                "Access-Control-Allow-Methods",
+                self.allowed_methods
+                    .iter()
+                    .map(|method| method.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+            if !self.allowed_headers.is_empty() {
+                res = res.header(
+                    "Access-Control-Allow-Headers",
+                    self.allowed_headers.join(", "),
+                );
+            }
+            if let Some(max_age) = self.max_age {
+                res = res.header("Access-Control-Max-Age", max_age.to_string());
+            }
+        } else if !self.exposed_headers.is_empty() {
+            res = res.header(
+                "Access-Control-Expose-Headers",
+                self.exposed_headers.join(", "),
+            );
+        }
+        res
+    }
+}
+
+impl<T> Layer<T> for Cors {
+    fn call(&self, req: &Request, ctx: &mut T, next: Next<'_, T>) -> Response {
+        // Only answer real preflight requests, other OPTIONS requests go to the router
+        if req.method == Method::Options
+            && req.headers.get("Origin").is_some()
+            && req.headers.get("Access-Control-Request-Method").is_some()
+        {
+            return self.add_headers(req, Response::with_status(Status::NoContent), true);
+        }
+        let res = next.run(req, ctx);
+        self.add_headers(req, res, false)
+    }
+}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::RouterBuilder;
+
+    #[test]
+    fn test_cors() {
+        let router = RouterBuilder::new()
+            .layer(
+                Cors::new()
+                    .allow_origin("https://example.com")
+                    .allow_methods(&[Method::Get])
+                    .allow_header("Content-Type"),
+            )
+            .get("/", |_, _| "Home")
+            .build();
+
+        // Preflight from allowed origin
+        let res = router.handle(
+            &Request::options("http://localhost/")
+                .header("Origin", "https://example.com")
+                .header("Access-Control-Request-Method", "GET"),
+        );
+        assert_eq!(res.status, Status::NoContent);
+        assert_eq!(
+            res.headers
+                .get("Access-Control-Allow-Origin")
+                .map(|s| s.as_str()),
+            Some("https://example.com")
+        );
+        assert_eq!(
+            res.headers
+                .get("Access-Control-Allow-Methods")
+                .map(|s| s.as_str()),
+            Some("GET")
+        );
+        assert_eq!(
+            res.headers
+                .get("Access-Control-Allow-Headers")
+                .map(|s| s.as_str()),
+            Some("Content-Type")
+        );
+
+        // Request from other origin
+        let res =
+            router.handle(&Request::get("http://localhost/").header("Origin", "https://evil.com"));
+        assert_eq!(res.status, Status::Ok);
+        assert!(res.headers.get("Access-Control-Allow-Origin").is_none());
+    }
+
+    #[test]
+    fn test_cors_non_preflight_options() {
+        let router = RouterBuilder::new()
+            .layer(Cors::new())
+            .get("/", |_, _| "Home")
+            .options("/custom", |_, _| "Custom options")
+            .build();
+
+        // OPTIONS without preflight headers gets the automatic OPTIONS response
+        let res = router.handle(&Request::options("http://localhost/"));
+        assert_eq!(res.status, Status::NoContent);
+        assert_eq!(
+            res.headers.get("Allow").map(|s| s.as_str()),
+            Some("GET, HEAD, OPTIONS")
+        );
+        assert!(res.headers.get("Access-Control-Allow-Methods").is_none());
+
+        // OPTIONS with only an origin reaches the explicit OPTIONS handler
+        let res = router.handle(
+            &Request::options("http://localhost/custom").header("Origin", "https://example.com"),
+        );
+        assert_eq!(res.status, Status::Ok);
+        assert_eq!(res.body, b"Custom options");
+        assert_eq!(
+            res.headers
+                .get("Access-Control-Allow-Origin")
+                .map(|s| s.as_str()),
+            Some("*")
+        );
+    }
+}
@@ -0,0 +1,19 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Standard layers that can be added to a router with [`RouterBuilder::layer`](crate::RouterBuilder::layer)
+
+pub use self::auth::{BasicAuth, BearerAuth};
+pub use self::body_limit::BodyLimit;
+pub use self::cors::Cors;
+pub use self::rate_limit::RateLimit;
+pub use self::timing::Timing;
+
+mod auth;
+mod body_limit;
+mod cors;
+mod rate_limit;
+mod timing;
@@ -0,0 +1,149 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use small_http::{Request, Response, Status};
+
+use crate::{Error, IntoResponse, Layer, Next};
+
+/// Amount of tracked clients after which full buckets are pruned
+const PRUNE_THRESHOLD: usize = 1024;
+
+// MARK: RateLimit
+/// Layer that rate limits requests per client IP address with a token bucket
+pub struct RateLimit {
+    capacity: f64,
+    refill_per_second: f64,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimit {
+    /// Create new rate limit layer that allows `requests` requests per `period` with bursts up to `requests`
+    ///
+    /// Panics when `requests` or `period` is zero.
+    pub fn new(requests: u32, period: Duration) -> Self {
+        assert!(
+            requests > 0,
+            "Rate limit requests must be greater than zero"
+        );
+        assert!(
+            !period.is_zero(),
+            "Rate limit period must be greater than zero"
+        );
+        Self {
+            capacity: requests as f64,
+            refill_per_second: requests as f64 / period.as_secs_f64(),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Set burst capacity
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.capacity = burst as f64;
+        self
+    }
+
+    /// Try to take a token for client, returns the wait time when there is none
+    fn take(&self, ip: IpAddr) -> Result<u32, Duration> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().expect("Can't lock");
+        if buckets.len() > PRUNE_THRESHOLD {
+            buckets.retain(|_, bucket| {
+                bucket.tokens
+                    + now.duration_since(bucket.updated_at).as_secs_f64() * self.refill_per_second
+                    < self.capacity
+            });
+        }
+
+        let bucket = buckets.entry(ip).or_insert(Bucket {
+            tokens: self.capacity,
+            updated_at: now,
+        });
+        bucket.tokens = (bucket.tokens
+            + now.duration_since(bucket.updated_at).as_secs_f64() * self.refill_per_second)
+            .min(self.capacity);
+        bucket.updated_at = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(bucket.tokens as u32)
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / self.refill_per_second,
+            ))
+        }
+    }
+}
+
+impl<T> Layer<T> for RateLimit {
+    fn call(&self, req: &Request, ctx: &mut T, next: Next<'_, T>) -> Response {
+        match self.take(req.client_addr.ip()) {
+            Ok(remaining) => next
+                .run(req, ctx)
+                .header("RateLimit-Limit", (self.capacity as u32).to_string())
+                .header("RateLimit-Remaining", remaining.to_string()),
+            Err(wait) => Error::new(Status::TooManyRequests)
+                .into_response()
+                .header(
+                    "Retry-After",
+                    (wait.as_secs_f64().ceil() as u64).to_string(),
+                )
+                .header("RateLimit-Limit", (self.capacity as u32).to_string())
+                .header("RateLimit-Remaining", "0"),
+        }
+    }
+}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::RouterBuilder;
+
+    #[test]
+    fn test_rate_limit() {
+        let router = RouterBuilder::new()
+            .layer(RateLimit::new(2, Duration::from_secs(60)))
+            .get("/", |_, _| "Home")
+            .build();
+
+        for remaining in ["1", "0"] {
+            let res = router.handle(&Request::get("http://localhost/"));
+            assert_eq!(res.status, Status::Ok);
+            assert_eq!(
+                res.headers.get("RateLimit-Remaining").map(|s| s.as_str()),
+                Some(remaining)
+            );
+        }
+
+        let res = router.handle(&Request::get("http://localhost/"));
+        assert_eq!(res.status, Status::TooManyRequests);
+        assert_eq!(
+            res.headers.get("Retry-After").map(|s| s.as_str()),
+            Some("30")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Rate limit requests must be greater than zero")]
+    fn test_rate_limit_zero_requests() {
+        RateLimit::new(0, Duration::from_secs(60));
+    }
+
+    #[test]
+    #[should_panic(expected = "Rate limit period must be greater than zero")]
+    fn test_rate_limit_zero_period() {
+        RateLimit::new(1, Duration::ZERO);
+    }
+}
@@ -0,0 +1,33 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::time::Instant;
+
+use small_http::{Request, Response};
+
+use crate::{Layer, Next};
+
+// MARK: Timing
+/// Layer that adds the request handling duration as `Server-Timing` and `X-Response-Time` headers
+#[derive(Clone, Default)]
+pub struct Timing;
+
+impl Timing {
+    /// Create new timing layer
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<T> Layer<T> for Timing {
+    fn call(&self, req: &Request, ctx: &mut T, next: Next<'_, T>) -> Response {
+        let start = Instant::now();
+        let res = next.run(req, ctx);
+        let duration = start.elapsed().as_secs_f64() * 1000.0;
+        res.header("Server-Timing", format!("app;dur={:.3}", duration))
+            .header("X-Response-Time", format!("{:.3}ms", duration))
+    }
+}
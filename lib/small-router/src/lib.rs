@@ -8,37 +8,75 @@
 #![forbid(unsafe_code)]
 
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::fmt::{self, Display, Formatter};
+use std::sync::{Arc, RwLock};
 
 use small_http::{Method, Request, Response, Status};
 
+pub use crate::error::{Error, Problem};
+pub use crate::layer::{Layer, Next};
+#[cfg(feature = "json")]
+pub use crate::openapi::RouteDoc;
+pub use crate::response::IntoResponse;
+#[cfg(feature = "json")]
+pub use crate::response::Json;
+
+mod error;
+mod layer;
+pub mod layers;
+#[cfg(feature = "json")]
+mod openapi;
+mod response;
+
+const ALL_METHODS: [Method; 9] = [
+    Method::Get,
+    Method::Head,
+    Method::Post,
+    Method::Put,
+    Method::Delete,
+    Method::Connect,
+    Method::Options,
+    Method::Trace,
+    Method::Patch,
+];
+
 // MARK: Handler
 
 /// Parsed path parameters
-type HandlerFn<T> = fn(&Request, &T) -> Response;
+type HandlerFn<T> = Box<dyn Fn(&Request, &T) -> Result<Response, Error> + Send + Sync>;
 type PreLayerFn<T> = fn(&Request, &mut T) -> Option<Response>;
 type PostLayerFn<T> = fn(&Request, &mut T, Response) -> Response;
+type ErrorMapperFn<T> = fn(&Request, &T, Error) -> Response;
 
 struct Handler<T> {
     handler: HandlerFn<T>,
+    layers: Vec<Arc<dyn Layer<T>>>,
     pre_layers: Vec<PreLayerFn<T>>,
     post_layers: Vec<PostLayerFn<T>>,
 }
 
-impl<T> Handler<T> {
-    fn new(
-        handler: HandlerFn<T>,
+impl<T: 'static> Handler<T> {
+    fn new<R: IntoResponse + 'static>(
+        handler: fn(&Request, &T) -> R,
+        layers: Vec<Arc<dyn Layer<T>>>,
         pre_layers: Vec<PreLayerFn<T>>,
         post_layers: Vec<PostLayerFn<T>>,
     ) -> Self {
         Self {
-            handler,
+            handler: Box::new(move |req, ctx| handler(req, ctx).into_result()),
+            layers,
             pre_layers,
             post_layers,
         }
     }
+}
 
-    fn call(&self, req: &Request, ctx: &mut T) -> Response {
+impl<T> Handler<T> {
+    fn call(&self, req: &Request, ctx: &mut T, error_mapper: ErrorMapperFn<T>) -> Response {
+        Next::new(&self.layers, self, error_mapper).run(req, ctx)
+    }
+
+    fn call_inner(&self, req: &Request, ctx: &mut T, error_mapper: ErrorMapperFn<T>) -> Response {
         for pre_layer in &self.pre_layers {
             if let Some(mut res) = pre_layer(req, ctx) {
                 for post_layer in &self.post_layers {
@@ -47,7 +85,10 @@ impl<T> Handler<T> {
                 return res;
             }
         }
-        let mut res = (self.handler)(req, ctx);
+        let mut res = match (self.handler)(req, ctx) {
+            Ok(res) => res,
+            Err(err) => error_mapper(req, ctx, err),
+        };
         for post_layer in &self.post_layers {
             res = post_layer(req, ctx, res);
         }
@@ -55,27 +96,38 @@ impl<T> Handler<T> {
     }
 }
 
+fn default_error_mapper<T>(req: &Request, _: &T, err: Error) -> Response {
+    err.into_problem().instance(req.url.path()).into_response()
+}
+
 // MARK: Route
+#[derive(Clone)]
 enum RoutePart {
     Static(String),
     Param(String),
 }
 
 struct Route<T> {
+    name: Option<String>,
     methods: Vec<Method>,
     route: String,
     parts: Vec<RoutePart>,
     handler: Handler<T>,
+    #[cfg(feature = "json")]
+    doc: Option<RouteDoc>,
 }
 
 impl<T> Route<T> {
-    fn new(methods: Vec<Method>, route: String, handler: Handler<T>) -> Self {
+    fn new(name: Option<String>, methods: Vec<Method>, route: String, handler: Handler<T>) -> Self {
         let parts = Self::route_parse_parts(&route);
         Self {
+            name,
             methods,
             route,
             parts,
             handler,
+            #[cfg(feature = "json")]
+            doc: None,
         }
     }
 
@@ -126,7 +178,7 @@ impl<T> Route<T> {
                 }
                 RoutePart::Param(name) => {
                     if let Some(value) = path_parts.next() {
-                        params.insert(name.clone(), value.to_string());
+                        params.insert(name.clone(), percent_decode(value));
                     }
                 }
             }
@@ -135,15 +187,138 @@ impl<T> Route<T> {
     }
 }
 
+fn route_url(parts: &[RoutePart], params: &[(&str, &str)]) -> Result<String, UrlForError> {
+    for (name, _) in params {
+        if !parts
+            .iter()
+            .any(|part| matches!(part, RoutePart::Param(param) if param == name))
+        {
+            return Err(UrlForError::ExtraParam(name.to_string()));
+        }
+    }
+
+    let mut url = String::new();
+    for part in parts {
+        url.push('/');
+        match part {
+            RoutePart::Static(value) => url.push_str(value),
+            RoutePart::Param(name) => {
+                let (_, value) = params
+                    .iter()
+                    .find(|(param, _)| param == name)
+                    .ok_or_else(|| UrlForError::MissingParam(name.clone()))?;
+                url.push_str(&percent_encode(value));
+            }
+        }
+    }
+    if url.is_empty() {
+        url.push('/');
+    }
+    Ok(url)
+}
+
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let (Some(high), Some(low)) = (
+                (bytes[i + 1] as char).to_digit(16),
+                (bytes[i + 2] as char).to_digit(16),
+            ) {
+                decoded.push((high * 16 + low) as u8);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// MARK: Urls
+/// Named route url generator that can be shared with handlers through the context
+///
+/// ```rs
+/// let urls = Urls::new();
+/// let router = RouterBuilder::with(Context { urls: urls.clone() })
+///     .urls(urls)
+///     .get_named("persons.show", "/persons/:id", persons_show)
+///     .build();
+/// ```
+#[derive(Clone, Default)]
+pub struct Urls(Arc<RwLock<HashMap<String, Vec<RoutePart>>>>);
+
+impl Urls {
+    /// Create new empty url generator that is filled when the router is built
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generate url path for named route with params
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, UrlForError> {
+        let routes = self.0.read().expect("Can't lock");
+        let parts = routes
+            .get(name)
+            .ok_or_else(|| UrlForError::UnknownRoute(name.to_string()))?;
+        route_url(parts, params)
+    }
+}
+
+// MARK: UrlForError
+/// Url for error
+#[derive(Debug, PartialEq, Eq)]
+pub enum UrlForError {
+    /// No route with this name
+    UnknownRoute(String),
+    /// Route param is missing
+    MissingParam(String),
+    /// Param is not part of the route
+    ExtraParam(String),
+}
+
+impl Display for UrlForError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            UrlForError::UnknownRoute(name) => write!(f, "Unknown route: {}", name),
+            UrlForError::MissingParam(name) => write!(f, "Missing route param: {}", name),
+            UrlForError::ExtraParam(name) => write!(f, "Extra route param: {}", name),
+        }
+    }
+}
+
+impl std::error::Error for UrlForError {}
+
 // MARK: RouterBuilder
 /// Router builder
 pub struct RouterBuilder<T: Clone> {
     ctx: T,
+    #[cfg(feature = "json")]
+    openapi_info: (String, String),
+    #[cfg(feature = "json")]
+    openapi_schemas: serde_json::Map<String, serde_json::Value>,
+    layers: Vec<Arc<dyn Layer<T>>>,
     pre_layers: Vec<PreLayerFn<T>>,
     post_layers: Vec<PostLayerFn<T>>,
     routes: Vec<Route<T>>,
     not_allowed_method_handler: Option<Handler<T>>,
     fallback_handler: Option<Handler<T>>,
+    error_mapper: ErrorMapperFn<T>,
+    urls: Urls,
 }
 
 impl Default for RouterBuilder<()> {
@@ -159,19 +334,32 @@ impl RouterBuilder<()> {
     }
 }
 
-impl<T: Clone> RouterBuilder<T> {
+impl<T: Clone + 'static> RouterBuilder<T> {
     /// Create new router with context
     pub fn with(ctx: T) -> Self {
         Self {
             ctx,
+            #[cfg(feature = "json")]
+            openapi_info: ("API".to_string(), "0.1.0".to_string()),
+            #[cfg(feature = "json")]
+            openapi_schemas: serde_json::Map::new(),
+            layers: Vec::new(),
             pre_layers: Vec::new(),
             post_layers: Vec::new(),
             routes: Vec::new(),
             not_allowed_method_handler: None,
             fallback_handler: None,
+            error_mapper: default_error_mapper,
+            urls: Urls::new(),
         }
     }
 
+    /// Add layer that wraps the handlers of the routes added after it
+    pub fn layer(mut self, layer: impl Layer<T> + 'static) -> Self {
+        self.layers.push(Arc::new(layer));
+        self
+    }
+
     /// Add pre layer
     pub fn pre_layer(mut self, layer: PreLayerFn<T>) -> Self {
         self.pre_layers.push(layer);
@@ -184,111 +372,270 @@ impl<T: Clone> RouterBuilder<T> {
         self
     }
 
+    /// Set url generator that is filled with the named routes when the router is built
+    pub fn urls(mut self, urls: Urls) -> Self {
+        self.urls = urls;
+        self
+    }
+
+    /// Set error mapper that turns handler errors into responses
+    pub fn error_mapper(mut self, error_mapper: ErrorMapperFn<T>) -> Self {
+        self.error_mapper = error_mapper;
+        self
+    }
+
     /// Add route
-    pub fn route(mut self, methods: &[Method], route: String, handler: HandlerFn<T>) -> Self {
+    pub fn route<R: IntoResponse + 'static>(
+        mut self,
+        methods: &[Method],
+        route: String,
+        handler: fn(&Request, &T) -> R,
+    ) -> Self {
+        self.routes.push(Route::new(
+            None,
+            methods.to_vec(),
+            route,
+            self.new_handler(handler),
+        ));
+        self
+    }
+
+    /// Set documentation of the last added route
+    #[cfg(feature = "json")]
+    pub fn doc(mut self, doc: RouteDoc) -> Self {
+        self.routes
+            .last_mut()
+            .expect("No route added before doc")
+            .doc = Some(doc);
+        self
+    }
+
+    /// Set title and version used in the generated OpenAPI document
+    #[cfg(feature = "json")]
+    pub fn openapi_info(mut self, title: impl Into<String>, version: impl Into<String>) -> Self {
+        self.openapi_info = (title.into(), version.into());
+        self
+    }
+
+    /// Add JSON schema to the OpenAPI document components that route docs can refer to by name
+    #[cfg(feature = "json")]
+    pub fn openapi_schema(mut self, name: impl Into<String>, schema: serde_json::Value) -> Self {
+        self.openapi_schemas.insert(name.into(), schema);
+        self
+    }
+
+    /// Add named route that can be used for url generation with [`Router::url_for`] and [`Urls::url_for`]
+    pub fn route_named<R: IntoResponse + 'static>(
+        mut self,
+        name: impl Into<String>,
+        methods: &[Method],
+        route: String,
+        handler: fn(&Request, &T) -> R,
+    ) -> Self {
+        let name = name.into();
+        assert!(
+            !self
+                .routes
+                .iter()
+                .any(|route| route.name.as_ref() == Some(&name)),
+            "Route name {} is already used",
+            name
+        );
         self.routes.push(Route::new(
+            Some(name),
             methods.to_vec(),
             route,
-            Handler::new(handler, self.pre_layers.clone(), self.post_layers.clone()),
+            self.new_handler(handler),
         ));
         self
     }
 
     /// Add route for any method
-    pub fn any(self, route: impl Into<String>, handler: HandlerFn<T>) -> Self {
-        self.route(
-            &[
-                Method::Get,
-                Method::Head,
-                Method::Post,
-                Method::Put,
-                Method::Delete,
-                Method::Connect,
-                Method::Options,
-                Method::Trace,
-                Method::Patch,
-            ],
-            route.into(),
-            handler,
-        )
+    pub fn any<R: IntoResponse + 'static>(
+        self,
+        route: impl Into<String>,
+        handler: fn(&Request, &T) -> R,
+    ) -> Self {
+        self.route(&ALL_METHODS, route.into(), handler)
     }
     /// Add route for GET method
-    pub fn get(self, route: impl Into<String>, handler: HandlerFn<T>) -> Self {
+    pub fn get<R: IntoResponse + 'static>(
+        self,
+        route: impl Into<String>,
+        handler: fn(&Request, &T) -> R,
+    ) -> Self {
         self.route(&[Method::Get], route.into(), handler)
     }
 
+    /// Add named route for GET method
+    pub fn get_named<R: IntoResponse + 'static>(
+        self,
+        name: impl Into<String>,
+        route: impl Into<String>,
+        handler: fn(&Request, &T) -> R,
+    ) -> Self {
+        self.route_named(name, &[Method::Get], route.into(), handler)
+    }
+
     /// Add route for HEAD method
-    pub fn head(self, route: impl Into<String>, handler: HandlerFn<T>) -> Self {
+    pub fn head<R: IntoResponse + 'static>(
+        self,
+        route: impl Into<String>,
+        handler: fn(&Request, &T) -> R,
+    ) -> Self {
         self.route(&[Method::Head], route.into(), handler)
     }
 
     /// Add route for POST method
-    pub fn post(self, route: impl Into<String>, handler: HandlerFn<T>) -> Self {
+    pub fn post<R: IntoResponse + 'static>(
+        self,
+        route: impl Into<String>,
+        handler: fn(&Request, &T) -> R,
+    ) -> Self {
         self.route(&[Method::Post], route.into(), handler)
     }
 
+    /// Add named route for POST method
+    pub fn post_named<R: IntoResponse + 'static>(
+        self,
+        name: impl Into<String>,
+        route: impl Into<String>,
+        handler: fn(&Request, &T) -> R,
+    ) -> Self {
+        self.route_named(name, &[Method::Post], route.into(), handler)
+    }
+
     /// Add route for PUT method
-    pub fn put(self, route: impl Into<String>, handler: HandlerFn<T>) -> Self {
+    pub fn put<R: IntoResponse + 'static>(
+        self,
+        route: impl Into<String>,
+        handler: fn(&Request, &T) -> R,
+    ) -> Self {
         self.route(&[Method::Put], route.into(), handler)
     }
 
+    /// Add named route for PUT method
+    pub fn put_named<R: IntoResponse + 'static>(
+        self,
+        name: impl Into<String>,
+        route: impl Into<String>,
+        handler: fn(&Request, &T) -> R,
+    ) -> Self {
+        self.route_named(name, &[Method::Put], route.into(), handler)
+    }
+
     /// Add route for DELETE method
-    pub fn delete(self, route: impl Into<String>, handler: HandlerFn<T>) -> Self {
+    pub fn delete<R: IntoResponse + 'static>(
+        self,
+        route: impl Into<String>,
+        handler: fn(&Request, &T) -> R,
+    ) -> Self {
         self.route(&[Method::Delete], route.into(), handler)
     }
 
+    /// Add named route for DELETE method
+    pub fn delete_named<R: IntoResponse + 'static>(
+        self,
+        name: impl Into<String>,
+        route: impl Into<String>,
+        handler: fn(&Request, &T) -> R,
+    ) -> Self {
+        self.route_named(name, &[Method::Delete], route.into(), handler)
+    }
+
     /// Add route for CONNECT method
-    pub fn connect(self, route: impl Into<String>, handler: HandlerFn<T>) -> Self {
+    pub fn connect<R: IntoResponse + 'static>(
+        self,
+        route: impl Into<String>,
+        handler: fn(&Request, &T) -> R,
+    ) -> Self {
         self.route(&[Method::Connect], route.into(), handler)
     }
 
     /// Add route for OPTIONS method
-    pub fn options(self, route: impl Into<String>, handler: HandlerFn<T>) -> Self {
+    pub fn options<R: IntoResponse + 'static>(
+        self,
+        route: impl Into<String>,
+        handler: fn(&Request, &T) -> R,
+    ) -> Self {
         self.route(&[Method::Options], route.into(), handler)
     }
 
     /// Add route for TRACE method
-    pub fn trace(self, route: impl Into<String>, handler: HandlerFn<T>) -> Self {
+    pub fn trace<R: IntoResponse + 'static>(
+        self,
+        route: impl Into<String>,
+        handler: fn(&Request, &T) -> R,
+    ) -> Self {
         self.route(&[Method::Trace], route.into(), handler)
     }
 
     /// Add route for PATCH method
-    pub fn patch(self, route: impl Into<String>, handler: HandlerFn<T>) -> Self {
+    pub fn patch<R: IntoResponse + 'static>(
+        self,
+        route: impl Into<String>,
+        handler: fn(&Request, &T) -> R,
+    ) -> Self {
         self.route(&[Method::Patch], route.into(), handler)
     }
 
+    /// Add named route for PATCH method
+    pub fn patch_named<R: IntoResponse + 'static>(
+        self,
+        name: impl Into<String>,
+        route: impl Into<String>,
+        handler: fn(&Request, &T) -> R,
+    ) -> Self {
+        self.route_named(name, &[Method::Patch], route.into(), handler)
+    }
+
     /// Set fallback handler
-    pub fn fallback(mut self, handler: HandlerFn<T>) -> Self {
-        self.fallback_handler = Some(Handler::new(
+    pub fn fallback<R: IntoResponse + 'static>(mut self, handler: fn(&Request, &T) -> R) -> Self {
+        self.fallback_handler = Some(self.new_handler(handler));
+        self
+    }
+
+    fn new_handler<R: IntoResponse + 'static>(&self, handler: fn(&Request, &T) -> R) -> Handler<T> {
+        Handler::new(
             handler,
+            self.layers.clone(),
             self.pre_layers.clone(),
             self.post_layers.clone(),
-        ));
-        self
+        )
     }
 
     /// Build router
-    pub fn build(self) -> Router<T> {
+    pub fn build(mut self) -> Router<T> {
+        let options_handler = self.new_handler(|_, _| Status::NoContent);
+        let not_allowed_method_handler =
+            self.not_allowed_method_handler.take().unwrap_or_else(|| {
+                self.new_handler(|_, _| {
+                    Response::with_status(Status::MethodNotAllowed).body("405 Method Not Allowed")
+                })
+            });
+        let fallback_handler = self.fallback_handler.take().unwrap_or_else(|| {
+            self.new_handler(|_, _| Response::with_status(Status::NotFound).body("404 Not Found"))
+        });
+        {
+            let mut urls = self.urls.0.write().expect("Can't lock");
+            for route in &self.routes {
+                if let Some(name) = &route.name {
+                    urls.insert(name.clone(), route.parts.clone());
+                }
+            }
+        }
         Router(Arc::new(InnerRouter {
             ctx: self.ctx,
+            urls: self.urls,
+            #[cfg(feature = "json")]
+            openapi_info: self.openapi_info,
+            #[cfg(feature = "json")]
+            openapi_schemas: self.openapi_schemas,
             routes: self.routes,
-            not_allowed_method_handler: self.not_allowed_method_handler.unwrap_or_else(|| {
-                Handler::new(
-                    |_, _| {
-                        Response::with_status(Status::MethodNotAllowed)
-                            .body("405 Method Not Allowed")
-                    },
-                    self.pre_layers.clone(),
-                    self.post_layers.clone(),
-                )
-            }),
-            fallback_handler: self.fallback_handler.unwrap_or_else(|| {
-                Handler::new(
-                    |_, _| Response::with_status(Status::NotFound).body("404 Not Found"),
-                    self.pre_layers.clone(),
-                    self.post_layers.clone(),
-                )
-            }),
+            error_mapper: self.error_mapper,
+            options_handler,
+            not_allowed_method_handler,
+            fallback_handler,
         }))
     }
 }
@@ -296,12 +643,19 @@ impl<T: Clone> RouterBuilder<T> {
 // MARK: InnerRouter
 struct InnerRouter<T: Clone> {
     ctx: T,
+    urls: Urls,
+    #[cfg(feature = "json")]
+    openapi_info: (String, String),
+    #[cfg(feature = "json")]
+    openapi_schemas: serde_json::Map<String, serde_json::Value>,
     routes: Vec<Route<T>>,
+    error_mapper: ErrorMapperFn<T>,
+    options_handler: Handler<T>,
     not_allowed_method_handler: Handler<T>,
     fallback_handler: Handler<T>,
 }
 
-impl<T: Clone> InnerRouter<T> {
+impl<T: Clone + 'static> InnerRouter<T> {
     fn handle(&self, req: &Request) -> Response {
         let mut ctx = self.ctx.clone();
 
@@ -313,19 +667,60 @@ impl<T: Clone> InnerRouter<T> {
                 req.params = route.match_path(path);
 
                 // Find matching route by method
-                for route in self.routes.iter().filter(|r| r.route == route.route) {
+                let routes = self
+                    .routes
+                    .iter()
+                    .filter(|r| r.route == route.route)
+                    .collect::<Vec<_>>();
+                for route in &routes {
                     if route.methods.contains(&req.method) {
-                        return route.handler.call(&req, &mut ctx);
+                        return route.handler.call(&req, &mut ctx, self.error_mapper);
                     }
                 }
 
+                // Or run GET handler without body for HEAD requests
+                if req.method == Method::Head {
+                    if let Some(route) = routes.iter().find(|r| r.methods.contains(&Method::Get)) {
+                        let mut res = route.handler.call(&req, &mut ctx, self.error_mapper);
+                        let content_length = res.body.len().to_string();
+                        res.body.clear();
+                        return res.header("Content-Length", content_length);
+                    }
+                }
+
+                // Or answer OPTIONS requests with the allowed methods
+                let allow = Self::allowed_methods(&routes);
+                if req.method == Method::Options {
+                    return self
+                        .options_handler
+                        .call(&req, &mut ctx, self.error_mapper)
+                        .header("Allow", allow);
+                }
+
                 // Or run not allowed method handler
-                return self.not_allowed_method_handler.call(&req, &mut ctx);
+                return self
+                    .not_allowed_method_handler
+                    .call(&req, &mut ctx, self.error_mapper)
+                    .header("Allow", allow);
             }
         }
 
         // Or run fallback handler
-        self.fallback_handler.call(req, &mut ctx)
+        self.fallback_handler.call(req, &mut ctx, self.error_mapper)
+    }
+
+    fn allowed_methods(routes: &[&Route<T>]) -> String {
+        ALL_METHODS
+            .iter()
+            .filter(|method| {
+                routes.iter().any(|route| {
+                    route.methods.contains(method)
+                        || (**method == Method::Head && route.methods.contains(&Method::Get))
+                }) || **method == Method::Options
+            })
+            .map(|method| method.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
     }
 }
 
@@ -334,11 +729,23 @@ impl<T: Clone> InnerRouter<T> {
 #[derive(Clone)]
 pub struct Router<T: Clone>(Arc<InnerRouter<T>>);
 
-impl<T: Clone> Router<T> {
+impl<T: Clone + 'static> Router<T> {
     /// Handle request
     pub fn handle(&self, req: &Request) -> Response {
         self.0.handle(req)
     }
+
+    /// Generate OpenAPI 3.1 document of the registered routes
+    #[cfg(feature = "json")]
+    pub fn openapi(&self) -> serde_json::Value {
+        let (title, version) = &self.0.openapi_info;
+        openapi::openapi_document(title, version, &self.0.openapi_schemas, &self.0.routes)
+    }
+
+    /// Generate url path for named route with params
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, UrlForError> {
+        self.0.urls.url_for(name, params)
+    }
 }
 
 // MARK: Tests
@@ -387,8 +794,311 @@ mod test {
         assert_eq!(res.status, Status::Ok);
 
         // Test wrong method
-        let res = router.handle(&Request::options("http://localhost/"));
+        let res = router.handle(&Request::post("http://localhost/"));
         assert_eq!(res.status, Status::MethodNotAllowed);
         assert_eq!(res.body, b"405 Method Not Allowed");
+        assert_eq!(
+            res.headers.get("Allow").map(|s| s.as_str()),
+            Some("GET, HEAD, OPTIONS")
+        );
+    }
+
+    #[test]
+    fn test_url_for() {
+        let router = RouterBuilder::new()
+            .get_named("home", "/", home)
+            .get_named("hello", "/hello/:name", hello)
+            .build();
+
+        assert_eq!(router.url_for("home", &[]), Ok("/".to_string()));
+        assert_eq!(
+            router.url_for("hello", &[("name", "Bassie")]),
+            Ok("/hello/Bassie".to_string())
+        );
+        assert_eq!(
+            router.url_for("hello", &[("name", "Bassie & Adriaan/2")]),
+            Ok("/hello/Bassie%20%26%20Adriaan%2F2".to_string())
+        );
+        assert_eq!(
+            router.url_for("hello", &[]),
+            Err(UrlForError::MissingParam("name".to_string()))
+        );
+        assert_eq!(
+            router.url_for("hello", &[("name", "Bassie"), ("age", "20")]),
+            Err(UrlForError::ExtraParam("age".to_string()))
+        );
+        assert_eq!(
+            router.url_for("unknown", &[]),
+            Err(UrlForError::UnknownRoute("unknown".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_params_are_percent_decoded() {
+        let router = RouterBuilder::new()
+            .get_named("hello", "/hello/:name", hello)
+            .build();
+        let url = router
+            .url_for("hello", &[("name", "Bassie & Adriaan/2")])
+            .unwrap();
+        let res = router.handle(&Request::get(format!("http://localhost{}", url)));
+        assert_eq!(res.body, b"Hello, Bassie & Adriaan/2!");
+
+        // Invalid escapes are kept as is
+        let res = router.handle(&Request::get("http://localhost/hello/100%25%zz"));
+        assert_eq!(res.body, b"Hello, 100%%zz!");
+    }
+
+    #[test]
+    fn test_urls_in_handler() {
+        #[derive(Clone)]
+        struct Context {
+            urls: Urls,
+        }
+        fn create(_req: &Request, ctx: &Context) -> Response {
+            let url = ctx.urls.url_for("show", &[("id", "1")]).unwrap();
+            Response::with_status(Status::Created).header("Location", url)
+        }
+        fn show(_req: &Request, _ctx: &Context) -> Response {
+            Response::with_status(Status::Ok)
+        }
+
+        let urls = Urls::new();
+        let router = RouterBuilder::with(Context { urls: urls.clone() })
+            .urls(urls)
+            .post_named("create", "/items", create)
+            .get_named("show", "/items/:id", show)
+            .build();
+        let res = router.handle(&Request::post("http://localhost/items"));
+        assert_eq!(res.status, Status::Created);
+        assert_eq!(
+            res.headers.get("Location").map(|s| s.as_str()),
+            Some("/items/1")
+        );
+        assert_eq!(router.url_for("create", &[]), Ok("/items".to_string()));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_openapi() {
+        let router = RouterBuilder::new()
+            .openapi_info("Test API", "1.0.0")
+            .get("/", home)
+            .get("/hello/:name", hello)
+            .doc(
+                RouteDoc::new()
+                    .summary("Say hello")
+                    .tag("Hello")
+                    .query_param("lang", "string")
+                    .response(Status::Ok, "Greeting")
+                    .empty_response(Status::NotFound),
+            )
+            .build();
+
+        let doc = router.openapi();
+        assert_eq!(doc["openapi"], "3.1.0");
+        assert_eq!(doc["info"]["title"], "Test API");
+        assert_eq!(doc["info"]["version"], "1.0.0");
+        assert_eq!(
+            doc["paths"]["/"]["get"]["responses"]["200"]["description"],
+            "OK"
+        );
+
+        let operation = &doc["paths"]["/hello/{name}"]["get"];
+        assert_eq!(operation["summary"], "Say hello");
+        assert_eq!(operation["tags"][0], "Hello");
+        assert_eq!(operation["parameters"][0]["name"], "name");
+        assert_eq!(operation["parameters"][0]["in"], "path");
+        assert_eq!(operation["parameters"][1]["name"], "lang");
+        assert_eq!(operation["parameters"][1]["in"], "query");
+        assert_eq!(
+            operation["responses"]["200"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/Greeting"
+        );
+        assert_eq!(operation["responses"]["404"]["description"], "Not Found");
+    }
+
+    #[cfg(feature = "json")]
+    fn collect_refs(value: &serde_json::Value, refs: &mut Vec<String>) {
+        match value {
+            serde_json::Value::Object(object) => {
+                for (key, value) in object {
+                    match value {
+                        serde_json::Value::String(r#ref) if key == "$ref" => {
+                            refs.push(r#ref.clone())
+                        }
+                        _ => collect_refs(value, refs),
+                    }
+                }
+            }
+            serde_json::Value::Array(array) => {
+                for value in array {
+                    collect_refs(value, refs);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_openapi_document_structure() {
+        let router = RouterBuilder::new()
+            .openapi_schema(
+                "Greeting",
+                serde_json::json!({
+                    "type": "object",
+                    "properties": { "message": { "type": "string" } },
+                }),
+            )
+            .any("/any", home)
+            .post("/hello", home)
+            .doc(
+                RouteDoc::new()
+                    .request_body("HelloBody")
+                    .response(Status::Ok, "Greeting"),
+            )
+            .build();
+        let doc = router.openapi();
+
+        // Every path item only contains OpenAPI operations
+        let operations = [
+            "get", "put", "post", "delete", "options", "head", "patch", "trace",
+        ];
+        for (_, item) in doc["paths"].as_object().unwrap() {
+            for (method, operation) in item.as_object().unwrap() {
+                assert!(operations.contains(&method.as_str()), "{}", method);
+                assert!(operation["responses"].is_object());
+            }
+        }
+        assert_eq!(doc["paths"]["/any"].as_object().unwrap().len(), 8);
+
+        // Every reference resolves to a component schema
+        let mut refs = Vec::new();
+        collect_refs(&doc["paths"], &mut refs);
+        assert_eq!(refs.len(), 2);
+        for r#ref in refs {
+            let name = r#ref.strip_prefix("#/components/schemas/").unwrap();
+            assert!(doc["components"]["schemas"][name].is_object(), "{}", name);
+        }
+        assert_eq!(
+            doc["components"]["schemas"]["Greeting"]["properties"]["message"]["type"],
+            "string"
+        );
+        assert_eq!(
+            doc["components"]["schemas"]["HelloBody"],
+            serde_json::json!({})
+        );
+    }
+
+    #[test]
+    fn test_automatic_head_and_options() {
+        let router = RouterBuilder::new().get("/", home).post("/", home).build();
+
+        // Test HEAD runs GET handler without body
+        let res = router.handle(&Request::head("http://localhost/"));
+        assert_eq!(res.status, Status::Ok);
+        assert!(res.body.is_empty());
+        assert_eq!(
+            res.headers.get("Content-Length").map(|s| s.as_str()),
+            Some("13")
+        );
+
+        // Test OPTIONS returns allowed methods
+        let res = router.handle(&Request::options("http://localhost/"));
+        assert_eq!(res.status, Status::NoContent);
+        assert_eq!(
+            res.headers.get("Allow").map(|s| s.as_str()),
+            Some("GET, HEAD, POST, OPTIONS")
+        );
+
+        // Test unknown path is still not found
+        let res = router.handle(&Request::options("http://localhost/unknown"));
+        assert_eq!(res.status, Status::NotFound);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_into_response() {
+        fn text(_req: &Request, _ctx: &()) -> &'static str {
+            "Hello"
+        }
+        fn created(_req: &Request, _ctx: &()) -> (Status, String) {
+            (Status::Created, "Created".to_string())
+        }
+        fn json(_req: &Request, _ctx: &()) -> Json<Vec<i32>> {
+            Json(vec![1, 2, 3])
+        }
+        fn fallible(req: &Request, _ctx: &()) -> Result<Status, Error> {
+            if req.url.query().is_some() {
+                Err(Error::bad_request("No query allowed"))
+            } else {
+                Ok(Status::NoContent)
+            }
+        }
+
+        let router = RouterBuilder::new()
+            .get("/text", text)
+            .get("/created", created)
+            .get("/json", json)
+            .get("/fallible", fallible)
+            .build();
+
+        let res = router.handle(&Request::get("http://localhost/text"));
+        assert_eq!(res.status, Status::Ok);
+        assert_eq!(res.body, b"Hello");
+
+        let res = router.handle(&Request::get("http://localhost/created"));
+        assert_eq!(res.status, Status::Created);
+        assert_eq!(res.body, b"Created");
+
+        let res = router.handle(&Request::get("http://localhost/json"));
+        assert_eq!(
+            res.headers.get("Content-Type").map(|s| s.as_str()),
+            Some("application/json")
+        );
+        assert_eq!(res.body, b"[1,2,3]");
+
+        let res = router.handle(&Request::get("http://localhost/fallible"));
+        assert_eq!(res.status, Status::NoContent);
+
+        let res = router.handle(&Request::get("http://localhost/fallible?a=b"));
+        assert_eq!(res.status, Status::BadRequest);
+        assert_eq!(
+            res.headers.get("Content-Type").map(|s| s.as_str()),
+            Some("application/problem+json")
+        );
+        let problem = serde_json::from_slice::<serde_json::Value>(&res.body).unwrap();
+        assert_eq!(problem["type"], "about:blank");
+        assert_eq!(problem["title"], "Bad Request");
+        assert_eq!(problem["status"], 400);
+        assert_eq!(problem["detail"], "No query allowed");
+        assert_eq!(problem["instance"], "/fallible");
+    }
+
+    #[test]
+    fn test_error_mapper() {
+        fn io_error(_req: &Request, _ctx: &()) -> Result<Response, Error> {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "disk on fire",
+            ))?
+        }
+
+        let router = RouterBuilder::new()
+            .error_mapper(|_, _, err| {
+                Response::with_status(err.status()).body(format!(
+                    "Mapped: {}",
+                    err.source_error()
+                        .map(|e| e.to_string())
+                        .unwrap_or_default()
+                ))
+            })
+            .get("/", io_error)
+            .build();
+
+        let res = router.handle(&Request::get("http://localhost/"));
+        assert_eq!(res.status, Status::InternalServerError);
+        assert_eq!(res.body, b"Mapped: disk on fire");
     }
 }
@@ -0,0 +1,262 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use serde_json::{json, Map, Value};
+use small_http::{Method, Status};
+
+use crate::error::status_reason;
+use crate::{Route, RoutePart};
+
+// MARK: RouteDoc
+/// Route documentation used for OpenAPI document generation
+#[derive(Clone, Default)]
+pub struct RouteDoc {
+    summary: Option<String>,
+    tags: Vec<String>,
+    params: Vec<Param>,
+    request_body: Option<Body>,
+    responses: Vec<(Status, Option<Body>)>,
+}
+
+#[derive(Clone)]
+struct Param {
+    name: String,
+    location: &'static str,
+    r#type: String,
+}
+
+#[derive(Clone)]
+struct Body {
+    content_type: String,
+    schema: String,
+}
+
+impl RouteDoc {
+    /// Create new route doc
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set summary
+    pub fn summary(mut self, summary: impl Into<String>) -> Self {
+        self.summary = Some(summary.into());
+        self
+    }
+
+    /// Add tag
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Set path param schema type, undocumented path params are strings
+    pub fn path_param(mut self, name: impl Into<String>, r#type: impl Into<String>) -> Self {
+        self.params.push(Param {
+            name: name.into(),
+            location: "path",
+            r#type: r#type.into(),
+        });
+        self
+    }
+
+    /// Add query param with schema type
+    pub fn query_param(mut self, name: impl Into<String>, r#type: impl Into<String>) -> Self {
+        self.params.push(Param {
+            name: name.into(),
+            location: "query",
+            r#type: r#type.into(),
+        });
+        self
+    }
+
+    /// Set JSON request body schema name
+    pub fn request_body(self, schema: impl Into<String>) -> Self {
+        self.request_body_with("application/json", schema)
+    }
+
+    /// Set request body content type and schema name
+    pub fn request_body_with(
+        mut self,
+        content_type: impl Into<String>,
+        schema: impl Into<String>,
+    ) -> Self {
+        self.request_body = Some(Body {
+            content_type: content_type.into(),
+            schema: schema.into(),
+        });
+        self
+    }
+
+    /// Add JSON response with schema name
+    pub fn response(self, status: Status, schema: impl Into<String>) -> Self {
+        self.response_with(status, "application/json", schema)
+    }
+
+    /// Add response with content type and schema name
+    pub fn response_with(
+        mut self,
+        status: Status,
+        content_type: impl Into<String>,
+        schema: impl Into<String>,
+    ) -> Self {
+        self.responses.push((
+            status,
+            Some(Body {
+                content_type: content_type.into(),
+                schema: schema.into(),
+            }),
+        ));
+        self
+    }
+
+    /// Add response without body
+    pub fn empty_response(mut self, status: Status) -> Self {
+        self.responses.push((status, None));
+        self
+    }
+}
+
+// MARK: OpenAPI
+pub(crate) fn openapi_document<T>(
+    title: &str,
+    version: &str,
+    schemas: &Map<String, Value>,
+    routes: &[Route<T>],
+) -> Value {
+    let mut paths = Map::new();
+    let mut schemas = schemas.clone();
+    for route in routes {
+        let path = route
+            .parts
+            .iter()
+            .map(|part| match part {
+                RoutePart::Static(value) => format!("/{}", value),
+                RoutePart::Param(name) => format!("/{{{}}}", name),
+            })
+            .collect::<String>();
+        let path = if path.is_empty() {
+            "/".to_string()
+        } else {
+            path
+        };
+
+        let doc = route.doc.clone().unwrap_or_default();
+        // Schemas that are referenced but not added are documented as any value
+        for body in doc
+            .request_body
+            .iter()
+            .chain(doc.responses.iter().filter_map(|(_, body)| body.as_ref()))
+        {
+            schemas
+                .entry(body.schema.clone())
+                .or_insert_with(|| json!({}));
+        }
+
+        let item = paths
+            .entry(path)
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .expect("Should be object");
+        // CONNECT is not an OpenAPI operation
+        for method in route
+            .methods
+            .iter()
+            .filter(|method| **method != Method::Connect)
+        {
+            item.insert(
+                method.to_string().to_lowercase(),
+                operation(&doc, &route.parts),
+            );
+        }
+    }
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": title,
+            "version": version,
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas,
+        },
+    })
+}
+
+fn operation(doc: &RouteDoc, parts: &[RoutePart]) -> Value {
+    let mut operation = Map::new();
+    if !doc.tags.is_empty() {
+        operation.insert("tags".to_string(), json!(doc.tags));
+    }
+    if let Some(summary) = &doc.summary {
+        operation.insert("summary".to_string(), json!(summary));
+    }
+
+    // Parameters
+    let mut parameters = Vec::new();
+    for part in parts {
+        if let RoutePart::Param(name) = part {
+            let r#type = doc
+                .params
+                .iter()
+                .find(|param| param.location == "path" && param.name == *name)
+                .map_or("string", |param| param.r#type.as_str());
+            parameters.push(json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": { "type": r#type },
+            }));
+        }
+    }
+    for param in doc.params.iter().filter(|param| param.location == "query") {
+        parameters.push(json!({
+            "name": param.name,
+            "in": "query",
+            "required": false,
+            "schema": { "type": param.r#type },
+        }));
+    }
+    if !parameters.is_empty() {
+        operation.insert("parameters".to_string(), Value::Array(parameters));
+    }
+
+    // Request body
+    if let Some(body) = &doc.request_body {
+        operation.insert(
+            "requestBody".to_string(),
+            json!({
+                "required": true,
+                "content": body_content(body),
+            }),
+        );
+    }
+
+    // Responses
+    let mut responses = Map::new();
+    if doc.responses.is_empty() {
+        responses.insert("200".to_string(), json!({ "description": "OK" }));
+    }
+    for (status, body) in &doc.responses {
+        let mut response = Map::new();
+        response.insert("description".to_string(), json!(status_reason(*status)));
+        if let Some(body) = body {
+            response.insert("content".to_string(), body_content(body));
+        }
+        responses.insert((*status as u16).to_string(), Value::Object(response));
+    }
+    operation.insert("responses".to_string(), Value::Object(responses));
+
+    Value::Object(operation)
+}
+
+fn body_content(body: &Body) -> Value {
+    json!({
+        body.content_type.clone(): {
+            "schema": { "$ref": format!("#/components/schemas/{}", body.schema) },
+        },
+    })
+}
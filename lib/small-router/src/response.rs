@@ -0,0 +1,98 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+#[cfg(feature = "json")]
+use serde::Serialize;
+use small_http::{Response, Status};
+
+use crate::error::Error;
+
+// MARK: IntoResponse
+/// Trait for types that can be converted into a response
+pub trait IntoResponse: Sized {
+    /// Convert into response
+    fn into_response(self) -> Response;
+
+    /// Convert into response or router error, used by the router to run the error mapper
+    #[doc(hidden)]
+    fn into_result(self) -> Result<Response, Error> {
+        Ok(self.into_response())
+    }
+}
+
+impl IntoResponse for Response {
+    fn into_response(self) -> Response {
+        self
+    }
+}
+
+impl IntoResponse for Status {
+    fn into_response(self) -> Response {
+        Response::with_status(self)
+    }
+}
+
+impl IntoResponse for () {
+    fn into_response(self) -> Response {
+        Response::new()
+    }
+}
+
+impl IntoResponse for &'static str {
+    fn into_response(self) -> Response {
+        Response::with_body(self)
+    }
+}
+
+impl IntoResponse for String {
+    fn into_response(self) -> Response {
+        Response::with_body(self)
+    }
+}
+
+impl IntoResponse for Vec<u8> {
+    fn into_response(self) -> Response {
+        Response::with_body(self)
+    }
+}
+
+impl<R: IntoResponse> IntoResponse for (Status, R) {
+    fn into_response(self) -> Response {
+        self.1.into_response().status(self.0)
+    }
+
+    fn into_result(self) -> Result<Response, Error> {
+        self.1.into_result().map(|res| res.status(self.0))
+    }
+}
+
+impl<T: IntoResponse, E: IntoResponse> IntoResponse for Result<T, E> {
+    fn into_response(self) -> Response {
+        match self {
+            Ok(value) => value.into_response(),
+            Err(err) => err.into_response(),
+        }
+    }
+
+    fn into_result(self) -> Result<Response, Error> {
+        match self {
+            Ok(value) => value.into_result(),
+            Err(err) => err.into_result(),
+        }
+    }
+}
+
+// MARK: Json
+/// Json response wrapper
+#[cfg(feature = "json")]
+pub struct Json<T>(pub T);
+
+#[cfg(feature = "json")]
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        Response::with_json(self.0)
+    }
+}
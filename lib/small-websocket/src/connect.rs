@@ -0,0 +1,203 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::io::Read;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use small_http::{Request, Response, Status};
+
+#[cfg(feature = "deflate")]
+use crate::DeflateConfig;
+#[cfg(feature = "deflate")]
+use crate::deflate;
+use crate::upgrade::{accept_key, split_header};
+use crate::{ConnectError, WebSocket};
+
+/// Maximum size of the handshake response head
+const MAX_HANDSHAKE_SIZE: usize = 8 * 1024;
+
+// MARK: ConnectBuilder
+/// Builder to customise the WebSocket client handshake
+#[derive(Clone)]
+pub struct ConnectBuilder {
+    url: String,
+    headers: Vec<(String, String)>,
+    protocols: Vec<String>,
+    connect_timeout: Option<Duration>,
+    handshake_timeout: Option<Duration>,
+    #[cfg(feature = "deflate")]
+    deflate: Option<DeflateConfig>,
+}
+
+impl ConnectBuilder {
+    /// Create new connect builder
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            headers: Vec::new(),
+            protocols: Vec::new(),
+            connect_timeout: None,
+            handshake_timeout: None,
+            #[cfg(feature = "deflate")]
+            deflate: Some(DeflateConfig::default()),
+        }
+    }
+
+    /// Add request header
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Add subprotocol to offer, in order of preference
+    pub fn protocol(mut self, protocol: impl Into<String>) -> Self {
+        self.protocols.push(protocol.into());
+        self
+    }
+
+    /// Set TCP connect timeout
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set timeout for the upgrade handshake
+    pub fn handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = Some(timeout);
+        self
+    }
+
+    /// Set permessage-deflate config
+    #[cfg(feature = "deflate")]
+    pub fn deflate(mut self, config: DeflateConfig) -> Self {
+        self.deflate = Some(config);
+        self
+    }
+
+    /// Disable permessage-deflate compression
+    #[cfg(feature = "deflate")]
+    pub fn no_deflate(mut self) -> Self {
+        self.deflate = None;
+        self
+    }
+
+    /// Connect to a WebSocket server
+    pub fn connect(self) -> Result<WebSocket, ConnectError> {
+        let parsed_url = url::Url::parse(&self.url)
+            .map_err(|err| ConnectError::new(format!("invalid url: {}", err)))?;
+        let addr = format!(
+            "{}:{}",
+            parsed_url
+                .host()
+                .ok_or_else(|| ConnectError::new("url has no host"))?,
+            parsed_url.port().unwrap_or(80)
+        );
+        let io_error = |err: std::io::Error| ConnectError::new(err.to_string());
+        let mut stream = match self.connect_timeout {
+            Some(timeout) => addr
+                .to_socket_addrs()
+                .map_err(io_error)?
+                .find_map(|addr| TcpStream::connect_timeout(&addr, timeout).ok())
+                .ok_or_else(|| ConnectError::new(format!("can't connect to {}", addr)))?,
+            None => TcpStream::connect(&addr).map_err(io_error)?,
+        };
+        stream
+            .set_read_timeout(self.handshake_timeout)
+            .map_err(io_error)?;
+        stream
+            .set_write_timeout(self.handshake_timeout)
+            .map_err(io_error)?;
+
+        let mut random_key = [0u8; 16];
+        getrandom::fill(&mut random_key).expect("Can't generate random key");
+        let random_key = BASE64_STANDARD.encode(random_key);
+        let mut req = Request::get(&self.url)
+            .header("Upgrade", "websocket")
+            .header("Connection", "Upgrade")
+            .header("Sec-WebSocket-Version", "13")
+            .header("Sec-WebSocket-Key", &random_key);
+        if !self.protocols.is_empty() {
+            req = req.header("Sec-WebSocket-Protocol", self.protocols.join(", "));
+        }
+        #[cfg(feature = "deflate")]
+        if let Some(config) = &self.deflate {
+            req = req.header("Sec-WebSocket-Extensions", deflate::client_offer(config));
+        }
+        for (name, value) in &self.headers {
+            req = req.header(name, value);
+        }
+        req.write_to_stream(&mut stream, false);
+
+        // Read response head byte by byte, so frames send right after the handshake stay in the stream
+        let mut head = Vec::new();
+        let mut byte = [0; 1];
+        while !head.ends_with(b"\r\n\r\n") {
+            if head.len() >= MAX_HANDSHAKE_SIZE {
+                return Err(ConnectError::new("handshake response is too large"));
+            }
+            stream.read_exact(&mut byte).map_err(io_error)?;
+            head.push(byte[0]);
+        }
+        let res = Response::read_from_stream(&mut head.as_slice())
+            .map_err(|err| ConnectError::new(format!("invalid handshake response: {}", err)))?;
+        if res.status != Status::SwitchingProtocols {
+            return Err(ConnectError::new(format!(
+                "expected status 101 Switching Protocols, got {}",
+                res.status
+            )));
+        }
+        let websocket_accept = res
+            .headers
+            .get("Sec-WebSocket-Accept")
+            .ok_or_else(|| ConnectError::new("missing Sec-WebSocket-Accept header"))?;
+        let expected_accept = accept_key(&random_key);
+        if *websocket_accept != expected_accept {
+            return Err(ConnectError::new(format!(
+                "expected Sec-WebSocket-Accept header to be '{}', got '{}'",
+                expected_accept, websocket_accept
+            )));
+        }
+
+        // Server may only select one of the offered subprotocols
+        let protocol = match res.headers.get("Sec-WebSocket-Protocol") {
+            Some(protocol) => {
+                let mut selected = split_header(protocol);
+                match (selected.next(), selected.next()) {
+                    (Some(protocol), None) if self.protocols.iter().any(|p| p == protocol) => {
+                        Some(protocol.to_string())
+                    }
+                    _ => {
+                        return Err(ConnectError::new(format!(
+                            "server selected unoffered subprotocol '{}'",
+                            protocol
+                        )));
+                    }
+                }
+            }
+            None => None,
+        };
+
+        stream.set_read_timeout(None).map_err(io_error)?;
+        stream.set_write_timeout(None).map_err(io_error)?;
+        let mut ws = WebSocket::new(stream, true);
+        ws.protocol = protocol;
+        #[cfg(feature = "deflate")]
+        if let Some(extensions) = res.headers.get("Sec-WebSocket-Extensions") {
+            let negotiated = self
+                .deflate
+                .as_ref()
+                .and_then(|config| deflate::parse_response(extensions, config))
+                .ok_or_else(|| {
+                    ConnectError::new(format!("unsupported extensions '{}'", extensions))
+                })?;
+            ws.set_deflate(negotiated, true);
+        }
+        Ok(ws)
+    }
+}
@@ -0,0 +1,487 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+//! permessage-deflate extension ([RFC 7692](https://www.rfc-editor.org/rfc/rfc7692))
+
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress};
+
+use crate::ProtocolError;
+
+/// Tail that is removed from and added to compressed messages
+const DEFLATE_TAIL: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// Smallest window size the compressor supports
+const MIN_WINDOW_BITS: u8 = 9;
+const MAX_WINDOW_BITS: u8 = 15;
+
+// MARK: DeflateConfig
+/// permessage-deflate configuration
+#[derive(Debug, Clone)]
+pub struct DeflateConfig {
+    server_no_context_takeover: bool,
+    client_no_context_takeover: bool,
+    server_max_window_bits: u8,
+    client_max_window_bits: u8,
+}
+
+impl Default for DeflateConfig {
+    fn default() -> Self {
+        Self {
+            server_no_context_takeover: false,
+            client_no_context_takeover: false,
+            server_max_window_bits: MAX_WINDOW_BITS,
+            client_max_window_bits: MAX_WINDOW_BITS,
+        }
+    }
+}
+
+impl DeflateConfig {
+    /// Create new default config
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reset server compression context after every message, uses less memory
+    pub fn server_no_context_takeover(mut self) -> Self {
+        self.server_no_context_takeover = true;
+        self
+    }
+
+    /// Reset client compression context after every message, uses less memory
+    pub fn client_no_context_takeover(mut self) -> Self {
+        self.client_no_context_takeover = true;
+        self
+    }
+
+    /// Set server compression max window bits (9..=15)
+    pub fn server_max_window_bits(mut self, bits: u8) -> Self {
+        assert!(
+            (MIN_WINDOW_BITS..=MAX_WINDOW_BITS).contains(&bits),
+            "Window bits must be within 9..=15"
+        );
+        self.server_max_window_bits = bits;
+        self
+    }
+
+    /// Set client compression max window bits (9..=15)
+    pub fn client_max_window_bits(mut self, bits: u8) -> Self {
+        assert!(
+            (MIN_WINDOW_BITS..=MAX_WINDOW_BITS).contains(&bits),
+            "Window bits must be within 9..=15"
+        );
+        self.client_max_window_bits = bits;
+        self
+    }
+}
+
+// MARK: Negotiation
+/// Negotiated permessage-deflate parameters
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Negotiated {
+    server_no_context_takeover: bool,
+    client_no_context_takeover: bool,
+    server_max_window_bits: u8,
+    client_max_window_bits: u8,
+}
+
+impl Negotiated {
+    /// Extension response header value
+    pub(crate) fn response_header(&self) -> String {
+        let mut header = "permessage-deflate".to_string();
+        if self.server_no_context_takeover {
+            header.push_str("; server_no_context_takeover");
+        }
+        if self.client_no_context_takeover {
+            header.push_str("; client_no_context_takeover");
+        }
+        if self.server_max_window_bits < MAX_WINDOW_BITS {
+            header.push_str(&format!(
+                "; server_max_window_bits={}",
+                self.server_max_window_bits
+            ));
+        }
+        if self.client_max_window_bits < MAX_WINDOW_BITS {
+            header.push_str(&format!(
+                "; client_max_window_bits={}",
+                self.client_max_window_bits
+            ));
+        }
+        header
+    }
+
+    /// Create compressor and decompressor for our side of the connection
+    pub(crate) fn into_codecs(self, is_client: bool) -> (Deflater, Inflater) {
+        let (window_bits, compress_reset, decompress_reset) = if is_client {
+            (
+                self.client_max_window_bits,
+                self.client_no_context_takeover,
+                self.server_no_context_takeover,
+            )
+        } else {
+            (
+                self.server_max_window_bits,
+                self.server_no_context_takeover,
+                self.client_no_context_takeover,
+            )
+        };
+        (
+            Deflater {
+                compress: Compress::new_with_window_bits(
+                    Compression::default(),
+                    false,
+                    window_bits,
+                ),
+                no_context_takeover: compress_reset,
+            },
+            Inflater {
+                decompress: Decompress::new(false),
+                no_context_takeover: decompress_reset,
+            },
+        )
+    }
+}
+
+type Params<'a> = Vec<(&'a str, Option<&'a str>)>;
+
+/// Parse extensions header into permessage-deflate offers, returns `None` for malformed params
+fn parse_offers(header: &str) -> Vec<Option<Params<'_>>> {
+    header
+        .split(',')
+        .filter_map(|extension| {
+            let mut parts = extension.split(';').map(str::trim);
+            if parts.next() != Some("permessage-deflate") {
+                return None;
+            }
+            let mut params = Params::new();
+            for part in parts {
+                let (name, value) = match part.split_once('=') {
+                    Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                    None => (part, None),
+                };
+                if params.iter().any(|(other, _)| *other == name) {
+                    return Some(None);
+                }
+                params.push((name, value));
+            }
+            Some(Some(params))
+        })
+        .collect()
+}
+
+fn parse_window_bits(value: Option<&str>) -> Option<u8> {
+    value
+        .and_then(|value| value.parse::<u8>().ok())
+        .filter(|bits| (8..=MAX_WINDOW_BITS).contains(bits))
+}
+
+/// Accept first acceptable client offer, returns `None` when no offer is acceptable
+pub(crate) fn accept_offer(header: &str, config: &DeflateConfig) -> Option<Negotiated> {
+    parse_offers(header)
+        .into_iter()
+        .flatten()
+        .find_map(|params| {
+            let mut negotiated = Negotiated {
+                server_no_context_takeover: config.server_no_context_takeover,
+                client_no_context_takeover: config.client_no_context_takeover,
+                server_max_window_bits: config.server_max_window_bits,
+                client_max_window_bits: MAX_WINDOW_BITS,
+            };
+            for (name, value) in params {
+                match (name, value) {
+                    ("server_no_context_takeover", None) => {
+                        negotiated.server_no_context_takeover = true
+                    }
+                    ("client_no_context_takeover", None) => {
+                        negotiated.client_no_context_takeover = true
+                    }
+                    ("server_max_window_bits", value) => {
+                        negotiated.server_max_window_bits = negotiated
+                            .server_max_window_bits
+                            .min(parse_window_bits(value)?)
+                    }
+                    ("client_max_window_bits", None) => {
+                        negotiated.client_max_window_bits = config.client_max_window_bits
+                    }
+                    ("client_max_window_bits", value) => {
+                        negotiated.client_max_window_bits =
+                            config.client_max_window_bits.min(parse_window_bits(value)?)
+                    }
+                    _ => return None,
+                }
+            }
+            // The compressor doesn't support 8 bit windows
+            if negotiated.server_max_window_bits < MIN_WINDOW_BITS
+                || negotiated.client_max_window_bits < MIN_WINDOW_BITS
+            {
+                return None;
+            }
+            Some(negotiated)
+        })
+}
+
+/// Client extension offer header value
+#[cfg(feature = "client")]
+pub(crate) fn client_offer(config: &DeflateConfig) -> String {
+    let mut header = "permessage-deflate; client_max_window_bits".to_string();
+    if config.client_max_window_bits < MAX_WINDOW_BITS {
+        header.push_str(&format!("={}", config.client_max_window_bits));
+    }
+    if config.server_no_context_takeover {
+        header.push_str("; server_no_context_takeover");
+    }
+    if config.client_no_context_takeover {
+        header.push_str("; client_no_context_takeover");
+    }
+    if config.server_max_window_bits < MAX_WINDOW_BITS {
+        header.push_str(&format!(
+            "; server_max_window_bits={}",
+            config.server_max_window_bits
+        ));
+    }
+    header
+}
+
+/// Parse server extension response, returns `None` when the response is invalid
+#[cfg(feature = "client")]
+pub(crate) fn parse_response(header: &str, config: &DeflateConfig) -> Option<Negotiated> {
+    let offers = parse_offers(header);
+    if offers.len() != 1 {
+        return None;
+    }
+    let mut negotiated = Negotiated {
+        server_no_context_takeover: false,
+        client_no_context_takeover: config.client_no_context_takeover,
+        server_max_window_bits: MAX_WINDOW_BITS,
+        client_max_window_bits: config.client_max_window_bits,
+    };
+    for (name, value) in offers.into_iter().next().flatten()? {
+        match (name, value) {
+            ("server_no_context_takeover", None) => negotiated.server_no_context_takeover = true,
+            ("client_no_context_takeover", None) => negotiated.client_no_context_takeover = true,
+            ("server_max_window_bits", value) => {
+                negotiated.server_max_window_bits = parse_window_bits(value)?
+            }
+            ("client_max_window_bits", value) => {
+                negotiated.client_max_window_bits = negotiated
+                    .client_max_window_bits
+                    .min(parse_window_bits(value)?)
+            }
+            _ => return None,
+        }
+    }
+    if negotiated.client_max_window_bits < MIN_WINDOW_BITS
+        || (config.server_no_context_takeover && !negotiated.server_no_context_takeover)
+        || negotiated.server_max_window_bits > config.server_max_window_bits
+    {
+        return None;
+    }
+    Some(negotiated)
+}
+
+// MARK: Deflater
+/// Message compressor
+pub(crate) struct Deflater {
+    compress: Compress,
+    no_context_takeover: bool,
+}
+
+impl Deflater {
+    pub(crate) fn deflate(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut output = Vec::with_capacity(data.len() / 2 + 64);
+        let start_in = self.compress.total_in();
+        loop {
+            let consumed = (self.compress.total_in() - start_in) as usize;
+            output.reserve(256);
+            self.compress
+                .compress_vec(&data[consumed..], &mut output, FlushCompress::Sync)
+                .expect("Can't compress message");
+            let consumed = (self.compress.total_in() - start_in) as usize;
+            if consumed == data.len() && output.len() < output.capacity() {
+                break;
+            }
+        }
+        if output.ends_with(&DEFLATE_TAIL) {
+            output.truncate(output.len() - DEFLATE_TAIL.len());
+        }
+        if self.no_context_takeover {
+            self.compress.reset();
+        }
+        output
+    }
+}
+
+// MARK: Inflater
+/// Message decompressor
+pub(crate) struct Inflater {
+    decompress: Decompress,
+    no_context_takeover: bool,
+}
+
+impl Inflater {
+    pub(crate) fn inflate(
+        &mut self,
+        data: &[u8],
+        max_size: usize,
+    ) -> Result<Vec<u8>, ProtocolError> {
+        let mut input = Vec::with_capacity(data.len() + DEFLATE_TAIL.len());
+        input.extend_from_slice(data);
+        input.extend_from_slice(&DEFLATE_TAIL);
+
+        let mut output = Vec::with_capacity((data.len() * 2).min(max_size));
+        let start_in = self.decompress.total_in();
+        loop {
+            let consumed = (self.decompress.total_in() - start_in) as usize;
+            let total_out = self.decompress.total_out();
+            output.reserve((max_size + 1 - output.len()).clamp(1, 64 * 1024));
+            self.decompress
+                .decompress_vec(&input[consumed..], &mut output, FlushDecompress::Sync)
+                .map_err(|_| ProtocolError::Protocol("Invalid compressed data"))?;
+            if output.len() > max_size {
+                return Err(ProtocolError::MessageTooBig);
+            }
+            let consumed_now = (self.decompress.total_in() - start_in) as usize;
+            let done = consumed_now == input.len() && output.len() < output.capacity();
+            let stalled = consumed_now == consumed && self.decompress.total_out() == total_out;
+            if done || stalled {
+                break;
+            }
+        }
+        if self.no_context_takeover {
+            self.decompress.reset(false);
+        }
+        Ok(output)
+    }
+}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_accept_offer() {
+        let config = DeflateConfig::default();
+        assert_eq!(
+            accept_offer("permessage-deflate; client_max_window_bits", &config)
+                .unwrap()
+                .response_header(),
+            "permessage-deflate"
+        );
+        assert_eq!(
+            accept_offer(
+                "permessage-deflate; server_max_window_bits=10; client_no_context_takeover",
+                &config
+            )
+            .unwrap()
+            .response_header(),
+            "permessage-deflate; client_no_context_takeover; server_max_window_bits=10"
+        );
+
+        // Unacceptable offers are skipped
+        assert_eq!(
+            accept_offer(
+                "permessage-deflate; server_max_window_bits=8, permessage-deflate; server_no_context_takeover",
+                &config
+            )
+            .unwrap()
+            .response_header(),
+            "permessage-deflate; server_no_context_takeover"
+        );
+        assert!(accept_offer("permessage-deflate; unknown", &config).is_none());
+        assert!(accept_offer("x-webkit-deflate-frame", &config).is_none());
+
+        // Client window bits are only limited when client allows it
+        let config = DeflateConfig::new().client_max_window_bits(12);
+        assert_eq!(
+            accept_offer("permessage-deflate; client_max_window_bits", &config)
+                .unwrap()
+                .response_header(),
+            "permessage-deflate; client_max_window_bits=12"
+        );
+        assert_eq!(
+            accept_offer("permessage-deflate", &config)
+                .unwrap()
+                .response_header(),
+            "permessage-deflate"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "client")]
+    fn test_parse_response() {
+        let config = DeflateConfig::default();
+        assert_eq!(
+            client_offer(&config),
+            "permessage-deflate; client_max_window_bits"
+        );
+        let negotiated = parse_response(
+            "permessage-deflate; server_no_context_takeover; client_max_window_bits=10",
+            &config,
+        )
+        .unwrap();
+        assert!(negotiated.server_no_context_takeover);
+        assert_eq!(negotiated.client_max_window_bits, 10);
+
+        assert!(parse_response("permessage-deflate; unknown", &config).is_none());
+        assert!(parse_response("permessage-deflate, permessage-deflate", &config).is_none());
+        let config = DeflateConfig::new().server_no_context_takeover();
+        assert!(parse_response("permessage-deflate", &config).is_none());
+    }
+
+    #[test]
+    fn test_inflate_rfc_example() {
+        let (_, mut inflater) = accept_offer("permessage-deflate", &DeflateConfig::default())
+            .unwrap()
+            .into_codecs(false);
+        // Example from RFC 7692 section 7.2.3.2, the second message uses the shared context
+        assert_eq!(
+            inflater
+                .inflate(&[0xf2, 0x48, 0xcd, 0xc9, 0xc9, 0x07, 0x00], 1024)
+                .unwrap(),
+            b"Hello"
+        );
+        assert_eq!(
+            inflater
+                .inflate(&[0xf2, 0x00, 0x11, 0x00, 0x00], 1024)
+                .unwrap(),
+            b"Hello"
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for offer in [
+            "permessage-deflate",
+            "permessage-deflate; server_no_context_takeover; server_max_window_bits=9",
+        ] {
+            let (mut deflater, _) = accept_offer(offer, &DeflateConfig::default())
+                .unwrap()
+                .into_codecs(false);
+            let (_, mut inflater) = accept_offer(offer, &DeflateConfig::default())
+                .unwrap()
+                .into_codecs(true);
+            let message = br#"{"type":"setColor","color":16711680}"#.repeat(20);
+            for _ in 0..3 {
+                let compressed = deflater.deflate(&message);
+                assert!(compressed.len() < message.len() / 4);
+                assert_eq!(inflater.inflate(&compressed, 1 << 20).unwrap(), message);
+            }
+        }
+    }
+
+    #[test]
+    fn test_inflate_max_size() {
+        let (mut deflater, mut inflater) =
+            accept_offer("permessage-deflate", &DeflateConfig::default())
+                .unwrap()
+                .into_codecs(false);
+        let compressed = deflater.deflate(&[0; 100_000]);
+        assert_eq!(
+            inflater.inflate(&compressed, 1000),
+            Err(ProtocolError::MessageTooBig)
+        );
+    }
+}
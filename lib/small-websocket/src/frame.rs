@@ -0,0 +1,685 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read};
+
+use crate::Message;
+#[cfg(feature = "deflate")]
+use crate::deflate::Inflater;
+
+// MARK: Opcodes
+pub(crate) const OPCODE_CONTINUATION: u8 = 0x0;
+pub(crate) const OPCODE_TEXT: u8 = 0x1;
+pub(crate) const OPCODE_BINARY: u8 = 0x2;
+pub(crate) const OPCODE_CLOSE: u8 = 0x8;
+pub(crate) const OPCODE_PING: u8 = 0x9;
+pub(crate) const OPCODE_PONG: u8 = 0xA;
+
+/// Reserved bit used by permessage-deflate to mark compressed messages
+pub(crate) const RSV1: u8 = 0x40;
+
+/// Maximum payload size of control frames
+pub(crate) const MAX_CONTROL_PAYLOAD_SIZE: usize = 125;
+
+/// Default maximum message size
+pub(crate) const DEFAULT_MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+// MARK: ProtocolError
+/// WebSocket protocol error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtocolError {
+    /// Peer violated the WebSocket protocol
+    Protocol(&'static str),
+    /// Text message or close reason is not valid UTF-8
+    InvalidUtf8,
+    /// Message is larger than the maximum message size
+    MessageTooBig,
+}
+
+impl ProtocolError {
+    /// Close status code for this error
+    pub fn close_code(&self) -> u16 {
+        match self {
+            ProtocolError::Protocol(_) => 1002,
+            ProtocolError::InvalidUtf8 => 1007,
+            ProtocolError::MessageTooBig => 1009,
+        }
+    }
+}
+
+impl Display for ProtocolError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::Protocol(reason) => write!(f, "Protocol error: {}", reason),
+            ProtocolError::InvalidUtf8 => write!(f, "Invalid UTF-8"),
+            ProtocolError::MessageTooBig => write!(f, "Message too big"),
+        }
+    }
+}
+
+impl Error for ProtocolError {}
+
+// MARK: Frame
+#[derive(Debug)]
+pub(crate) struct Frame {
+    pub fin: bool,
+    pub compressed: bool,
+    pub opcode: u8,
+    pub payload: Vec<u8>,
+}
+
+pub(crate) fn encode_frame(
+    fin: bool,
+    opcode: u8,
+    payload: &[u8],
+    mask: Option<[u8; 4]>,
+) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 14);
+    frame.push(if fin { 0x80 } else { 0 } | opcode);
+    let mask_bit = if mask.is_some() { 0x80 } else { 0 };
+    let payload_len = payload.len();
+    if payload_len <= 125 {
+        frame.push(mask_bit | payload_len as u8);
+    } else if payload_len <= 65535 {
+        frame.push(mask_bit | 126);
+        frame.extend_from_slice(&(payload_len as u16).to_be_bytes());
+    } else {
+        frame.push(mask_bit | 127);
+        frame.extend_from_slice(&(payload_len as u64).to_be_bytes());
+    }
+    match mask {
+        Some(mask) => {
+            frame.extend_from_slice(&mask);
+            frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        }
+        None => frame.extend_from_slice(payload),
+    }
+    frame
+}
+
+// MARK: FrameDecoder
+/// Incremental frame decoder that buffers partial frames
+pub(crate) struct FrameDecoder {
+    buffer: Vec<u8>,
+    expect_masked: bool,
+    allow_compressed: bool,
+}
+
+impl FrameDecoder {
+    pub(crate) fn new(expect_masked: bool) -> Self {
+        Self {
+            buffer: Vec::new(),
+            expect_masked,
+            allow_compressed: false,
+        }
+    }
+
+    pub(crate) fn extend(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Decode next frame out of the buffer, returns `None` when more data is needed
+    pub(crate) fn decode(
+        &mut self,
+        max_payload_len: usize,
+    ) -> Result<Option<Frame>, ProtocolError> {
+        if self.buffer.len() < 2 {
+            return Ok(None);
+        }
+        let fin = self.buffer[0] & 0x80 != 0;
+        let compressed = self.buffer[0] & RSV1 != 0;
+        let rsv = (self.buffer[0] >> 4) & 0x3;
+        let opcode = self.buffer[0] & 0x0F;
+        let masked = self.buffer[1] & 0x80 != 0;
+        let short_len = self.buffer[1] & 0x7F;
+
+        // Validate header
+        if rsv != 0 || (compressed && !self.allow_compressed) {
+            return Err(ProtocolError::Protocol("Reserved bits must be zero"));
+        }
+        match opcode {
+            OPCODE_CONTINUATION | OPCODE_TEXT | OPCODE_BINARY => {}
+            OPCODE_CLOSE | OPCODE_PING | OPCODE_PONG => {
+                if !fin {
+                    return Err(ProtocolError::Protocol(
+                        "Control frames can't be fragmented",
+                    ));
+                }
+                if short_len as usize > MAX_CONTROL_PAYLOAD_SIZE {
+                    return Err(ProtocolError::Protocol("Control frame payload too large"));
+                }
+                if compressed {
+                    return Err(ProtocolError::Protocol(
+                        "Control frames can't be compressed",
+                    ));
+                }
+            }
+            _ => return Err(ProtocolError::Protocol("Unknown opcode")),
+        }
+        if masked != self.expect_masked {
+            return Err(ProtocolError::Protocol(if self.expect_masked {
+                "Frames from client must be masked"
+            } else {
+                "Frames from server must not be masked"
+            }));
+        }
+
+        // Read payload length
+        let len_size = match short_len {
+            126 => 2,
+            127 => 8,
+            _ => 0,
+        };
+        let header_len = 2 + len_size + if masked { 4 } else { 0 };
+        if self.buffer.len() < header_len {
+            return Ok(None);
+        }
+        let payload_len = match short_len {
+            126 => u16::from_be_bytes([self.buffer[2], self.buffer[3]]) as u64,
+            127 => {
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(&self.buffer[2..10]);
+                let len = u64::from_be_bytes(bytes);
+                if len & (1 << 63) != 0 {
+                    return Err(ProtocolError::Protocol(
+                        "Payload length most significant bit set",
+                    ));
+                }
+                len
+            }
+            len => len as u64,
+        };
+        let payload_len = usize::try_from(payload_len)
+            .ok()
+            .filter(|len| *len <= max_payload_len)
+            .ok_or(ProtocolError::MessageTooBig)?;
+        if self.buffer.len() < header_len + payload_len {
+            return Ok(None);
+        }
+
+        // Unmask payload
+        let mut payload = self.buffer[header_len..header_len + payload_len].to_vec();
+        if masked {
+            let mask_offset = header_len - 4;
+            let mask = [
+                self.buffer[mask_offset],
+                self.buffer[mask_offset + 1],
+                self.buffer[mask_offset + 2],
+                self.buffer[mask_offset + 3],
+            ];
+            for (i, byte) in payload.iter_mut().enumerate() {
+                *byte ^= mask[i % 4];
+            }
+        }
+        self.buffer.drain(..header_len + payload_len);
+
+        Ok(Some(Frame {
+            fin,
+            compressed,
+            opcode,
+            payload,
+        }))
+    }
+}
+
+// MARK: MessageReader
+/// Message reader that reassembles fragmented messages out of frames
+pub(crate) struct MessageReader {
+    decoder: FrameDecoder,
+    fragments: Option<(u8, bool, Vec<u8>)>,
+    pub(crate) max_message_size: usize,
+    #[cfg(feature = "deflate")]
+    inflater: Option<Inflater>,
+}
+
+impl MessageReader {
+    pub(crate) fn new(expect_masked: bool) -> Self {
+        Self {
+            decoder: FrameDecoder::new(expect_masked),
+            fragments: None,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            #[cfg(feature = "deflate")]
+            inflater: None,
+        }
+    }
+
+    /// Accept compressed messages and decompress them with inflater
+    #[cfg(feature = "deflate")]
+    pub(crate) fn set_inflater(&mut self, inflater: Inflater) {
+        self.decoder.allow_compressed = true;
+        self.inflater = Some(inflater);
+    }
+
+    /// Read next message from stream, blocks until a complete message is read
+    #[cfg(test)]
+    pub(crate) fn read_message(&mut self, stream: &mut impl Read) -> io::Result<Message> {
+        loop {
+            if let Some(message) = self.next_message().map_err(invalid_data)? {
+                return Ok(message);
+            }
+            if !self.fill(stream)? {
+                return Ok(Message::Close(None, Some("Connection closed".to_string())));
+            }
+        }
+    }
+
+    /// Read data from stream into the decoder, returns `false` when the stream is closed
+    pub(crate) fn fill(&mut self, stream: &mut impl Read) -> io::Result<bool> {
+        let mut buf = [0; 8192];
+        let n = stream.read(&mut buf)?;
+        self.decoder.extend(&buf[..n]);
+        Ok(n > 0)
+    }
+
+    /// Get next complete message out of the buffered frames
+    pub(crate) fn next_message(&mut self) -> Result<Option<Message>, ProtocolError> {
+        loop {
+            let remaining = self.max_message_size
+                - self.fragments.as_ref().map_or(0, |(_, _, data)| data.len());
+            let frame = match self.decoder.decode(remaining)? {
+                Some(frame) => frame,
+                None => return Ok(None),
+            };
+            match frame.opcode {
+                OPCODE_CONTINUATION => {
+                    if frame.compressed {
+                        return Err(ProtocolError::Protocol(
+                            "Continuation frames can't be compressed",
+                        ));
+                    }
+                    let (_, _, data) = self
+                        .fragments
+                        .as_mut()
+                        .ok_or(ProtocolError::Protocol("Unexpected continuation frame"))?;
+                    data.extend_from_slice(&frame.payload);
+                    if frame.fin {
+                        let (opcode, compressed, data) =
+                            self.fragments.take().expect("Should be some");
+                        return self.data_message(opcode, compressed, data).map(Some);
+                    }
+                }
+                OPCODE_TEXT | OPCODE_BINARY => {
+                    if self.fragments.is_some() {
+                        return Err(ProtocolError::Protocol("Expected continuation frame"));
+                    }
+                    if frame.fin {
+                        return self
+                            .data_message(frame.opcode, frame.compressed, frame.payload)
+                            .map(Some);
+                    }
+                    self.fragments = Some((frame.opcode, frame.compressed, frame.payload));
+                }
+                OPCODE_CLOSE => return close_message(frame.payload).map(Some),
+                OPCODE_PING => return Ok(Some(Message::Ping(frame.payload))),
+                OPCODE_PONG => return Ok(Some(Message::Pong(frame.payload))),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    fn data_message(
+        &mut self,
+        opcode: u8,
+        compressed: bool,
+        data: Vec<u8>,
+    ) -> Result<Message, ProtocolError> {
+        #[cfg(feature = "deflate")]
+        let data = match (compressed, &mut self.inflater) {
+            (true, Some(inflater)) => inflater.inflate(&data, self.max_message_size)?,
+            _ => data,
+        };
+        #[cfg(not(feature = "deflate"))]
+        let _ = compressed;
+
+        if opcode == OPCODE_TEXT {
+            String::from_utf8(data)
+                .map(Message::Text)
+                .map_err(|_| ProtocolError::InvalidUtf8)
+        } else {
+            Ok(Message::Binary(data))
+        }
+    }
+}
+
+fn close_message(payload: Vec<u8>) -> Result<Message, ProtocolError> {
+    match payload.len() {
+        0 => Ok(Message::Close(None, None)),
+        1 => Err(ProtocolError::Protocol("Close payload too short")),
+        _ => {
+            let code = u16::from_be_bytes([payload[0], payload[1]]);
+            if !matches!(code, 1000..=1003 | 1007..=1014 | 3000..=4999) {
+                return Err(ProtocolError::Protocol("Invalid close code"));
+            }
+            let reason =
+                String::from_utf8(payload[2..].to_vec()).map_err(|_| ProtocolError::InvalidUtf8)?;
+            Ok(Message::Close(
+                Some(code),
+                if reason.is_empty() {
+                    None
+                } else {
+                    Some(reason)
+                },
+            ))
+        }
+    }
+}
+
+pub(crate) fn invalid_data(err: ProtocolError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Reader that returns at most `chunk_size` bytes per read
+    struct ChunkedReader<'a> {
+        data: &'a [u8],
+        chunk_size: usize,
+    }
+
+    impl Read for ChunkedReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.data.len().min(self.chunk_size).min(buf.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    enum Expect {
+        Text(&'static str),
+        TextLen(usize),
+        Binary(usize),
+        Ping(&'static [u8]),
+        Close(Option<u16>),
+        Fail(u16),
+    }
+
+    enum Input {
+        Frame(bool, u8, &'static [u8]),
+        Repeat(bool, u8, u8, usize),
+        Raw(&'static [u8]),
+    }
+
+    struct Vector {
+        case: &'static str,
+        max_message_size: usize,
+        input: &'static [Input],
+        expected: &'static [Expect],
+    }
+
+    const MAX: usize = DEFAULT_MAX_MESSAGE_SIZE;
+    const MASK: [u8; 4] = [0x37, 0xfa, 0x21, 0x3d];
+
+    // Test vectors modelled after the Autobahn test suite cases
+    const VECTORS: &[Vector] = &[
+        Vector {
+            case: "1.1.1 empty text",
+            max_message_size: MAX,
+            input: &[Input::Frame(true, OPCODE_TEXT, b"")],
+            expected: &[Expect::Text("")],
+        },
+        Vector {
+            case: "1.1.6 text with 16-bit length",
+            max_message_size: MAX,
+            input: &[Input::Repeat(true, OPCODE_TEXT, b'*', 65535)],
+            expected: &[Expect::TextLen(65535)],
+        },
+        Vector {
+            case: "1.2.8 binary with 64-bit length",
+            max_message_size: MAX,
+            input: &[Input::Repeat(true, OPCODE_BINARY, 0xfe, 65536)],
+            expected: &[Expect::Binary(65536)],
+        },
+        Vector {
+            case: "2.3 ping with binary payload",
+            max_message_size: MAX,
+            input: &[Input::Frame(true, OPCODE_PING, b"\x00\xff\xfe\xfd")],
+            expected: &[Expect::Ping(b"\x00\xff\xfe\xfd")],
+        },
+        Vector {
+            case: "2.5 ping with payload larger than 125 bytes",
+            max_message_size: MAX,
+            input: &[Input::Repeat(true, OPCODE_PING, b'*', 126)],
+            expected: &[Expect::Fail(1002)],
+        },
+        Vector {
+            case: "3.1 reserved bit set",
+            max_message_size: MAX,
+            input: &[Input::Raw(b"\xc1\x80\x00\x00\x00\x00")],
+            expected: &[Expect::Fail(1002)],
+        },
+        Vector {
+            case: "4.1.1 reserved non-control opcode",
+            max_message_size: MAX,
+            input: &[Input::Frame(true, 0x3, b"")],
+            expected: &[Expect::Fail(1002)],
+        },
+        Vector {
+            case: "4.2.1 reserved control opcode",
+            max_message_size: MAX,
+            input: &[Input::Frame(true, 0xB, b"")],
+            expected: &[Expect::Fail(1002)],
+        },
+        Vector {
+            case: "5.1 fragmented ping",
+            max_message_size: MAX,
+            input: &[
+                Input::Frame(false, OPCODE_PING, b"frag"),
+                Input::Frame(true, OPCODE_CONTINUATION, b"ment"),
+            ],
+            expected: &[Expect::Fail(1002)],
+        },
+        Vector {
+            case: "5.3 fragmented text",
+            max_message_size: MAX,
+            input: &[
+                Input::Frame(false, OPCODE_TEXT, b"frag"),
+                Input::Frame(true, OPCODE_CONTINUATION, b"ment"),
+            ],
+            expected: &[Expect::Text("fragment")],
+        },
+        Vector {
+            case: "5.6 fragmented text with ping in between",
+            max_message_size: MAX,
+            input: &[
+                Input::Frame(false, OPCODE_TEXT, b"frag"),
+                Input::Frame(true, OPCODE_PING, b"ping"),
+                Input::Frame(false, OPCODE_CONTINUATION, b"me"),
+                Input::Frame(true, OPCODE_CONTINUATION, b"nt"),
+            ],
+            expected: &[Expect::Ping(b"ping"), Expect::Text("fragment")],
+        },
+        Vector {
+            case: "5.9 continuation without start",
+            max_message_size: MAX,
+            input: &[Input::Frame(true, OPCODE_CONTINUATION, b"fragment")],
+            expected: &[Expect::Fail(1002)],
+        },
+        Vector {
+            case: "5.18 new text while fragmented",
+            max_message_size: MAX,
+            input: &[
+                Input::Frame(false, OPCODE_TEXT, b"frag"),
+                Input::Frame(true, OPCODE_TEXT, b"ment"),
+            ],
+            expected: &[Expect::Fail(1002)],
+        },
+        Vector {
+            case: "6.2.3 valid UTF-8 split in codepoint",
+            max_message_size: MAX,
+            input: &[
+                Input::Frame(false, OPCODE_TEXT, b"\xce\xba\xe1"),
+                Input::Frame(
+                    true,
+                    OPCODE_CONTINUATION,
+                    b"\xbd\xb9\xcf\x83\xce\xbc\xce\xb5",
+                ),
+            ],
+            expected: &[Expect::Text("\u{3ba}\u{1f79}\u{3c3}\u{3bc}\u{3b5}")],
+        },
+        Vector {
+            case: "6.3.1 invalid UTF-8",
+            max_message_size: MAX,
+            input: &[Input::Frame(
+                true,
+                OPCODE_TEXT,
+                b"\xce\xba\xe1\xbd\xb9\xcf\x83\xce\xbc\xce\xb5\xed\xa0\x80",
+            )],
+            expected: &[Expect::Fail(1007)],
+        },
+        Vector {
+            case: "7.1.1 text then close",
+            max_message_size: MAX,
+            input: &[
+                Input::Frame(true, OPCODE_TEXT, b"Hello"),
+                Input::Frame(true, OPCODE_CLOSE, b"\x03\xe8"),
+            ],
+            expected: &[Expect::Text("Hello"), Expect::Close(Some(1000))],
+        },
+        Vector {
+            case: "7.3.1 close without payload",
+            max_message_size: MAX,
+            input: &[Input::Frame(true, OPCODE_CLOSE, b"")],
+            expected: &[Expect::Close(None)],
+        },
+        Vector {
+            case: "7.3.2 close with 1 byte payload",
+            max_message_size: MAX,
+            input: &[Input::Frame(true, OPCODE_CLOSE, b"\x03")],
+            expected: &[Expect::Fail(1002)],
+        },
+        Vector {
+            case: "7.5.1 close reason invalid UTF-8",
+            max_message_size: MAX,
+            input: &[Input::Frame(
+                true,
+                OPCODE_CLOSE,
+                b"\x03\xe8\xce\xba\xe1\xbd",
+            )],
+            expected: &[Expect::Fail(1007)],
+        },
+        Vector {
+            case: "7.9.3 invalid close code",
+            max_message_size: MAX,
+            input: &[Input::Frame(true, OPCODE_CLOSE, b"\x03\xec")],
+            expected: &[Expect::Fail(1002)],
+        },
+        Vector {
+            case: "9.1.6 large text message",
+            max_message_size: MAX,
+            input: &[Input::Repeat(true, OPCODE_TEXT, b'*', 16 * 1024 * 1024)],
+            expected: &[Expect::TextLen(16 * 1024 * 1024)],
+        },
+        Vector {
+            case: "9.2.x message larger than maximum",
+            max_message_size: 1024,
+            input: &[Input::Repeat(true, OPCODE_BINARY, 0xfe, 1025)],
+            expected: &[Expect::Fail(1009)],
+        },
+        Vector {
+            case: "9.2.x fragmented message larger than maximum",
+            max_message_size: 1024,
+            input: &[
+                Input::Repeat(false, OPCODE_BINARY, 0xfe, 1000),
+                Input::Repeat(true, OPCODE_CONTINUATION, 0xfe, 25),
+            ],
+            expected: &[Expect::Fail(1009)],
+        },
+        Vector {
+            case: "unmasked frame from client",
+            max_message_size: MAX,
+            input: &[Input::Raw(b"\x81\x05Hello")],
+            expected: &[Expect::Fail(1002)],
+        },
+    ];
+
+    fn encode_input(input: &[Input]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for input in input {
+            match input {
+                Input::Frame(fin, opcode, payload) => {
+                    data.extend(encode_frame(*fin, *opcode, payload, Some(MASK)))
+                }
+                Input::Repeat(fin, opcode, byte, len) => {
+                    data.extend(encode_frame(*fin, *opcode, &vec![*byte; *len], Some(MASK)))
+                }
+                Input::Raw(raw) => data.extend_from_slice(raw),
+            }
+        }
+        data
+    }
+
+    fn run_vector(vector: &Vector, chunk_size: usize) {
+        let data = encode_input(vector.input);
+        let mut stream = ChunkedReader {
+            data: &data,
+            chunk_size,
+        };
+        let mut reader = MessageReader::new(true);
+        reader.max_message_size = vector.max_message_size;
+
+        for expected in vector.expected {
+            let result = reader.read_message(&mut stream);
+            match (expected, result) {
+                (Expect::Text(expected), Ok(Message::Text(text))) => assert_eq!(text, *expected),
+                (Expect::TextLen(len), Ok(Message::Text(text))) => assert_eq!(text.len(), *len),
+                (Expect::Binary(len), Ok(Message::Binary(data))) => assert_eq!(data.len(), *len),
+                (Expect::Ping(expected), Ok(Message::Ping(data))) => assert_eq!(data, *expected),
+                (Expect::Close(expected), Ok(Message::Close(code, _))) => {
+                    assert_eq!(code, *expected)
+                }
+                (Expect::Fail(expected), Err(err)) => {
+                    let err = err
+                        .get_ref()
+                        .and_then(|err| err.downcast_ref::<ProtocolError>())
+                        .unwrap_or_else(|| panic!("{}: expected protocol error", vector.case));
+                    assert_eq!(err.close_code(), *expected, "{}", vector.case);
+                }
+                (_, result) => panic!("{}: unexpected result {:?}", vector.case, result),
+            }
+        }
+    }
+
+    #[test]
+    fn test_vectors() {
+        for vector in VECTORS {
+            run_vector(vector, 8192);
+        }
+    }
+
+    #[test]
+    fn test_vectors_byte_by_byte() {
+        for vector in VECTORS
+            .iter()
+            .filter(|vector| vector.input.iter().all(|i| !matches!(i, Input::Repeat(..))))
+        {
+            run_vector(vector, 1);
+        }
+    }
+
+    #[test]
+    fn test_multiple_frames_in_one_read() {
+        let data = encode_input(&[
+            Input::Frame(true, OPCODE_TEXT, b"one"),
+            Input::Frame(true, OPCODE_TEXT, b"two"),
+        ]);
+        let mut stream = data.as_slice();
+        let mut reader = MessageReader::new(true);
+        assert_eq!(
+            reader.read_message(&mut stream).unwrap(),
+            Message::Text("one".to_string())
+        );
+        assert_eq!(
+            reader.read_message(&mut stream).unwrap(),
+            Message::Text("two".to_string())
+        );
+    }
+}
@@ -0,0 +1,262 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::collections::HashSet;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use crate::{Message, WebSocketSender};
+
+// MARK: ConnectionId
+/// Id of a connection in a hub
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConnectionId(u64);
+
+// MARK: Hub
+/// Group of WebSocket connections that messages can be broadcast to, closed
+/// connections are removed automatically
+///
+/// Messages are written outside the hub lock so a slow connection doesn't block
+/// other threads from joining, leaving or sending.
+#[derive(Clone, Default)]
+pub struct Hub {
+    inner: Arc<Mutex<HubInner>>,
+}
+
+#[derive(Default)]
+struct HubInner {
+    next_id: u64,
+    connections: Vec<Connection>,
+}
+
+struct Connection {
+    id: ConnectionId,
+    sender: WebSocketSender,
+    rooms: HashSet<String>,
+}
+
+impl Hub {
+    /// Create new empty hub
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add connection to hub
+    pub fn join(&self, sender: WebSocketSender) -> ConnectionId {
+        let mut inner = self.inner.lock().expect("Can't get lock");
+        let id = ConnectionId(inner.next_id);
+        inner.next_id += 1;
+        inner.connections.push(Connection {
+            id,
+            sender,
+            rooms: HashSet::new(),
+        });
+        id
+    }
+
+    /// Remove connection from hub
+    pub fn leave(&self, id: ConnectionId) {
+        self.inner.lock().expect("Can't get lock").remove(&[id]);
+    }
+
+    /// Add connection to a room, returns `false` when the connection is unknown
+    pub fn join_room(&self, id: ConnectionId, room: impl Into<String>) -> bool {
+        let mut inner = self.inner.lock().expect("Can't get lock");
+        match inner
+            .connections
+            .iter_mut()
+            .find(|connection| connection.id == id)
+        {
+            Some(connection) => {
+                connection.rooms.insert(room.into());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove connection from a room
+    pub fn leave_room(&self, id: ConnectionId, room: &str) {
+        let mut inner = self.inner.lock().expect("Can't get lock");
+        if let Some(connection) = inner
+            .connections
+            .iter_mut()
+            .find(|connection| connection.id == id)
+        {
+            connection.rooms.remove(room);
+        }
+    }
+
+    /// Get amount of open connections
+    pub fn len(&self) -> usize {
+        let mut inner = self.inner.lock().expect("Can't get lock");
+        inner.remove_closed();
+        inner.connections.len()
+    }
+
+    /// Returns `true` when there are no open connections
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get amount of open connections in a room
+    pub fn room_len(&self, room: &str) -> usize {
+        let mut inner = self.inner.lock().expect("Can't get lock");
+        inner.remove_closed();
+        inner
+            .connections
+            .iter()
+            .filter(|connection| connection.rooms.contains(room))
+            .count()
+    }
+
+    /// Send message to one connection
+    pub fn send(&self, id: ConnectionId, message: Message) -> io::Result<()> {
+        let mut sender = {
+            let mut inner = self.inner.lock().expect("Can't get lock");
+            inner.remove_closed();
+            inner
+                .connections
+                .iter()
+                .find(|connection| connection.id == id)
+                .map(|connection| connection.sender.clone())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "Unknown connection"))?
+        };
+        let result = sender.send(message);
+        if result.is_err() {
+            self.leave(id);
+        }
+        result
+    }
+
+    /// Send message to all connections
+    pub fn broadcast(&self, message: Message) {
+        self.broadcast_filter(message, |_| true);
+    }
+
+    /// Send message to all connections except one, useful to forward a message to the other peers
+    pub fn broadcast_except(&self, except: ConnectionId, message: Message) {
+        self.broadcast_filter(message, |connection| connection.id != except);
+    }
+
+    /// Send message to all connections in a room
+    pub fn broadcast_room(&self, room: &str, message: Message) {
+        self.broadcast_filter(message, |connection| connection.rooms.contains(room));
+    }
+
+    /// Send message to all connections in a room except one
+    pub fn broadcast_room_except(&self, room: &str, except: ConnectionId, message: Message) {
+        self.broadcast_filter(message, |connection| {
+            connection.id != except && connection.rooms.contains(room)
+        });
+    }
+
+    fn broadcast_filter(&self, message: Message, filter: impl Fn(&Connection) -> bool) {
+        // Snapshot the senders so the lock is not held while writing
+        let senders = {
+            let mut inner = self.inner.lock().expect("Can't get lock");
+            inner.remove_closed();
+            inner
+                .connections
+                .iter()
+                .filter(|connection| filter(connection))
+                .map(|connection| (connection.id, connection.sender.clone()))
+                .collect::<Vec<_>>()
+        };
+        let failed = senders
+            .into_iter()
+            .filter_map(|(id, mut sender)| sender.send(message.clone()).err().map(|_| id))
+            .collect::<Vec<_>>();
+        if !failed.is_empty() {
+            self.inner.lock().expect("Can't get lock").remove(&failed);
+        }
+    }
+}
+
+impl HubInner {
+    fn remove_closed(&mut self) {
+        self.connections
+            .retain(|connection| !connection.sender.is_closed());
+    }
+
+    fn remove(&mut self, ids: &[ConnectionId]) {
+        self.connections
+            .retain(|connection| !ids.contains(&connection.id));
+    }
+}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{read_frame, server_with_raw_client};
+
+    #[test]
+    fn test_broadcast() {
+        let hub = Hub::new();
+        let (a, mut client_a) = server_with_raw_client();
+        let (b, mut client_b) = server_with_raw_client();
+        let a_id = hub.join(a.sender());
+        hub.join(b.sender());
+        assert_eq!(hub.len(), 2);
+
+        hub.broadcast(Message::Text("all".to_string()));
+        assert_eq!(read_frame(&mut client_a).1, b"all");
+        assert_eq!(read_frame(&mut client_b).1, b"all");
+
+        hub.broadcast_except(a_id, Message::Text("others".to_string()));
+        hub.send(a_id, Message::Text("a".to_string())).unwrap();
+        assert_eq!(read_frame(&mut client_a).1, b"a");
+        assert_eq!(read_frame(&mut client_b).1, b"others");
+
+        hub.leave(a_id);
+        assert_eq!(hub.len(), 1);
+        assert!(hub.send(a_id, Message::Text("a".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_rooms() {
+        let hub = Hub::new();
+        let (a, mut client_a) = server_with_raw_client();
+        let (b, mut client_b) = server_with_raw_client();
+        let (c, mut client_c) = server_with_raw_client();
+        let a_id = hub.join(a.sender());
+        let b_id = hub.join(b.sender());
+        let c_id = hub.join(c.sender());
+        assert!(hub.join_room(a_id, "lobby"));
+        assert!(hub.join_room(b_id, "lobby"));
+        assert!(hub.join_room(c_id, "game"));
+        assert_eq!(hub.room_len("lobby"), 2);
+
+        hub.broadcast_room("lobby", Message::Text("lobby".to_string()));
+        hub.broadcast_room("game", Message::Text("game".to_string()));
+        assert_eq!(read_frame(&mut client_a).1, b"lobby");
+        assert_eq!(read_frame(&mut client_b).1, b"lobby");
+        assert_eq!(read_frame(&mut client_c).1, b"game");
+
+        hub.broadcast_room_except("lobby", a_id, Message::Text("from a".to_string()));
+        hub.leave_room(b_id, "lobby");
+        hub.broadcast_room("lobby", Message::Text("only a".to_string()));
+        assert_eq!(read_frame(&mut client_b).1, b"from a");
+        assert_eq!(read_frame(&mut client_a).1, b"only a");
+        assert_eq!(hub.room_len("lobby"), 1);
+
+        hub.leave(a_id);
+        assert_eq!(hub.room_len("lobby"), 0);
+        assert!(!hub.join_room(a_id, "lobby"));
+    }
+
+    #[test]
+    fn test_closed_connections_removed() {
+        let hub = Hub::new();
+        let (mut ws, _client) = server_with_raw_client();
+        hub.join(ws.sender());
+        assert_eq!(hub.len(), 1);
+
+        ws.close(1000, "").unwrap();
+        assert!(hub.is_empty());
+    }
+}
@@ -0,0 +1,395 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+//! JSON-RPC 2.0 ([specification](https://www.jsonrpc.org/specification))
+//!
+//! Batch requests are not supported, they are answered with an Invalid Request error.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::time::{Duration, Instant};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::typed::TypedError;
+use crate::{Message, WebSocket};
+
+// MARK: RpcError
+/// JSON-RPC error object
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RpcError {
+    /// Error code
+    pub code: i64,
+    /// Error message
+    pub message: String,
+    /// Additional error data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    /// Create new error
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// Set additional error data
+    pub fn data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// Invalid JSON was received
+    pub fn parse_error() -> Self {
+        Self::new(-32700, "Parse error")
+    }
+
+    /// The JSON sent is not a valid request object
+    pub fn invalid_request() -> Self {
+        Self::new(-32600, "Invalid Request")
+    }
+
+    /// The method does not exist
+    pub fn method_not_found() -> Self {
+        Self::new(-32601, "Method not found")
+    }
+
+    /// Invalid method parameters
+    pub fn invalid_params() -> Self {
+        Self::new(-32602, "Invalid params")
+    }
+
+    /// Internal error
+    pub fn internal_error() -> Self {
+        Self::new(-32603, "Internal error")
+    }
+}
+
+impl Display for RpcError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)
+    }
+}
+
+impl Error for RpcError {}
+
+// MARK: RpcCall
+/// Incoming JSON-RPC request or notification
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcCall {
+    /// Request id, `None` for notifications
+    pub id: Option<Value>,
+    /// Method name
+    pub method: String,
+    /// Raw params
+    pub params: Value,
+}
+
+impl RpcCall {
+    /// Returns `true` when this call is a notification that expects no response
+    pub fn is_notification(&self) -> bool {
+        self.id.is_none()
+    }
+
+    /// Deserialize params
+    pub fn params<T: DeserializeOwned>(&self) -> Result<T, RpcError> {
+        serde_json::from_value(self.params.clone())
+            .map_err(|err| RpcError::invalid_params().data(Value::String(err.to_string())))
+    }
+}
+
+enum Incoming {
+    Call(RpcCall),
+    Response(Value, Result<Value, RpcError>),
+}
+
+// MARK: JsonRpcSocket
+/// WebSocket channel that speaks JSON-RPC 2.0 in both directions
+pub struct JsonRpcSocket {
+    ws: WebSocket,
+    next_id: u64,
+    queue: VecDeque<RpcCall>,
+    call_timeout: Option<Duration>,
+}
+
+impl JsonRpcSocket {
+    /// Create new JSON-RPC socket
+    pub fn new(ws: WebSocket) -> Self {
+        Self {
+            ws,
+            next_id: 1,
+            queue: VecDeque::new(),
+            call_timeout: None,
+        }
+    }
+
+    /// Get the underlying WebSocket
+    pub fn websocket(&self) -> &WebSocket {
+        &self.ws
+    }
+
+    /// Set maximum time [`JsonRpcSocket::call`] waits for a response, a late response is ignored
+    pub fn set_call_timeout(&mut self, timeout: Duration) {
+        self.call_timeout = Some(timeout);
+    }
+
+    /// Call method on peer and block until its response is received, calls that
+    /// arrive in the meantime are queued for [`JsonRpcSocket::recv`]
+    pub fn call<R: DeserializeOwned>(
+        &mut self,
+        method: &str,
+        params: impl Serialize,
+    ) -> Result<R, TypedError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.write(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        }))?;
+
+        let deadline = self.call_timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            match self.read(deadline)? {
+                Incoming::Call(call) => self.queue.push_back(call),
+                Incoming::Response(response_id, result) if response_id == id => {
+                    return match result {
+                        Ok(result) => Ok(serde_json::from_value(result)?),
+                        Err(err) => Err(TypedError::Rpc(err)),
+                    };
+                }
+                Incoming::Response(..) => {}
+            }
+        }
+    }
+
+    /// Send notification to peer
+    pub fn notify(&mut self, method: &str, params: impl Serialize) -> Result<(), TypedError> {
+        self.write(json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+        }))
+    }
+
+    /// Receive next request or notification from peer
+    pub fn recv(&mut self) -> Result<RpcCall, TypedError> {
+        if let Some(call) = self.queue.pop_front() {
+            return Ok(call);
+        }
+        loop {
+            if let Incoming::Call(call) = self.read(None)? {
+                return Ok(call);
+            }
+        }
+    }
+
+    /// Respond to a request, responses to notifications are not sent
+    pub fn respond(
+        &mut self,
+        call: &RpcCall,
+        result: Result<impl Serialize, RpcError>,
+    ) -> Result<(), TypedError> {
+        let Some(id) = &call.id else {
+            return Ok(());
+        };
+        match result {
+            Ok(result) => self.write(json!({ "jsonrpc": "2.0", "id": id, "result": result })),
+            Err(err) => self.write_error(id.clone(), err),
+        }
+    }
+
+    fn write(&mut self, value: Value) -> Result<(), TypedError> {
+        self.ws
+            .send(Message::Text(value.to_string()))
+            .map_err(TypedError::Io)
+    }
+
+    fn write_error(&mut self, id: Value, err: RpcError) -> Result<(), TypedError> {
+        self.write(json!({ "jsonrpc": "2.0", "id": id, "error": err }))
+    }
+
+    /// Read next valid message, invalid messages are answered with an error response
+    fn read(&mut self, deadline: Option<Instant>) -> Result<Incoming, TypedError> {
+        loop {
+            let message = match deadline {
+                Some(deadline) => self
+                    .ws
+                    .recv_timeout(deadline.saturating_duration_since(Instant::now()))?
+                    .ok_or_else(|| {
+                        TypedError::Io(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            "JSON-RPC call timed out",
+                        ))
+                    })?,
+                None => self.ws.recv()?,
+            };
+            let value = match message {
+                Message::Text(text) => serde_json::from_str::<Value>(&text),
+                Message::Binary(data) => serde_json::from_slice::<Value>(&data),
+                Message::Close(code, reason) => return Err(TypedError::Closed(code, reason)),
+                _ => continue,
+            };
+            let mut object = match value {
+                Ok(Value::Object(object)) => object,
+                Ok(_) => {
+                    self.write_error(Value::Null, RpcError::invalid_request())?;
+                    continue;
+                }
+                Err(_) => {
+                    self.write_error(Value::Null, RpcError::parse_error())?;
+                    continue;
+                }
+            };
+
+            let id = object.remove("id");
+            if object.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+                self.write_error(id.unwrap_or(Value::Null), RpcError::invalid_request())?;
+                continue;
+            }
+            if let Some(Value::String(method)) = object.remove("method") {
+                return Ok(Incoming::Call(RpcCall {
+                    id,
+                    method,
+                    params: object.remove("params").unwrap_or(Value::Null),
+                }));
+            }
+            match (id, object.remove("result"), object.remove("error")) {
+                (Some(id), Some(result), None) => return Ok(Incoming::Response(id, Ok(result))),
+                (Some(id), None, Some(error)) => {
+                    let error = serde_json::from_value(error)
+                        .unwrap_or_else(|_| RpcError::internal_error());
+                    return Ok(Incoming::Response(id, Err(error)));
+                }
+                (id, _, _) => {
+                    self.write_error(id.unwrap_or(Value::Null), RpcError::invalid_request())?
+                }
+            }
+        }
+    }
+}
+
+// MARK: Tests
+#[cfg(test)]
+#[cfg(feature = "client")]
+mod test {
+    use super::*;
+    use crate::test_util::socket_pair;
+
+    #[test]
+    fn test_call_and_notify() {
+        let (server, client) = socket_pair();
+        std::thread::spawn(move || {
+            let mut server = JsonRpcSocket::new(server);
+            while let Ok(call) = server.recv() {
+                match call.method.as_str() {
+                    "add" => {
+                        let result = call.params::<(i64, i64)>().map(|(a, b)| a + b);
+                        server.respond(&call, result).unwrap();
+                    }
+                    "log" => server.notify("logged", call.params.clone()).unwrap(),
+                    _ => server
+                        .respond(&call, Err::<(), _>(RpcError::method_not_found()))
+                        .unwrap(),
+                }
+            }
+        });
+
+        let mut client = JsonRpcSocket::new(client);
+        assert_eq!(client.call::<i64>("add", (1, 2)).unwrap(), 3);
+        assert!(matches!(
+            client.call::<i64>("add", "wrong"),
+            Err(TypedError::Rpc(RpcError { code: -32602, .. }))
+        ));
+
+        // Notifications received while waiting for a response are queued
+        client.notify("log", "Hello").unwrap();
+        assert!(matches!(
+            client.call::<()>("unknown", ()),
+            Err(TypedError::Rpc(RpcError { code: -32601, .. }))
+        ));
+        let call = client.recv().unwrap();
+        assert!(call.is_notification());
+        assert_eq!(call.method, "logged");
+        assert_eq!(call.params, json!("Hello"));
+    }
+
+    #[test]
+    fn test_invalid_messages() {
+        let (server, mut client) = socket_pair();
+        let mut server = JsonRpcSocket::new(server);
+        client.send(Message::Text("not json".to_string())).unwrap();
+        client
+            .send(Message::Text(
+                r#"[{"jsonrpc":"2.0","id":3,"method":"test"}]"#.to_string(),
+            ))
+            .unwrap();
+        client
+            .send(Message::Text(r#"{"id":1,"method":"test"}"#.to_string()))
+            .unwrap();
+        client
+            .send(Message::Text(
+                r#"{"jsonrpc":"2.0","id":2,"method":"test"}"#.to_string(),
+            ))
+            .unwrap();
+        assert_eq!(
+            server.recv().unwrap(),
+            RpcCall {
+                id: Some(json!(2)),
+                method: "test".to_string(),
+                params: Value::Null,
+            }
+        );
+
+        let parse_error: Value = match client.recv().unwrap() {
+            Message::Text(text) => serde_json::from_str(&text).unwrap(),
+            _ => panic!("Expected text message"),
+        };
+        assert_eq!(parse_error["error"]["code"], -32700);
+        let batch: Value = match client.recv().unwrap() {
+            Message::Text(text) => serde_json::from_str(&text).unwrap(),
+            _ => panic!("Expected text message"),
+        };
+        assert_eq!(batch["id"], Value::Null);
+        assert_eq!(batch["error"]["code"], -32600);
+        let invalid_request: Value = match client.recv().unwrap() {
+            Message::Text(text) => serde_json::from_str(&text).unwrap(),
+            _ => panic!("Expected text message"),
+        };
+        assert_eq!(invalid_request["id"], 1);
+        assert_eq!(invalid_request["error"]["code"], -32600);
+    }
+
+    #[test]
+    fn test_call_timeout() {
+        let (server, client) = socket_pair();
+        let mut server = JsonRpcSocket::new(server);
+        let mut client = JsonRpcSocket::new(client);
+        client.set_call_timeout(Duration::from_millis(50));
+        assert!(matches!(
+            client.call::<i64>("slow", ()),
+            Err(TypedError::Io(err)) if err.kind() == io::ErrorKind::TimedOut
+        ));
+
+        // Late response is ignored by the next call
+        let slow = server.recv().unwrap();
+        server.respond(&slow, Ok(1)).unwrap();
+        std::thread::spawn(move || {
+            let call = server.recv().unwrap();
+            server.respond(&call, Ok(2)).unwrap();
+        });
+        assert_eq!(client.call::<i64>("fast", ()).unwrap(), 2);
+    }
+}
@@ -10,17 +10,48 @@
 
 use std::error::Error;
 use std::fmt::{Display, Formatter};
-use std::io::{Read, Write};
-use std::net::TcpStream;
+use std::io::{self, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use base64::Engine;
-use base64::prelude::BASE64_STANDARD;
-use sha1::{Digest, Sha1};
-use small_http::{Request, Response, Status};
+use small_http::{Request, Response};
+
+#[cfg(feature = "client")]
+pub use crate::connect::ConnectBuilder;
+#[cfg(feature = "deflate")]
+pub use crate::deflate::DeflateConfig;
+pub use crate::frame::ProtocolError;
+use crate::frame::{
+    MAX_CONTROL_PAYLOAD_SIZE, MessageReader, OPCODE_BINARY, OPCODE_CLOSE, OPCODE_PING, OPCODE_PONG,
+    OPCODE_TEXT, encode_frame, invalid_data,
+};
+pub use crate::hub::{ConnectionId, Hub};
+#[cfg(feature = "json")]
+pub use crate::jsonrpc::{JsonRpcSocket, RpcCall, RpcError};
+#[cfg(feature = "json")]
+pub use crate::typed::{TypedError, TypedSocket};
+pub use crate::upgrade::UpgradeBuilder;
+
+#[cfg(feature = "deflate")]
+use crate::frame::RSV1;
+
+#[cfg(feature = "client")]
+mod connect;
+#[cfg(feature = "deflate")]
+mod deflate;
+mod frame;
+mod hub;
+#[cfg(feature = "json")]
+mod jsonrpc;
+#[cfg(test)]
+mod test_util;
+#[cfg(feature = "json")]
+mod typed;
+mod upgrade;
 
 /// WebSocket message
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Message {
     /// Text message
     Text(String),
@@ -34,236 +65,448 @@ pub enum Message {
     Close(Option<u16>, Option<String>),
 }
 
+// MARK: WebSocket
 /// WebSocket connection
 #[derive(Clone)]
 pub struct WebSocket {
-    stream: Arc<Mutex<TcpStream>>,
+    sender: WebSocketSender,
+    receiver: WebSocketReceiver,
+    protocol: Option<String>,
 }
 
 impl PartialEq for WebSocket {
     fn eq(&self, other: &Self) -> bool {
-        Arc::ptr_eq(&self.stream, &other.stream)
+        self.sender == other.sender
     }
 }
 impl Eq for WebSocket {}
 
 impl WebSocket {
-    fn new(stream: TcpStream) -> Self {
+    fn new(stream: TcpStream, is_client: bool) -> Self {
+        // Reading and writing use separate handles so sending doesn't wait on a blocking recv
+        let read_stream = stream.try_clone().expect("Can't clone stream");
+        let now = Instant::now();
+        let sender = WebSocketSender {
+            state: Arc::new(Mutex::new(SendState {
+                stream,
+                close_sent: false,
+                #[cfg(feature = "client")]
+                is_client,
+                #[cfg(feature = "deflate")]
+                deflater: None,
+            })),
+        };
+        let receiver = WebSocketReceiver {
+            state: Arc::new(Mutex::new(RecvState {
+                stream: read_stream,
+                // Frames from clients are masked, frames from servers are not
+                reader: MessageReader::new(!is_client),
+                heartbeat: None,
+                last_seen: now,
+                last_ping: now,
+                closed: false,
+            })),
+            sender: sender.clone(),
+        };
         WebSocket {
-            stream: Arc::new(Mutex::new(stream)),
+            sender,
+            receiver,
+            protocol: None,
         }
     }
 
     /// Connect to a WebSocket server
     #[cfg(feature = "client")]
     pub fn connect(url: impl AsRef<str>) -> Result<Self, ConnectError> {
-        let parsed_url = url::Url::parse(url.as_ref()).map_err(|_| ConnectError)?;
-        let mut stream = TcpStream::connect(format!(
-            "{}:{}",
-            parsed_url.host().expect("URL should have a host"),
-            parsed_url.port().unwrap_or(80)
-        ))
-        .map_err(|_| ConnectError)?;
-
-        let mut random_key = [0u8; 16];
-        getrandom::fill(&mut random_key).expect("Can't generate random key");
-        let random_key = BASE64_STANDARD.encode(random_key);
-        let req = Request::get(url.as_ref())
-            .header("Upgrade", "websocket")
-            .header("Connection", "Upgrade")
-            .header("Sec-WebSocket-Version", "13")
-            .header("Sec-WebSocket-Key", &random_key);
-        req.write_to_stream(&mut stream, false);
-
-        let res = Response::read_from_stream(&mut stream).map_err(|_| ConnectError)?;
-        if res.status != Status::SwitchingProtocols {
-            return Err(ConnectError);
-        }
-        let websocket_accept = res
-            .headers
-            .get("Sec-WebSocket-Accept")
-            .ok_or(ConnectError)?;
-        let mut sha1 = Sha1::new();
-        sha1.update(random_key.as_bytes());
-        sha1.update(b"258EAFA5-E914-47DA-95CA-C5AB0DC85B11");
-        let expected_accept = BASE64_STANDARD.encode(sha1.finalize());
-        if *websocket_accept != expected_accept {
-            eprintln!(
-                "WebSocket connection failed: expected Sec-WebSocket-Accept header to be '{}', got '{}'",
-                expected_accept, websocket_accept
-            );
-            return Err(ConnectError);
-        }
+        ConnectBuilder::new(url.as_ref()).connect()
+    }
 
-        Ok(WebSocket::new(stream))
+    /// Enable permessage-deflate compression with negotiated parameters
+    #[cfg(feature = "deflate")]
+    fn set_deflate(&self, negotiated: deflate::Negotiated, is_client: bool) {
+        let (deflater, inflater) = negotiated.into_codecs(is_client);
+        self.sender.state.lock().expect("Can't get lock").deflater = Some(deflater);
+        self.receiver
+            .state
+            .lock()
+            .expect("Can't get lock")
+            .reader
+            .set_inflater(inflater);
+    }
+
+    /// Get the negotiated subprotocol
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+
+    /// Split into independent send and receive halves
+    pub fn split(self) -> (WebSocketSender, WebSocketReceiver) {
+        (self.sender, self.receiver)
+    }
+
+    /// Get a send half of this connection
+    pub fn sender(&self) -> WebSocketSender {
+        self.sender.clone()
     }
 
     /// Get the underlying TCP stream peer address
-    pub fn peer_addr(&self) -> std::io::Result<std::net::SocketAddr> {
-        self.stream.lock().expect("Can't get lock").peer_addr()
-    }
-
-    /// Receive WebSocket message
-    pub fn recv(&mut self) -> std::io::Result<Message> {
-        let mut stream = self.stream.lock().expect("Can't get lock");
-        let mut buf = [0; 1024];
-        match stream.read(&mut buf) {
-            Ok(0) => Ok(Message::Close(None, Some("Connection closed".to_string()))),
-            Ok(_) => Self::parse_message(&buf).ok_or_else(|| {
-                std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid WebSocket frame")
-            }),
-            Err(e) => Err(e),
-        }
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.sender.peer_addr()
+    }
+
+    /// Set maximum size of a received message, larger messages fail the connection
+    pub fn set_max_message_size(&self, max_message_size: usize) {
+        self.receiver.set_max_message_size(max_message_size);
+    }
+
+    /// Enable heartbeat, pings the peer after `interval` of silence and fails the
+    /// connection when nothing is received within `timeout` after that
+    pub fn set_heartbeat(&self, interval: Duration, timeout: Duration) {
+        self.receiver.set_heartbeat(interval, timeout);
+    }
+
+    /// Receive WebSocket message, blocks until a message is received
+    ///
+    /// Pings are answered automatically and pongs are consumed, so only data and close
+    /// messages are returned. When a close message is returned the close handshake is done.
+    pub fn recv(&mut self) -> io::Result<Message> {
+        self.receiver.recv()
+    }
+
+    /// Receive WebSocket message, returns `None` when no message is received within `timeout`
+    pub fn recv_timeout(&mut self, timeout: Duration) -> io::Result<Option<Message>> {
+        self.receiver.recv_timeout(timeout)
     }
 
     /// Receive WebSocket message without blocking
-    pub fn recv_non_blocking(&mut self) -> std::io::Result<Option<Message>> {
-        let mut stream = self.stream.lock().expect("Can't get lock");
-        stream.set_nonblocking(true)?;
-        let mut buf = [0; 1024];
-        match stream.read(&mut buf) {
-            Ok(0) => Ok(Some(Message::Close(
-                None,
-                Some("Connection closed".to_string()),
-            ))),
-            Ok(_) => Self::parse_message(&buf).map(Some).ok_or_else(|| {
-                std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid WebSocket frame")
-            }),
-            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => Ok(None),
-            Err(e) => Err(e),
-        }
+    pub fn recv_non_blocking(&mut self) -> io::Result<Option<Message>> {
+        self.receiver.recv_non_blocking()
     }
 
-    fn parse_message(buf: &[u8]) -> Option<Message> {
-        // Parse WebSocket frame
-        let opcode = buf[0] & 0x0F;
-        let masked = (buf[1] & 0x80) != 0;
-        let payload_len = (buf[1] & 0x7F) as usize;
+    /// Write a WebSocket message, sending a close message starts the close handshake
+    ///
+    /// Ping and pong payloads can be at most 125 bytes and close reasons are truncated
+    /// to 123 bytes, because larger control frames fail the connection.
+    pub fn send(&mut self, message: Message) -> io::Result<()> {
+        self.sender.send(message)
+    }
 
-        // Handle payload length
-        let (payload_offset, payload_len) = match payload_len {
-            126 => {
-                let len = u16::from_be_bytes([buf[2], buf[3]]) as usize;
-                (4, len)
-            }
-            127 => {
-                let len = u64::from_be_bytes([
-                    buf[2], buf[3], buf[4], buf[5], buf[6], buf[7], buf[8], buf[9],
-                ]) as usize;
-                (10, len)
-            }
-            len => (2, len),
-        };
+    /// Start close handshake with status code and reason, the peer's close message is
+    /// returned by a next receive
+    pub fn close(&mut self, code: u16, reason: impl AsRef<str>) -> io::Result<()> {
+        self.sender.close(code, reason)
+    }
+
+    /// Returns `true` when the connection is closed or closing
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+}
+
+// MARK: WebSocketSender
+/// Send half of a WebSocket connection
+#[derive(Clone)]
+pub struct WebSocketSender {
+    state: Arc<Mutex<SendState>>,
+}
+
+struct SendState {
+    stream: TcpStream,
+    close_sent: bool,
+    #[cfg(feature = "client")]
+    is_client: bool,
+    #[cfg(feature = "deflate")]
+    deflater: Option<deflate::Deflater>,
+}
+
+impl PartialEq for WebSocketSender {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.state, &other.state)
+    }
+}
+impl Eq for WebSocketSender {}
 
-        // Get masking key if present
-        let (mask_offset, mask) = if masked {
-            (
-                payload_offset + 4,
-                [
-                    buf[payload_offset],
-                    buf[payload_offset + 1],
-                    buf[payload_offset + 2],
-                    buf[payload_offset + 3],
-                ],
-            )
-        } else {
-            (payload_offset, [0; 4])
+impl WebSocketSender {
+    /// Get the underlying TCP stream peer address
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.state
+            .lock()
+            .expect("Can't get lock")
+            .stream
+            .peer_addr()
+    }
+
+    /// Write a WebSocket message, sending a close message starts the close handshake
+    pub fn send(&mut self, message: Message) -> io::Result<()> {
+        let (opcode, payload) = match message {
+            Message::Text(text) => (OPCODE_TEXT, text.into_bytes()),
+            Message::Binary(data) => (OPCODE_BINARY, data),
+            Message::Ping(data) => (OPCODE_PING, data),
+            Message::Pong(data) => (OPCODE_PONG, data),
+            Message::Close(code, reason) => (OPCODE_CLOSE, close_payload(code, reason.as_deref())),
         };
+        if matches!(opcode, OPCODE_PING | OPCODE_PONG) && payload.len() > MAX_CONTROL_PAYLOAD_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Control frame payload too large",
+            ));
+        }
+        let mut state = self.state.lock().expect("Can't get lock");
+        if state.close_sent {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "WebSocket is closing",
+            ));
+        }
+        state.write(opcode, &payload)
+    }
+
+    /// Start close handshake with status code and reason, the peer's close message is
+    /// returned by a next receive
+    pub fn close(&mut self, code: u16, reason: impl AsRef<str>) -> io::Result<()> {
+        self.send(Message::Close(
+            Some(code),
+            Some(reason.as_ref().to_string()),
+        ))
+    }
+
+    /// Returns `true` when the connection is closed or closing
+    pub fn is_closed(&self) -> bool {
+        self.state.lock().expect("Can't get lock").close_sent
+    }
+}
 
-        // Unmask and collect payload
-        let mut payload = Vec::with_capacity(payload_len);
-        for i in 0..payload_len {
-            let byte = buf[mask_offset + i];
-            payload.push(if masked { byte ^ mask[i % 4] } else { byte });
+impl SendState {
+    fn write(&mut self, opcode: u8, payload: &[u8]) -> io::Result<()> {
+        if opcode == OPCODE_CLOSE {
+            self.close_sent = true;
         }
 
-        // Return appropriate message type
-        match opcode {
-            0x1 => Some(Message::Text(
-                String::from_utf8_lossy(&payload).into_owned(),
-            )),
-            0x2 => Some(Message::Binary(payload)),
-            0x8 => {
-                let code = if payload.len() >= 2 {
-                    Some(u16::from_be_bytes([payload[0], payload[1]]))
-                } else {
-                    None
-                };
-                let reason = if payload.len() > 2 {
-                    Some(String::from_utf8_lossy(&payload[2..]).into_owned())
-                } else {
-                    None
-                };
-                Some(Message::Close(code, reason))
-            }
-            0x9 => Some(Message::Ping(payload)),
-            0xA => Some(Message::Pong(payload)),
-            _ => None,
+        // Compress data messages when permessage-deflate is negotiated
+        #[cfg(feature = "deflate")]
+        if let (OPCODE_TEXT | OPCODE_BINARY, Some(deflater)) = (opcode, &mut self.deflater) {
+            let payload = deflater.deflate(payload);
+            let mut frame = encode_frame(true, opcode, &payload, self.mask());
+            frame[0] |= RSV1;
+            return self.stream.write_all(&frame);
+        }
+
+        let frame = encode_frame(true, opcode, payload, self.mask());
+        self.stream.write_all(&frame)
+    }
+
+    /// Frames send by clients must be masked with a random key
+    fn mask(&self) -> Option<[u8; 4]> {
+        #[cfg(feature = "client")]
+        if self.is_client {
+            let mut mask = [0; 4];
+            getrandom::fill(&mut mask).expect("Can't generate random mask");
+            return Some(mask);
         }
+        None
     }
+}
 
-    /// Write a WebSocket message
-    pub fn send(&mut self, message: Message) -> std::io::Result<()> {
-        let mut frame = Vec::new();
-        match message {
-            Message::Text(text) => {
-                frame.push(0x81); // Text frame
-                let payload = text.into_bytes();
-                self.write_frame(&mut frame, &payload)?;
+fn close_payload(code: Option<u16>, reason: Option<&str>) -> Vec<u8> {
+    let mut payload = Vec::new();
+    if let Some(code) = code {
+        payload.extend_from_slice(&code.to_be_bytes());
+        if let Some(reason) = reason {
+            // Truncate reason at a char boundary so the close frame fits
+            let mut len = reason.len().min(MAX_CONTROL_PAYLOAD_SIZE - 2);
+            while !reason.is_char_boundary(len) {
+                len -= 1;
             }
-            Message::Binary(data) => {
-                frame.push(0x82); // Binary frame
-                self.write_frame(&mut frame, &data)?;
+            payload.extend_from_slice(&reason.as_bytes()[..len]);
+        }
+    }
+    payload
+}
+
+// MARK: WebSocketReceiver
+/// Receive half of a WebSocket connection
+#[derive(Clone)]
+pub struct WebSocketReceiver {
+    state: Arc<Mutex<RecvState>>,
+    sender: WebSocketSender,
+}
+
+struct RecvState {
+    stream: TcpStream,
+    reader: MessageReader,
+    heartbeat: Option<(Duration, Duration)>,
+    last_seen: Instant,
+    last_ping: Instant,
+    closed: bool,
+}
+
+impl WebSocketReceiver {
+    /// Set maximum size of a received message, larger messages fail the connection
+    pub fn set_max_message_size(&self, max_message_size: usize) {
+        self.state
+            .lock()
+            .expect("Can't get lock")
+            .reader
+            .max_message_size = max_message_size;
+    }
+
+    /// Enable heartbeat, pings the peer after `interval` of silence and fails the
+    /// connection when nothing is received within `timeout` after that
+    pub fn set_heartbeat(&self, interval: Duration, timeout: Duration) {
+        self.state.lock().expect("Can't get lock").heartbeat = Some((interval, timeout));
+    }
+
+    /// Receive WebSocket message, blocks until a message is received
+    ///
+    /// Pings are answered automatically and pongs are consumed, so only data and close
+    /// messages are returned. When a close message is returned the close handshake is done.
+    pub fn recv(&mut self) -> io::Result<Message> {
+        self.recv_until(None)
+            .map(|message| message.expect("Should be some without deadline"))
+    }
+
+    /// Receive WebSocket message, returns `None` when no message is received within `timeout`
+    pub fn recv_timeout(&mut self, timeout: Duration) -> io::Result<Option<Message>> {
+        self.recv_until(Some(Instant::now() + timeout))
+    }
+
+    /// Receive WebSocket message without blocking
+    pub fn recv_non_blocking(&mut self) -> io::Result<Option<Message>> {
+        self.recv_until(Some(Instant::now()))
+    }
+
+    fn recv_until(&self, deadline: Option<Instant>) -> io::Result<Option<Message>> {
+        let mut state = self.state.lock().expect("Can't get lock");
+        if state.closed {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "WebSocket is closed",
+            ));
+        }
+        let result = self.recv_inner(&mut state, deadline);
+        if let Err(err) = &result {
+            let mut sender = self.sender.state.lock().expect("Can't get lock");
+            // Send close frame with the matching status code when the peer violated the protocol
+            if let Some(protocol_error) = err
+                .get_ref()
+                .and_then(|err| err.downcast_ref::<ProtocolError>())
+            {
+                _ = sender.write(
+                    OPCODE_CLOSE,
+                    &close_payload(
+                        Some(protocol_error.close_code()),
+                        Some(&protocol_error.to_string()),
+                    ),
+                );
             }
-            Message::Ping(data) => {
-                frame.push(0x89); // Ping frame
-                self.write_frame(&mut frame, &data)?;
+            sender.close_sent = true;
+            state.closed = true;
+            _ = state.stream.shutdown(Shutdown::Both);
+        }
+        result
+    }
+
+    fn recv_inner(
+        &self,
+        state: &mut RecvState,
+        deadline: Option<Instant>,
+    ) -> io::Result<Option<Message>> {
+        loop {
+            if let Some(message) = state.reader.next_message().map_err(invalid_data)? {
+                match message {
+                    Message::Ping(data) => {
+                        let mut sender = self.sender.state.lock().expect("Can't get lock");
+                        if !sender.close_sent {
+                            sender.write(OPCODE_PONG, &data)?;
+                        }
+                    }
+                    Message::Pong(_) => {}
+                    Message::Close(code, reason) => {
+                        // Echo close frame when peer started the close handshake
+                        let mut sender = self.sender.state.lock().expect("Can't get lock");
+                        if !sender.close_sent {
+                            _ = sender.write(OPCODE_CLOSE, &close_payload(code, None));
+                        }
+                        state.closed = true;
+                        _ = state.stream.shutdown(Shutdown::Both);
+                        return Ok(Some(Message::Close(code, reason)));
+                    }
+                    message => return Ok(Some(message)),
+                }
+                continue;
             }
-            Message::Pong(data) => {
-                frame.push(0x8A); // Pong frame
-                self.write_frame(&mut frame, &data)?;
+
+            // Send heartbeat ping or detect dead peer
+            let now = Instant::now();
+            let mut wait = deadline.map(|deadline| deadline.saturating_duration_since(now));
+            if let Some((interval, timeout)) = state.heartbeat {
+                let idle = now.duration_since(state.last_seen);
+                if idle >= interval + timeout {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "Peer didn't respond to heartbeat",
+                    ));
+                }
+                if idle >= interval && now.duration_since(state.last_ping) >= interval {
+                    self.sender
+                        .state
+                        .lock()
+                        .expect("Can't get lock")
+                        .write(OPCODE_PING, &[])?;
+                    state.last_ping = now;
+                }
+                let next = if idle >= interval {
+                    interval + timeout - idle
+                } else {
+                    interval - idle
+                };
+                wait = Some(wait.map_or(next, |wait| wait.min(next)));
             }
-            Message::Close(code, reason) => {
-                frame.push(0x88); // Close frame
-                let mut payload = Vec::new();
-                if let Some(c) = code {
-                    payload.extend_from_slice(&c.to_be_bytes());
+
+            // Read more data from stream
+            state
+                .stream
+                .set_read_timeout(wait.map(|wait| wait.max(Duration::from_micros(1))))?;
+            match state.reader.fill(&mut state.stream) {
+                Ok(true) => state.last_seen = Instant::now(),
+                Ok(false) => {
+                    self.sender.state.lock().expect("Can't get lock").close_sent = true;
+                    state.closed = true;
+                    return Ok(Some(Message::Close(
+                        None,
+                        Some("Connection closed".to_string()),
+                    )));
                 }
-                if let Some(r) = reason {
-                    payload.extend_from_slice(r.as_bytes());
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                        return Ok(None);
+                    }
                 }
-                self.write_frame(&mut frame, &payload)?;
+                Err(err) => return Err(err),
             }
         }
-        self.stream
-            .lock()
-            .expect("Can't get lock")
-            .write_all(&frame)
-    }
-
-    fn write_frame(&self, frame: &mut Vec<u8>, payload: &[u8]) -> std::io::Result<()> {
-        let payload_len = payload.len();
-        if payload_len <= 125 {
-            frame.push(payload_len as u8);
-        } else if payload_len <= 65535 {
-            frame.push(126);
-            frame.extend_from_slice(&(payload_len as u16).to_be_bytes());
-        } else {
-            frame.push(127);
-            frame.extend_from_slice(&(payload_len as u64).to_be_bytes());
-        }
-        frame.extend_from_slice(payload);
-        Ok(())
     }
 }
 
 /// ConnectError
 #[derive(Debug)]
-pub struct ConnectError;
+pub struct ConnectError(String);
+
+impl ConnectError {
+    #[cfg(feature = "client")]
+    pub(crate) fn new(message: impl Into<String>) -> Self {
+        Self(message.into())
+    }
+}
 
 impl Display for ConnectError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Connect error")
+        write!(f, "Connect error: {}", self.0)
     }
 }
 
@@ -271,30 +514,20 @@ impl Error for ConnectError {}
 
 /// Upgrade HTTP request to WebSocket connection
 pub fn upgrade(request: &Request, handler: impl FnOnce(WebSocket) + Send + 'static) -> Response {
-    let mut res = Response::with_status(Status::SwitchingProtocols)
-        .header("Upgrade", "websocket")
-        .header("Connection", "Upgrade");
-    if let Some(key) = request.headers.get("Sec-WebSocket-Key") {
-        let mut hasher = Sha1::new();
-        hasher.update(key.as_bytes());
-        hasher.update(b"258EAFA5-E914-47DA-95CA-C5AB0DC85B11");
-        res = res.header(
-            "Sec-WebSocket-Accept",
-            BASE64_STANDARD.encode(hasher.finalize()),
-        );
-    }
-    res = res.takeover(|stream| handler(WebSocket::new(stream)));
-    res
+    UpgradeBuilder::new().upgrade(request, handler)
 }
 
 // MARK: Tests
 #[cfg(test)]
 mod test {
+    use std::io::Read;
     use std::net::{Ipv4Addr, TcpListener};
 
     use super::*;
+    use crate::test_util::{read_frame, server_with_raw_client, write_frame};
 
     #[test]
+    #[cfg(feature = "client")]
     fn test_websocket_server_client() {
         // Create WebSocket server
         let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
@@ -303,8 +536,10 @@ mod test {
             small_http::serve(listener, |req| {
                 upgrade(req, |mut ws| {
                     loop {
-                        if let Message::Text(text) = ws.recv().expect("Failed to receive message") {
-                            ws.send(Message::Text(text)).unwrap();
+                        match ws.recv().expect("Failed to receive message") {
+                            Message::Text(text) => ws.send(Message::Text(text)).unwrap(),
+                            Message::Close(_, _) => break,
+                            _ => {}
                         }
                     }
                 })
@@ -318,4 +553,212 @@ mod test {
             assert_eq!(text, "Hello")
         }
     }
+
+    #[test]
+    fn test_auto_pong() {
+        let (mut ws, mut client) = server_with_raw_client();
+        write_frame(&mut client, OPCODE_PING, b"ping");
+        write_frame(&mut client, OPCODE_TEXT, b"Hello");
+        assert_eq!(ws.recv().unwrap(), Message::Text("Hello".to_string()));
+        assert_eq!(read_frame(&mut client), (OPCODE_PONG, b"ping".to_vec()));
+    }
+
+    #[test]
+    fn test_close_handshake_by_peer() {
+        let (mut ws, mut client) = server_with_raw_client();
+        write_frame(&mut client, OPCODE_CLOSE, b"\x03\xe9bye");
+        assert_eq!(
+            ws.recv().unwrap(),
+            Message::Close(Some(1001), Some("bye".to_string()))
+        );
+        assert_eq!(
+            read_frame(&mut client),
+            (OPCODE_CLOSE, b"\x03\xe9".to_vec())
+        );
+        assert!(ws.send(Message::Text("Hello".to_string())).is_err());
+        assert!(ws.recv().is_err());
+    }
+
+    #[test]
+    fn test_close_handshake_by_server() {
+        let (mut ws, mut client) = server_with_raw_client();
+        ws.close(1000, "done").unwrap();
+        assert_eq!(
+            read_frame(&mut client),
+            (OPCODE_CLOSE, b"\x03\xe8done".to_vec())
+        );
+        assert!(ws.send(Message::Text("Hello".to_string())).is_err());
+
+        write_frame(&mut client, OPCODE_CLOSE, b"\x03\xe8");
+        assert_eq!(ws.recv().unwrap(), Message::Close(Some(1000), None));
+        let mut buf = [0; 16];
+        assert_eq!(client.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_protocol_error_closes_connection() {
+        let (mut ws, mut client) = server_with_raw_client();
+        client.write_all(b"\x81\x05Hello").unwrap();
+        let err = ws.recv().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let (opcode, payload) = read_frame(&mut client);
+        assert_eq!(opcode, OPCODE_CLOSE);
+        assert_eq!(&payload[..2], b"\x03\xea");
+    }
+
+    #[test]
+    fn test_recv_timeout() {
+        let (mut ws, mut client) = server_with_raw_client();
+        assert_eq!(ws.recv_timeout(Duration::from_millis(20)).unwrap(), None);
+        assert_eq!(ws.recv_non_blocking().unwrap(), None);
+
+        write_frame(&mut client, OPCODE_TEXT, b"Hello");
+        assert_eq!(
+            ws.recv_timeout(Duration::from_secs(5)).unwrap(),
+            Some(Message::Text("Hello".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_control_frame_size() {
+        let (mut ws, mut client) = server_with_raw_client();
+        for message in [Message::Ping(vec![0; 126]), Message::Pong(vec![0; 126])] {
+            let err = ws.send(message).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+        ws.send(Message::Ping(vec![0; 125])).unwrap();
+        assert_eq!(read_frame(&mut client), (OPCODE_PING, vec![0; 125]));
+
+        // Close reason is truncated at a char boundary
+        ws.close(1000, "é".repeat(100)).unwrap();
+        let (opcode, payload) = read_frame(&mut client);
+        assert_eq!(opcode, OPCODE_CLOSE);
+        assert_eq!(payload.len(), 2 + 122);
+        assert_eq!(&payload[2..], "é".repeat(61).as_bytes());
+    }
+
+    #[test]
+    fn test_heartbeat() {
+        let interval = Duration::from_millis(50);
+        let timeout = Duration::from_millis(500);
+        let (mut ws, mut client) = server_with_raw_client();
+        ws.set_heartbeat(interval, timeout);
+        let handle = std::thread::spawn(move || {
+            let result = ws.recv();
+            (result, Instant::now())
+        });
+
+        // Answer the first ping, which extends the deadline of the peer
+        let (opcode, payload) = read_frame(&mut client);
+        assert_eq!(opcode, OPCODE_PING);
+        let pong_sent = Instant::now();
+        write_frame(&mut client, OPCODE_PONG, &payload);
+
+        // Stop answering, the peer is detected as dead after interval + timeout of silence
+        let (result, failed_at) = handle.join().unwrap();
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::TimedOut);
+        let elapsed = failed_at.duration_since(pong_sent);
+        assert!(elapsed >= interval + timeout, "{:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(30), "{:?}", elapsed);
+    }
+
+    #[test]
+    fn test_split_send_while_receiving() {
+        let (ws, mut client) = server_with_raw_client();
+        let (mut sender, mut receiver) = ws.split();
+        let handle = std::thread::spawn(move || receiver.recv());
+
+        // Send while the receive half is blocked in recv
+        sender.send(Message::Text("Hello".to_string())).unwrap();
+        assert_eq!(read_frame(&mut client), (OPCODE_TEXT, b"Hello".to_vec()));
+
+        write_frame(&mut client, OPCODE_TEXT, b"World");
+        assert_eq!(
+            handle.join().unwrap().unwrap(),
+            Message::Text("World".to_string())
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "client", feature = "deflate"))]
+    fn test_websocket_deflate() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            small_http::serve(listener, |req| {
+                assert!(req.headers.get("Sec-WebSocket-Extensions").is_some());
+                upgrade(req, |mut ws| {
+                    while let Ok(Message::Text(text)) = ws.recv() {
+                        ws.send(Message::Text(text)).unwrap();
+                    }
+                })
+            });
+        });
+
+        let mut ws = WebSocket::connect(format!("ws://{}:{}/", addr.ip(), addr.port())).unwrap();
+        for i in 0..3 {
+            let text = format!("{{\"type\":\"setColor\",\"color\":{}}}", i).repeat(100);
+            ws.send(Message::Text(text.clone())).unwrap();
+            assert_eq!(ws.recv().unwrap(), Message::Text(text));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "client")]
+    fn test_connect_builder_protocol() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            small_http::serve(listener, |req| {
+                UpgradeBuilder::new()
+                    .protocol("chat.v2")
+                    .validate(|req| match req.headers.get("X-Token") {
+                        Some(token) if token == "secret" => Ok(()),
+                        _ => Err(Response::with_status(small_http::Status::Unauthorized)),
+                    })
+                    .upgrade(req, |mut ws| {
+                        let protocol = ws.protocol().unwrap_or_default().to_string();
+                        ws.send(Message::Text(protocol)).unwrap();
+                    })
+            });
+        });
+
+        let url = format!("ws://{}:{}/", addr.ip(), addr.port());
+        let err = ConnectBuilder::new(&url).connect().err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Connect error: expected status 101 Switching Protocols, got 401 Unauthorized"
+        );
+
+        let mut ws = ConnectBuilder::new(&url)
+            .header("X-Token", "secret")
+            .protocol("chat.v3")
+            .protocol("chat.v2")
+            .connect_timeout(Duration::from_secs(5))
+            .handshake_timeout(Duration::from_secs(5))
+            .connect()
+            .unwrap();
+        assert_eq!(ws.protocol(), Some("chat.v2"));
+        assert_eq!(ws.recv().unwrap(), Message::Text("chat.v2".to_string()));
+    }
+
+    #[test]
+    #[cfg(feature = "client")]
+    fn test_connect_handshake_too_large() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.write_all(b"HTTP/1.1 101 Switching Protocols\r\n");
+            let _ = stream.write_all(format!("X-Junk: {}\r\n", "a".repeat(16 * 1024)).as_bytes());
+        });
+
+        let err = WebSocket::connect(format!("ws://{}:{}/", addr.ip(), addr.port()))
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Connect error: handshake response is too large"
+        );
+    }
 }
@@ -0,0 +1,51 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+//! Shared helpers for the unit tests
+
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+
+use crate::WebSocket;
+use crate::frame::{FrameDecoder, encode_frame};
+
+/// Create server WebSocket connected to a raw client stream
+pub(crate) fn server_with_raw_client() -> (WebSocket, TcpStream) {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (server, _) = listener.accept().unwrap();
+    (WebSocket::new(server, false), client)
+}
+
+/// Write a masked frame like a client does
+pub(crate) fn write_frame(client: &mut TcpStream, opcode: u8, payload: &[u8]) {
+    client
+        .write_all(&encode_frame(true, opcode, payload, Some([1, 2, 3, 4])))
+        .unwrap();
+}
+
+/// Read an unmasked frame from the server
+pub(crate) fn read_frame(client: &mut TcpStream) -> (u8, Vec<u8>) {
+    let mut decoder = FrameDecoder::new(false);
+    loop {
+        if let Some(frame) = decoder.decode(usize::MAX).unwrap() {
+            return (frame.opcode, frame.payload);
+        }
+        let mut buf = [0; 1024];
+        let n = client.read(&mut buf).unwrap();
+        assert!(n > 0, "Connection closed");
+        decoder.extend(&buf[..n]);
+    }
+}
+
+/// Create connected server and client WebSockets
+#[cfg(all(feature = "client", feature = "json"))]
+pub(crate) fn socket_pair() -> (WebSocket, WebSocket) {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (server, _) = listener.accept().unwrap();
+    (WebSocket::new(server, false), WebSocket::new(client, true))
+}
@@ -0,0 +1,173 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::jsonrpc::RpcError;
+use crate::{Message, WebSocket};
+
+// MARK: TypedError
+/// Typed socket error
+#[derive(Debug)]
+pub enum TypedError {
+    /// WebSocket IO error
+    Io(io::Error),
+    /// Message could not be (de)serialized, the connection stays open
+    Json(serde_json::Error),
+    /// Connection is closed
+    Closed(Option<u16>, Option<String>),
+    /// Peer responded with a JSON-RPC error
+    Rpc(RpcError),
+}
+
+impl Display for TypedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TypedError::Io(err) => write!(f, "IO error: {}", err),
+            TypedError::Json(err) => write!(f, "JSON error: {}", err),
+            TypedError::Closed(..) => write!(f, "Connection closed"),
+            TypedError::Rpc(err) => write!(f, "JSON-RPC error: {}", err),
+        }
+    }
+}
+
+impl Error for TypedError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TypedError::Io(err) => Some(err),
+            TypedError::Json(err) => Some(err),
+            TypedError::Rpc(err) => Some(err),
+            TypedError::Closed(..) => None,
+        }
+    }
+}
+
+impl From<io::Error> for TypedError {
+    fn from(err: io::Error) -> Self {
+        TypedError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for TypedError {
+    fn from(err: serde_json::Error) -> Self {
+        TypedError::Json(err)
+    }
+}
+
+// MARK: TypedSocket
+/// WebSocket channel that sends and receives JSON serialized messages
+pub struct TypedSocket<In, Out> {
+    ws: WebSocket,
+    _marker: PhantomData<fn(Out) -> In>,
+}
+
+impl<In: DeserializeOwned, Out: Serialize> TypedSocket<In, Out> {
+    /// Create new typed socket
+    pub fn new(ws: WebSocket) -> Self {
+        Self {
+            ws,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Get the underlying WebSocket
+    pub fn websocket(&self) -> &WebSocket {
+        &self.ws
+    }
+
+    /// Convert into the underlying WebSocket
+    pub fn into_inner(self) -> WebSocket {
+        self.ws
+    }
+
+    /// Receive message, blocks until a message is received
+    pub fn recv(&mut self) -> Result<In, TypedError> {
+        loop {
+            if let Some(message) = decode(self.ws.recv()?)? {
+                return Ok(message);
+            }
+        }
+    }
+
+    /// Receive message, returns `None` when no message is received within `timeout`
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<Option<In>, TypedError> {
+        match self.ws.recv_timeout(timeout)? {
+            Some(message) => decode(message),
+            None => Ok(None),
+        }
+    }
+
+    /// Send message
+    pub fn send(&mut self, message: &Out) -> Result<(), TypedError> {
+        self.ws
+            .send(Message::Text(serde_json::to_string(message)?))
+            .map_err(TypedError::Io)
+    }
+}
+
+fn decode<T: DeserializeOwned>(message: Message) -> Result<Option<T>, TypedError> {
+    match message {
+        Message::Text(text) => Ok(Some(serde_json::from_str(&text)?)),
+        Message::Binary(data) => Ok(Some(serde_json::from_slice(&data)?)),
+        Message::Close(code, reason) => Err(TypedError::Closed(code, reason)),
+        _ => Ok(None),
+    }
+}
+
+// MARK: Tests
+#[cfg(test)]
+#[cfg(feature = "client")]
+mod test {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::test_util::socket_pair;
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    #[serde(tag = "type", rename_all = "camelCase")]
+    enum IpcMessage {
+        GetState,
+        SetColor { color: u32 },
+    }
+
+    #[test]
+    fn test_typed_socket() {
+        let (server, client) = socket_pair();
+        let mut server = TypedSocket::<IpcMessage, IpcMessage>::new(server);
+        let mut client = TypedSocket::<IpcMessage, IpcMessage>::new(client);
+
+        client
+            .send(&IpcMessage::SetColor { color: 0xff0000 })
+            .unwrap();
+        assert_eq!(
+            server.recv().unwrap(),
+            IpcMessage::SetColor { color: 0xff0000 }
+        );
+
+        // Invalid messages are reported and don't close the connection
+        client
+            .websocket()
+            .clone()
+            .send(Message::Text("{\"type\":\"unknown\"}".to_string()))
+            .unwrap();
+        assert!(matches!(server.recv(), Err(TypedError::Json(_))));
+        client.send(&IpcMessage::GetState).unwrap();
+        assert_eq!(server.recv().unwrap(), IpcMessage::GetState);
+
+        client.into_inner().close(1000, "").unwrap();
+        assert!(matches!(
+            server.recv(),
+            Err(TypedError::Closed(Some(1000), None))
+        ));
+    }
+}
@@ -0,0 +1,329 @@
+/*
+ * Copyright (c) 2025 Bastiaan van der Plaat
+ *
+ * SPDX-License-Identifier: MIT
+ */
+
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use std::sync::Arc;
+
+use sha1::{Digest, Sha1};
+use small_http::{Method, Request, Response, Status};
+
+#[cfg(feature = "deflate")]
+use crate::DeflateConfig;
+use crate::WebSocket;
+#[cfg(feature = "deflate")]
+use crate::deflate;
+
+/// GUID that is appended to the key to compute the accept key
+const WEBSOCKET_GUID: &[u8] = b"258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Compute `Sec-WebSocket-Accept` value for a `Sec-WebSocket-Key`
+pub(crate) fn accept_key(key: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(key.as_bytes());
+    hasher.update(WEBSOCKET_GUID);
+    BASE64_STANDARD.encode(hasher.finalize())
+}
+
+/// Split comma separated header value
+pub(crate) fn split_header(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+}
+
+// MARK: UpgradeBuilder
+type ValidatorFn = Arc<dyn Fn(&Request) -> Result<(), Response> + Send + Sync>;
+
+/// Builder to customise the WebSocket upgrade handshake
+#[derive(Clone, Default)]
+pub struct UpgradeBuilder {
+    allowed_origins: Option<Vec<String>>,
+    protocols: Vec<String>,
+    headers: Vec<(String, String)>,
+    validator: Option<ValidatorFn>,
+    #[cfg(feature = "deflate")]
+    deflate: DeflateConfig,
+    #[cfg(feature = "deflate")]
+    no_deflate: bool,
+}
+
+impl UpgradeBuilder {
+    /// Create new upgrade builder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow origin, when origins are allowed requests from other origins are rejected
+    pub fn allow_origin(mut self, origin: impl Into<String>) -> Self {
+        self.allowed_origins
+            .get_or_insert_with(Vec::new)
+            .push(origin.into());
+        self
+    }
+
+    /// Add supported subprotocol, the first protocol offered by the client that is supported is selected
+    pub fn protocol(mut self, protocol: impl Into<String>) -> Self {
+        self.protocols.push(protocol.into());
+        self
+    }
+
+    /// Add response header
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Set validator that can reject the upgrade by returning a response
+    pub fn validate(
+        mut self,
+        validator: impl Fn(&Request) -> Result<(), Response> + Send + Sync + 'static,
+    ) -> Self {
+        self.validator = Some(Arc::new(validator));
+        self
+    }
+
+    /// Set permessage-deflate config
+    #[cfg(feature = "deflate")]
+    pub fn deflate(mut self, config: DeflateConfig) -> Self {
+        self.deflate = config;
+        self
+    }
+
+    /// Disable permessage-deflate compression
+    #[cfg(feature = "deflate")]
+    pub fn no_deflate(mut self) -> Self {
+        self.no_deflate = true;
+        self
+    }
+
+    /// Upgrade HTTP request to WebSocket connection, or return a rejection response
+    pub fn upgrade(
+        &self,
+        request: &Request,
+        handler: impl FnOnce(WebSocket) + Send + 'static,
+    ) -> Response {
+        // Validate handshake
+        if request.method != Method::Get {
+            return Response::with_status(Status::MethodNotAllowed).header("Allow", "GET");
+        }
+        if !request.headers.get("Upgrade").is_some_and(|upgrade| {
+            split_header(upgrade).any(|protocol| protocol.eq_ignore_ascii_case("websocket"))
+        }) {
+            return Response::with_status(Status::BadRequest)
+                .body("Missing Upgrade: websocket header");
+        }
+        if !request.headers.get("Connection").is_some_and(|connection| {
+            split_header(connection).any(|option| option.eq_ignore_ascii_case("upgrade"))
+        }) {
+            return Response::with_status(Status::BadRequest)
+                .body("Missing Connection: Upgrade header");
+        }
+        let key = match request.headers.get("Sec-WebSocket-Key") {
+            Some(key) => key,
+            None => {
+                return Response::with_status(Status::BadRequest)
+                    .body("Missing Sec-WebSocket-Key header");
+            }
+        };
+        if request
+            .headers
+            .get("Sec-WebSocket-Version")
+            .is_none_or(|version| version != "13")
+        {
+            return Response::with_status(Status::UpgradeRequired)
+                .header("Sec-WebSocket-Version", "13");
+        }
+        if let Some(allowed_origins) = &self.allowed_origins {
+            if !request
+                .headers
+                .get("Origin")
+                .is_some_and(|origin| allowed_origins.iter().any(|allowed| allowed == origin))
+            {
+                return Response::with_status(Status::Forbidden).body("Origin not allowed");
+            }
+        }
+        if let Some(validator) = &self.validator {
+            if let Err(res) = validator(request) {
+                return res;
+            }
+        }
+
+        let mut res = Response::with_status(Status::SwitchingProtocols)
+            .header("Upgrade", "websocket")
+            .header("Connection", "Upgrade")
+            .header("Sec-WebSocket-Accept", accept_key(key));
+
+        // Select subprotocol
+        let protocol = request
+            .headers
+            .get("Sec-WebSocket-Protocol")
+            .and_then(|offered| {
+                split_header(offered).find(|offered| self.protocols.iter().any(|p| p == offered))
+            })
+            .map(str::to_string);
+        if let Some(protocol) = &protocol {
+            res = res.header("Sec-WebSocket-Protocol", protocol);
+        }
+
+        // Negotiate extensions
+        #[cfg(feature = "deflate")]
+        let negotiated = match request.headers.get("Sec-WebSocket-Extensions") {
+            Some(offer) if !self.no_deflate => deflate::accept_offer(offer, &self.deflate),
+            _ => None,
+        };
+        #[cfg(feature = "deflate")]
+        if let Some(negotiated) = &negotiated {
+            res = res.header("Sec-WebSocket-Extensions", negotiated.response_header());
+        }
+
+        for (name, value) in &self.headers {
+            res = res.header(name, value);
+        }
+        res.takeover(move |stream| {
+            let mut ws = WebSocket::new(stream, false);
+            ws.protocol = protocol;
+            #[cfg(feature = "deflate")]
+            if let Some(negotiated) = negotiated {
+                ws.set_deflate(negotiated, false);
+            }
+            handler(ws)
+        })
+    }
+}
+
+// MARK: Tests
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn upgrade_request() -> Request {
+        Request::get("http://localhost/ws")
+            .header("Upgrade", "websocket")
+            .header("Connection", "Upgrade")
+            .header("Sec-WebSocket-Version", "13")
+            .header("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ==")
+    }
+
+    #[test]
+    fn test_accept_key() {
+        // Example from RFC 6455 section 1.3
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn test_invalid_handshake() {
+        let res = UpgradeBuilder::new().upgrade(&Request::get("http://localhost/ws"), |_| {});
+        assert_eq!(res.status, Status::BadRequest);
+
+        let res = UpgradeBuilder::new().upgrade(
+            &Request::get("http://localhost/ws")
+                .header("Upgrade", "h2c")
+                .header("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="),
+            |_| {},
+        );
+        assert_eq!(res.status, Status::BadRequest);
+
+        let res = UpgradeBuilder::new().upgrade(&upgrade_request().method(Method::Post), |_| {});
+        assert_eq!(res.status, Status::MethodNotAllowed);
+
+        let res = UpgradeBuilder::new().upgrade(
+            &Request::get("http://localhost/ws")
+                .header("Upgrade", "websocket")
+                .header("Sec-WebSocket-Version", "13")
+                .header("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="),
+            |_| {},
+        );
+        assert_eq!(res.status, Status::BadRequest);
+
+        for version in [Some("8"), None] {
+            let mut req = Request::get("http://localhost/ws")
+                .header("Upgrade", "WebSocket")
+                .header("Connection", "keep-alive, Upgrade")
+                .header("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ==");
+            if let Some(version) = version {
+                req = req.header("Sec-WebSocket-Version", version);
+            }
+            let res = UpgradeBuilder::new().upgrade(&req, |_| {});
+            assert_eq!(res.status, Status::UpgradeRequired);
+            assert_eq!(
+                res.headers.get("Sec-WebSocket-Version").map(|s| s.as_str()),
+                Some("13")
+            );
+        }
+
+        let res = UpgradeBuilder::new().upgrade(&upgrade_request(), |_| {});
+        assert_eq!(res.status, Status::SwitchingProtocols);
+    }
+
+    #[test]
+    fn test_origin_allow_list() {
+        let builder = UpgradeBuilder::new().allow_origin("https://example.com");
+        let res = builder.upgrade(&upgrade_request(), |_| {});
+        assert_eq!(res.status, Status::Forbidden);
+
+        let res = builder.upgrade(
+            &upgrade_request().header("Origin", "https://evil.com"),
+            |_| {},
+        );
+        assert_eq!(res.status, Status::Forbidden);
+
+        let res = builder.upgrade(
+            &upgrade_request().header("Origin", "https://example.com"),
+            |_| {},
+        );
+        assert_eq!(res.status, Status::SwitchingProtocols);
+    }
+
+    #[test]
+    fn test_protocol_selection() {
+        let builder = UpgradeBuilder::new().protocol("json").protocol("msgpack");
+        let res = builder.upgrade(
+            &upgrade_request().header("Sec-WebSocket-Protocol", "cbor, msgpack, json"),
+            |_| {},
+        );
+        assert_eq!(
+            res.headers
+                .get("Sec-WebSocket-Protocol")
+                .map(|s| s.as_str()),
+            Some("msgpack")
+        );
+
+        let res = builder.upgrade(
+            &upgrade_request().header("Sec-WebSocket-Protocol", "cbor"),
+            |_| {},
+        );
+        assert_eq!(res.status, Status::SwitchingProtocols);
+        assert!(res.headers.get("Sec-WebSocket-Protocol").is_none());
+    }
+
+    #[test]
+    fn test_validate_and_headers() {
+        let secret = "Bearer secret".to_string();
+        let builder = UpgradeBuilder::new()
+            .header("X-Server", "small-websocket")
+            .validate(move |req| match req.headers.get("Authorization") {
+                Some(token) if *token == secret => Ok(()),
+                _ => Err(Response::with_status(Status::Unauthorized)),
+            });
+        let res = builder.upgrade(&upgrade_request(), |_| {});
+        assert_eq!(res.status, Status::Unauthorized);
+
+        let res = builder.upgrade(
+            &upgrade_request().header("Authorization", "Bearer secret"),
+            |_| {},
+        );
+        assert_eq!(res.status, Status::SwitchingProtocols);
+        assert_eq!(
+            res.headers.get("X-Server").map(|s| s.as_str()),
+            Some("small-websocket")
+        );
+    }
+}
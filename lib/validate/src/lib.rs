@@ -72,6 +72,27 @@ impl Report {
     }
 }
 
+#[cfg(feature = "small-router")]
+impl From<Report> for small_router::Error {
+    fn from(report: Report) -> Self {
+        small_router::Problem::new(small_http::Status::BadRequest)
+            .detail("Validation failed")
+            .extension("errors", report)
+            .into()
+    }
+}
+
+#[cfg(feature = "small-router")]
+impl small_router::IntoResponse for Report {
+    fn into_response(self) -> small_http::Response {
+        small_router::Error::from(self).into_response()
+    }
+
+    fn into_result(self) -> std::result::Result<small_http::Response, small_router::Error> {
+        Err(self.into())
+    }
+}
+
 // MARK: Validate
 /// Validate trait
 pub trait Validate {
@@ -117,7 +138,7 @@ pub fn is_valid_url(url: &str) -> bool {
 // MARK: Tests
 #[cfg(test)]
 mod test {
-    #[cfg(any(feature = "email", feature = "url"))]
+    #[cfg(any(feature = "email", feature = "url", feature = "small-router"))]
     use super::*;
 
     #[test]
@@ -156,4 +177,31 @@ mod test {
         assert!(!is_valid_url("example.com"));
         assert!(!is_valid_url("http://"));
     }
+
+    #[test]
+    #[cfg(feature = "small-router")]
+    fn test_small_router_problem() {
+        use small_router::IntoResponse;
+
+        let mut report = Report::new();
+        report.insert_error("name", "Name is too short");
+
+        let err = small_router::Error::from(Report(report.0.clone()));
+        assert_eq!(err.status(), small_http::Status::BadRequest);
+
+        let res = report.into_response();
+        assert_eq!(res.status, small_http::Status::BadRequest);
+        assert_eq!(
+            res.headers.get("Content-Type").map(|s| s.as_str()),
+            Some("application/problem+json")
+        );
+        let problem = serde_json::from_slice::<serde_json::Value>(&res.body).unwrap();
+        assert_eq!(problem["status"], 400);
+        assert_eq!(problem["title"], "Bad Request");
+        assert_eq!(problem["detail"], "Validation failed");
+        assert_eq!(
+            problem["errors"],
+            serde_json::json!({ "name": ["Name is too short"] })
+        );
+    }
 }